@@ -0,0 +1,122 @@
+use crate::outline::{self, Kind, Token};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How `canonicalize` writes address literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// Without leading zeros, e.g. `0x2`.
+    Short,
+    /// Padded to 64 digits, e.g. `0x000…0002`.
+    Long,
+}
+
+impl Notation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "short" => Some(Self::Short),
+            "long" => Some(Self::Long),
+            _ => None,
+        }
+    }
+
+    /// Writes a full 64-digit address in this notation.
+    pub fn render(self, address: &str) -> String {
+        match self {
+            Self::Long => address.to_string(),
+            Self::Short => {
+                let digits = address.trim_start_matches("0x").trim_start_matches('0');
+                format!("0x{}", if digits.is_empty() { "0" } else { digits })
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AddressUse {
+    pub line: usize,
+    pub column: usize,
+    /// The address as written: a hex literal or a named address.
+    pub text: String,
+    /// Full 64-digit form of the address; for a named address, of its bound value, or `None`
+    /// when it is unassigned.
+    pub address: Option<String>,
+    pub named: bool,
+    #[serde(skip)]
+    start: usize,
+    #[serde(skip)]
+    end: usize,
+}
+
+/// Finds the addresses written in a Move source file: hex literals and `bindings` names used
+/// as `@addr`, as the address of a path (`addr::module`), or in an `address addr {` block.
+/// Hex literals elsewhere are numbers and are left out, as are comments and strings.
+/// `bindings` maps named addresses to their full 64-digit values.
+pub fn find_uses(source: &str, bindings: &HashMap<String, Option<String>>) -> Vec<AddressUse> {
+    let (tokens, _) = outline::tokenize(source);
+    let punct = |index: Option<usize>, c: char| {
+        index
+            .and_then(|index| tokens.get(index))
+            .is_some_and(|token: &Token| matches!(token.kind, Kind::Punct(p) if p == c))
+    };
+
+    let mut uses = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        let Kind::Word(word) = &token.kind else {
+            continue;
+        };
+        let previous = index.checked_sub(1);
+        let after_at = punct(previous, '@') && tokens[index - 1].end == token.start;
+        let after_path_separator = punct(previous, ':');
+        let before_path = punct(Some(index + 1), ':') && punct(Some(index + 2), ':');
+        let in_address_block = previous.is_some_and(
+            |previous| matches!(&tokens[previous].kind, Kind::Word(word) if word == "address"),
+        );
+        if !(after_at || in_address_block || (before_path && !after_path_separator)) {
+            continue;
+        }
+
+        let (address, named) = if word.starts_with("0x") {
+            match crate::type_layout::normalize_address(word) {
+                Some(address) => (Some(address), false),
+                None => continue,
+            }
+        } else {
+            match bindings.get(word) {
+                Some(address) => (address.clone(), true),
+                None => continue,
+            }
+        };
+        uses.push(AddressUse {
+            line: token.line,
+            column: token.column,
+            text: word.clone(),
+            address,
+            named,
+            start: token.start,
+            end: token.end,
+        });
+    }
+    uses
+}
+
+/// Rewrites the hex address literals among `uses` in `notation`, leaving named addresses and
+/// everything else untouched.
+pub fn canonicalize(source: &str, uses: &[AddressUse], notation: Notation) -> String {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut copied = 0;
+    for address_use in uses {
+        let Some(address) = address_use
+            .address
+            .as_deref()
+            .filter(|_| !address_use.named)
+        else {
+            continue;
+        };
+        rewritten.push_str(&source[copied..address_use.start]);
+        rewritten.push_str(&notation.render(address));
+        copied = address_use.end;
+    }
+    rewritten.push_str(&source[copied..]);
+    rewritten
+}
@@ -0,0 +1,256 @@
+use anyhow::Context;
+use std::{path::Path, process::Stdio, time::Duration};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio_util::sync::CancellationToken;
+
+const HOVER_TIMEOUT: Duration = Duration::from_secs(60);
+const HOVER_ATTEMPTS: u64 = 5;
+
+/// Why a hover query produced no answer.
+#[derive(Debug)]
+pub enum HoverError {
+    /// `move-analyzer` is not installed or not on `PATH`.
+    NotInstalled,
+    Cancelled,
+    Failed(anyhow::Error),
+}
+
+/// Asks `move-analyzer` for the hover information (type/signature) at a 1-based
+/// `line`/`column` of `file`, by running a short-lived language server session.
+pub async fn hover(
+    project_folder: &Path,
+    file: &Path,
+    line: u32,
+    column: u32,
+    ct: &CancellationToken,
+) -> Result<Option<String>, HoverError> {
+    let mut child = tokio::process::Command::new("move-analyzer")
+        .current_dir(project_folder)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => HoverError::NotInstalled,
+            _ => HoverError::Failed(e.into()),
+        })?;
+
+    let stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut session = Session {
+        stdin,
+        stdout: BufReader::new(stdout),
+        next_id: 0,
+    };
+
+    let result = tokio::select! {
+        result = tokio::time::timeout(
+            HOVER_TIMEOUT,
+            session.hover(project_folder, file, line, column),
+        ) => result
+            .context("move-analyzer did not respond in time")
+            .and_then(|result| result)
+            .map_err(HoverError::Failed),
+        _ = ct.cancelled() => Err(HoverError::Cancelled),
+    };
+
+    let _ = child.kill().await;
+    result
+}
+
+struct Session {
+    stdin: tokio::process::ChildStdin,
+    stdout: BufReader<tokio::process::ChildStdout>,
+    next_id: u64,
+}
+
+impl Session {
+    async fn hover(
+        &mut self,
+        project_folder: &Path,
+        file: &Path,
+        line: u32,
+        column: u32,
+    ) -> anyhow::Result<Option<String>> {
+        let uri = file_uri(file);
+        let text = tokio::fs::read_to_string(file)
+            .await
+            .with_context(|| format!("Failed to read `{}`", file.display()))?;
+
+        self.request(
+            "initialize",
+            serde_json::json!({
+                "processId": std::process::id(),
+                "rootUri": file_uri(project_folder),
+                "capabilities": {},
+            }),
+        )
+        .await?;
+        self.notify("initialized", serde_json::json!({})).await?;
+        self.notify(
+            "textDocument/didOpen",
+            serde_json::json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "move",
+                    "version": 1,
+                    "text": text,
+                }
+            }),
+        )
+        .await?;
+
+        // The analyzer compiles the package in the background after opening a file, so early
+        // hovers can come back empty.
+        let mut contents = None;
+        for attempt in 0..HOVER_ATTEMPTS {
+            let result = self
+                .request(
+                    "textDocument/hover",
+                    serde_json::json!({
+                        "textDocument": { "uri": uri },
+                        "position": {
+                            "line": line.saturating_sub(1),
+                            "character": column.saturating_sub(1),
+                        },
+                    }),
+                )
+                .await?;
+            contents = hover_text(&result["contents"]);
+            if contents.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500 * (attempt + 1))).await;
+        }
+
+        let _ = self.request("shutdown", serde_json::Value::Null).await;
+        let _ = self.notify("exit", serde_json::Value::Null).await;
+        Ok(contents)
+    }
+
+    async fn request(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))
+        .await?;
+
+        loop {
+            let message = self.receive().await?;
+            if message["id"] != id {
+                // Notifications (e.g. diagnostics) and server requests are ignored.
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                anyhow::bail!("move-analyzer `{}` failed: {}", method, error);
+            }
+            return Ok(message["result"].clone());
+        }
+    }
+
+    async fn notify(&mut self, method: &str, params: serde_json::Value) -> anyhow::Result<()> {
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+        .await
+    }
+
+    async fn send(&mut self, message: serde_json::Value) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(&message)?;
+        self.stdin
+            .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+            .await?;
+        self.stdin.write_all(&body).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> anyhow::Result<serde_json::Value> {
+        let mut content_length = None;
+        loop {
+            let mut header = String::new();
+            if self.stdout.read_line(&mut header).await? == 0 {
+                anyhow::bail!("move-analyzer exited unexpectedly");
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(length) = header.strip_prefix("Content-Length:") {
+                content_length = Some(length.trim().parse::<usize>()?);
+            }
+        }
+        let length = content_length.context("move-analyzer sent a message without a length")?;
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// Flattens the `contents` of an LSP hover result, which may be a `MarkupContent`, a
+/// `MarkedString` or an array of them.
+fn hover_text(contents: &serde_json::Value) -> Option<String> {
+    let text = match contents {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(hover_text)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        serde_json::Value::Object(object) => object.get("value")?.as_str()?.to_string(),
+        _ => return None,
+    };
+    Some(text).filter(|text| !text.trim().is_empty())
+}
+
+/// Builds a `file://` URI for `path`, made absolute (and canonical when it exists), with
+/// every byte other than unreserved characters and `/` percent-encoded.
+pub(crate) fn file_uri(path: &Path) -> String {
+    let absolute = std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let text = absolute.to_string_lossy().replace('\\', "/");
+    // Canonical Windows paths carry a verbatim `\\?\` prefix.
+    let text = text.strip_prefix("//?/").unwrap_or(&text);
+    let mut uri = String::from("file://");
+    if !text.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_uri_is_absolute_and_percent_encoded() {
+        let uri = file_uri(Path::new("/tmp/my package/sources/café#1.move"));
+        assert_eq!(uri, "file:///tmp/my%20package/sources/caf%C3%A9%231.move");
+    }
+
+    #[test]
+    fn file_uri_resolves_relative_paths() {
+        let uri = file_uri(Path::new("sources/a.move"));
+        assert!(uri.starts_with("file:///"));
+        assert!(uri.ends_with("/sources/a.move"));
+    }
+}
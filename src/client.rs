@@ -0,0 +1,95 @@
+use crate::command;
+use anyhow::Context;
+use tokio_util::sync::CancellationToken;
+
+/// Runs `sui client <args> --json` in `project_folder` and parses its output, turning a
+/// non-JSON response into an error carrying the CLI's stderr.
+pub async fn json<I, S>(
+    project_folder: &str,
+    args: I,
+    ct: &CancellationToken,
+) -> anyhow::Result<serde_json::Value>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = command::output(
+        tokio::process::Command::new("sui")
+            .arg("client")
+            .args(args)
+            .arg("--json")
+            .current_dir(project_folder),
+        ct,
+    )
+    .await
+    .context("Failed to run `sui client`")?;
+
+    serde_json::from_slice(&output.stdout).map_err(|_| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::anyhow!("{}", strip_ansi_escapes::strip_str(stderr.trim()))
+    })
+}
+
+/// Runs a plain-text `sui client <args>` command, returning its trimmed stdout.
+pub async fn text<I, S>(
+    project_folder: &str,
+    args: I,
+    ct: &CancellationToken,
+) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = command::output(
+        tokio::process::Command::new("sui")
+            .arg("client")
+            .args(args)
+            .current_dir(project_folder),
+        ct,
+    )
+    .await
+    .context("Failed to run `sui client`")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", strip_ansi_escapes::strip_str(stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns the ID of the package created by a publish transaction response.
+pub fn published_package_id(response: &serde_json::Value) -> Option<String> {
+    response["objectChanges"]
+        .as_array()?
+        .iter()
+        .find(|change| change["type"] == "published")?["packageId"]
+        .as_str()
+        .map(|id| id.to_string())
+}
+
+/// Returns the transaction status, and the abort/failure message if it did not succeed.
+pub fn transaction_status(response: &serde_json::Value) -> (bool, Option<String>) {
+    let status = &response["effects"]["status"];
+    (
+        status["status"] == "success",
+        status["error"].as_str().map(|e| e.to_string()),
+    )
+}
+
+/// Returns the RPC URL of the CLI's active environment.
+pub async fn active_rpc_url(
+    project_folder: &str,
+    ct: &CancellationToken,
+) -> anyhow::Result<String> {
+    // `[[{ "alias": .., "rpc": .. }, ..], "<active alias>"]`
+    let envs = json(project_folder, ["envs"], ct).await?;
+    let active = envs[1].as_str().context("No active environment")?;
+    envs[0]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|env| env["alias"] == active)
+        .and_then(|env| env["rpc"].as_str())
+        .map(|rpc| rpc.to_string())
+        .with_context(|| format!("No RPC URL configured for environment `{}`", active))
+}
@@ -0,0 +1,387 @@
+use std::{
+    process::{ExitStatus, Output, Stdio},
+    sync::OnceLock,
+    time::Duration,
+};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+use tokio_util::sync::CancellationToken;
+
+static EXTRA_ENV: OnceLock<Vec<(String, String)>> = OnceLock::new();
+static MAX_OUTPUT: OnceLock<usize> = OnceLock::new();
+
+/// Captured stdout/stderr is cut off after this many bytes unless [`set_max_output`] is called.
+pub const DEFAULT_MAX_OUTPUT: usize = 16 * 1024 * 1024;
+
+/// Sets environment variables for every command spawned through [`run`]/[`output`] or
+/// [`with_env`], on top of the inherited environment. Only the first call takes effect.
+pub fn set_env(vars: Vec<(String, String)>) {
+    let _ = EXTRA_ENV.set(vars);
+}
+
+/// Caps how much of each output stream [`run`]/[`output`] keep. Only the first call takes
+/// effect.
+pub fn set_max_output(bytes: usize) {
+    let _ = MAX_OUTPUT.set(bytes);
+}
+
+/// Applies the variables from [`set_env`] to a command spawned outside of [`run`].
+pub fn with_env(cmd: &mut tokio::process::Command) -> &mut tokio::process::Command {
+    cmd.envs(EXTRA_ENV.get().into_iter().flatten().map(|(k, v)| (k, v)))
+}
+
+/// Parses a `KEY=VALUE` entry, rejecting keys that are not plain variable names.
+pub fn parse_env_var(entry: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = entry
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected `KEY=VALUE`, got `{}`", entry))?;
+    anyhow::ensure!(
+        !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        "Invalid environment variable name `{}`",
+        key
+    );
+    anyhow::ensure!(
+        !value.contains('\0'),
+        "Value of `{}` contains a NUL byte",
+        key
+    );
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// How a command run with [`run`] ended.
+pub enum Completion {
+    Finished(Output),
+    /// The timeout elapsed and the child was killed. Holds whatever the command had written
+    /// up to that point.
+    TimedOut {
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+}
+
+/// Runs a command to completion and collects its output, like
+/// [`std::process::Command::output`].
+///
+/// If `ct` is cancelled first (e.g. the client cancelled the request), the child process and
+/// any processes it started are killed, and the child reaped, before an [`std::io::ErrorKind::Interrupted`] error is returned.
+pub async fn output(
+    cmd: &mut tokio::process::Command,
+    ct: &CancellationToken,
+) -> std::io::Result<Output> {
+    match run(cmd, ct, None).await? {
+        Completion::Finished(output) => Ok(output),
+        Completion::TimedOut { .. } => unreachable!("no timeout was set"),
+    }
+}
+
+/// Like [`output`], but also kills the child once `timeout` elapses, returning the output
+/// captured so far instead of discarding it.
+pub async fn run(
+    cmd: &mut tokio::process::Command,
+    ct: &CancellationToken,
+    timeout: Option<Duration>,
+) -> std::io::Result<Completion> {
+    run_observed(cmd, ct, timeout, &|_| {}).await
+}
+
+/// Like [`run`], but also passes each line of stdout and stderr to `on_line` as it arrives,
+/// e.g. to report progress while the command runs.
+pub async fn run_observed(
+    cmd: &mut tokio::process::Command,
+    ct: &CancellationToken,
+    timeout: Option<Duration>,
+    on_line: &(dyn Fn(&str) + Sync),
+) -> std::io::Result<Completion> {
+    let mut child = spawn(cmd)?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let mut stdout_buf = vec![];
+    let mut stderr_buf = vec![];
+
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    enum Stop {
+        Exited(std::io::Result<ExitStatus>),
+        Cancelled,
+        TimedOut,
+    }
+
+    let stop = tokio::select! {
+        result = async {
+            tokio::try_join!(
+                child.wait(),
+                read_into(stdout, &mut stdout_buf, on_line),
+                read_into(stderr, &mut stderr_buf, on_line),
+            )
+            .map(|(status, _, _)| status)
+        } => Stop::Exited(result),
+        _ = ct.cancelled() => Stop::Cancelled,
+        _ = deadline => Stop::TimedOut,
+    };
+
+    match stop {
+        Stop::Exited(status) => Ok(Completion::Finished(Output {
+            status: status?,
+            stdout: stdout_buf,
+            stderr: stderr_buf,
+        })),
+        Stop::Cancelled => {
+            let pid = child.id();
+            kill(&mut child).await?;
+            tracing::info!(?pid, "killed cancelled command");
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled by client",
+            ))
+        }
+        Stop::TimedOut => {
+            let pid = child.id();
+            kill(&mut child).await?;
+            tracing::warn!(?pid, ?timeout, "killed timed out command");
+            Ok(Completion::TimedOut {
+                stdout: stdout_buf,
+                stderr: stderr_buf,
+            })
+        }
+    }
+}
+
+/// Spawns `cmd` with piped output. On unix it leads its own process group, so that [`kill`]
+/// also reaches the processes it starts, such as `sui` under `/usr/bin/time`.
+fn spawn(cmd: &mut tokio::process::Command) -> std::io::Result<tokio::process::Child> {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    with_env(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/// Kills a child from [`spawn`] together with its process group, then reaps it.
+async fn kill(child: &mut tokio::process::Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = std::process::Command::new("kill")
+            .arg("-KILL")
+            .arg("--")
+            .arg(format!("-{}", pid))
+            .stderr(Stdio::null())
+            .status();
+    }
+    child.kill().await
+}
+
+/// Reads to EOF in chunks, so everything read so far is kept in `buf` if the future is dropped.
+///
+/// Past the output cap the rest of the stream is drained and discarded, so the child never
+/// blocks on a full pipe. `buf` is cut back to the last complete line and ends with a marker.
+/// Every complete line, including discarded ones, is passed to `on_line`.
+async fn read_into<R: tokio::io::AsyncRead + Unpin>(
+    reader: Option<R>,
+    buf: &mut Vec<u8>,
+    on_line: &(dyn Fn(&str) + Sync),
+) -> std::io::Result<()> {
+    let Some(mut reader) = reader else {
+        return Ok(());
+    };
+    let max = *MAX_OUTPUT.get().unwrap_or(&DEFAULT_MAX_OUTPUT);
+    let mut truncated = false;
+    let mut chunk = [0; 8192];
+    // Start of the line not yet passed to `on_line`, cut short if it grows past a chunk.
+    let mut partial = vec![];
+    loop {
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            if !partial.is_empty() {
+                on_line(&String::from_utf8_lossy(&partial));
+            }
+            return Ok(());
+        }
+        for byte in &chunk[..n] {
+            if *byte == b'\n' {
+                on_line(String::from_utf8_lossy(&partial).trim_end_matches('\r'));
+                partial.clear();
+            } else if partial.len() < chunk.len() {
+                partial.push(*byte);
+            }
+        }
+        if truncated {
+            continue;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if buf.len() > max {
+            let end = buf[..max]
+                .iter()
+                .rposition(|b| *b == b'\n')
+                .map_or(0, |i| i + 1);
+            buf.truncate(end);
+            buf.extend_from_slice(
+                format!("[output truncated: exceeded {} bytes]\n", max).as_bytes(),
+            );
+            truncated = true;
+            tracing::warn!(max, "truncated command output");
+        }
+    }
+}
+
+/// Runs a command to completion like [`output`], recording when each line of stdout and
+/// stderr arrived, measured from the spawn and merged in arrival order.
+pub async fn timed_lines(
+    cmd: &mut tokio::process::Command,
+    ct: &CancellationToken,
+) -> std::io::Result<(ExitStatus, Vec<(Duration, String)>)> {
+    let started = std::time::Instant::now();
+    let mut child = spawn(cmd)?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let mut stdout_lines = vec![];
+    let mut stderr_lines = vec![];
+
+    let status = tokio::select! {
+        result = async {
+            tokio::try_join!(
+                child.wait(),
+                read_timed(stdout, started, &mut stdout_lines),
+                read_timed(stderr, started, &mut stderr_lines),
+            )
+            .map(|(status, _, _)| status)
+        } => result?,
+        _ = ct.cancelled() => {
+            kill(&mut child).await?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "cancelled by client",
+            ));
+        }
+    };
+
+    let mut lines = stdout_lines;
+    lines.extend(stderr_lines);
+    lines.sort_by_key(|(at, _)| *at);
+    Ok((status, lines))
+}
+
+/// Reads lines to EOF, stamping each with the time since `started`. Lines past the output
+/// cap are drained and dropped.
+async fn read_timed<R: tokio::io::AsyncRead + Unpin>(
+    reader: Option<R>,
+    started: std::time::Instant,
+    lines: &mut Vec<(Duration, String)>,
+) -> std::io::Result<()> {
+    let Some(reader) = reader else {
+        return Ok(());
+    };
+    let max = *MAX_OUTPUT.get().unwrap_or(&DEFAULT_MAX_OUTPUT);
+    let mut kept = 0;
+    let mut reader = tokio::io::BufReader::new(reader).lines();
+    while let Some(line) = reader.next_line().await? {
+        kept += line.len() + 1;
+        if kept <= max {
+            lines.push((started.elapsed(), line));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelling_kills_the_child() {
+        let pid_file =
+            std::env::temp_dir().join(format!("command-cancel-{}", uuid::Uuid::new_v4()));
+        let ct = CancellationToken::new();
+        // Cancels once the shell has written its PID, which `exec` hands on to `sleep`.
+        let canceller = {
+            let ct = ct.clone();
+            let pid_file = pid_file.clone();
+            async move {
+                while !std::fs::read_to_string(&pid_file).is_ok_and(|pid| pid.ends_with('\n')) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                ct.cancel();
+            }
+        };
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("echo $$ > '{}'; exec sleep 30", pid_file.display()));
+
+        let (result, ()) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(output(&mut cmd, &ct), canceller)
+        })
+        .await
+        .expect("the command was not killed");
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        let alive = std::process::Command::new("kill")
+            .arg("-0")
+            .arg(pid.trim())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap()
+            .success();
+        assert!(!alive);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelling_kills_the_process_group() {
+        let pid_file = std::env::temp_dir().join(format!("command-group-{}", uuid::Uuid::new_v4()));
+        let ct = CancellationToken::new();
+        // Cancels once the shell has written the PID of the `sleep` it started in the background.
+        let canceller = {
+            let ct = ct.clone();
+            let pid_file = pid_file.clone();
+            async move {
+                while !std::fs::read_to_string(&pid_file).is_ok_and(|pid| pid.ends_with('\n')) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+                ct.cancel();
+            }
+        };
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(format!(
+            "sleep 30 & echo $! > '{}'; wait",
+            pid_file.display()
+        ));
+
+        let (result, ()) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(output(&mut cmd, &ct), canceller)
+        })
+        .await
+        .expect("the command was not killed");
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        // The orphaned `sleep` is reaped by init, so it may briefly linger as a zombie.
+        let mut alive = true;
+        for _ in 0..100 {
+            let state = std::process::Command::new("ps")
+                .args(["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .unwrap();
+            let state = String::from_utf8_lossy(&state.stdout);
+            alive = !state.trim().is_empty() && !state.trim().starts_with('Z');
+            if !alive {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!alive);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::interface::ModuleInterface;
+use serde::Serialize;
+
+/// Variants of the Move compatibility checker's error enum, as printed by older CLIs that
+/// report a rejected upgrade as a debug-formatted list rather than diagnostics.
+const LEGACY_KINDS: &[&str] = &[
+    "StructMissing",
+    "StructAbilityMismatch",
+    "StructTypeParamMismatch",
+    "StructFieldMismatch",
+    "EnumMissing",
+    "EnumAbilityMismatch",
+    "EnumTypeParamMismatch",
+    "EnumNewVariant",
+    "EnumVariantMissing",
+    "EnumVariantMismatch",
+    "FunctionMissingPublic",
+    "FunctionMissingEntry",
+    "FunctionSignatureMismatch",
+    "FunctionLostPublicVisibility",
+    "FunctionEntryCompatibility",
+    "FriendNewFunction",
+    "ModuleMissing",
+    "IncompatibleUpgrade",
+];
+
+#[derive(Serialize, Debug)]
+pub struct CompatibilityError {
+    /// Diagnostic code (e.g. `Compatibility E01002`) or the checker's error variant.
+    pub code: String,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// The offending declaration: the source line the diagnostic points at, or the identifier
+    /// named in a legacy error.
+    pub declaration: Option<String>,
+    pub notes: Vec<String>,
+}
+
+/// Extracts upgrade compatibility errors from `sui client upgrade` output, ignoring ordinary
+/// compiler diagnostics.
+pub fn parse_errors(output: &str) -> Vec<CompatibilityError> {
+    let stripped = strip_ansi_escapes::strip_str(output);
+    let mut errors = vec![];
+    let mut lines = stripped.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(header) = line.strip_prefix("error[Compatibility") {
+            let Some((code, message)) = header.split_once("]:") else {
+                continue;
+            };
+            let mut error = CompatibilityError {
+                code: format!("Compatibility{}", code),
+                message: message.trim().to_string(),
+                file: None,
+                line: None,
+                declaration: None,
+                notes: vec![],
+            };
+            while let Some(next) = lines.next_if(|next| !next.trim().is_empty()) {
+                let trimmed = next.trim();
+                if let Some(location) = trimmed.strip_prefix("┌─") {
+                    let mut parts = location.trim().rsplitn(3, ':');
+                    let _column = parts.next();
+                    error.line = parts.next().and_then(|line| line.parse().ok());
+                    error.file = parts.next().map(|file| file.to_string());
+                } else if let Some(note) = trimmed.strip_prefix('=') {
+                    error.notes.push(note.trim().to_string());
+                } else if error.declaration.is_none() && error.line.is_some() {
+                    let snippet = next
+                        .split_once('│')
+                        .filter(|(gutter, _)| gutter.trim().parse::<u32>().ok() == error.line);
+                    if let Some((_, code)) = snippet {
+                        error.declaration = Some(code.trim().to_string());
+                    }
+                }
+            }
+            errors.push(error);
+        } else if let Some(kind) = LEGACY_KINDS.iter().find(|kind| line.contains(*kind)) {
+            errors.push(CompatibilityError {
+                code: kind.to_string(),
+                message: line.trim().to_string(),
+                file: None,
+                line: None,
+                declaration: identifier(line),
+                notes: vec![],
+            });
+        }
+    }
+
+    errors
+}
+
+/// Pulls the name out of a debug-formatted `Identifier("name")`.
+fn identifier(line: &str) -> Option<String> {
+    let rest = line.split_once("Identifier(\"")?.1;
+    rest.split_once('"').map(|(name, _)| name.to_string())
+}
+
+/// Compares the interfaces of two versions of a package under Sui's default (`compatible`)
+/// upgrade policy, reporting the same error kinds as the upgrade checker: modules and
+/// structs may not be removed, struct abilities, type parameters and fields may not change,
+/// and public functions may not be removed, made non-public or change signature. Additions,
+/// and changes to non-public functions, are allowed.
+pub fn check_interfaces(
+    old: &[ModuleInterface],
+    new: &[ModuleInterface],
+) -> Vec<CompatibilityError> {
+    let mut errors = vec![];
+    for old_module in old {
+        let Some(new_module) = new.iter().find(|module| module.name == old_module.name) else {
+            errors.push(mismatch(
+                "ModuleMissing",
+                format!("Module `{}` was removed", old_module.name),
+                &old_module.name,
+            ));
+            continue;
+        };
+
+        for old_struct in &old_module.structs {
+            let (name, old_params) = split_type_params(&old_struct.name);
+            let qualified = format!("{}::{}", old_module.name, name);
+            let Some(new_struct) = new_module
+                .structs
+                .iter()
+                .find(|item| split_type_params(&item.name).0 == name)
+            else {
+                errors.push(mismatch(
+                    "StructMissing",
+                    format!("Struct `{}` was removed", qualified),
+                    &qualified,
+                ));
+                continue;
+            };
+            if new_struct.abilities != old_struct.abilities {
+                errors.push(mismatch(
+                    "StructAbilityMismatch",
+                    format!(
+                        "Abilities of `{}` changed from `{}` to `{}`",
+                        qualified,
+                        old_struct.abilities.join(", "),
+                        new_struct.abilities.join(", ")
+                    ),
+                    &qualified,
+                ));
+            }
+            let new_params = split_type_params(&new_struct.name).1;
+            if normalize(new_params) != normalize(old_params) {
+                errors.push(mismatch(
+                    "StructTypeParamMismatch",
+                    format!(
+                        "Type parameters of `{}` changed from `{}` to `{}`",
+                        qualified, old_params, new_params
+                    ),
+                    &qualified,
+                ));
+            }
+            let fields =
+                |fields: &[String]| fields.iter().map(|f| normalize(f)).collect::<Vec<_>>();
+            if fields(&new_struct.fields) != fields(&old_struct.fields) {
+                errors.push(mismatch(
+                    "StructFieldMismatch",
+                    format!(
+                        "Fields of `{}` changed from `{{ {} }}` to `{{ {} }}`",
+                        qualified,
+                        old_struct.fields.join(", "),
+                        new_struct.fields.join(", ")
+                    ),
+                    &qualified,
+                ));
+            }
+        }
+
+        for old_function in old_module
+            .functions
+            .iter()
+            .filter(|function| function.visibility == "public")
+        {
+            let qualified = format!("{}::{}", old_module.name, old_function.name);
+            let Some(new_function) = new_module
+                .functions
+                .iter()
+                .find(|function| function.name == old_function.name)
+            else {
+                errors.push(mismatch(
+                    "FunctionMissingPublic",
+                    format!("Public function `{}` was removed", qualified),
+                    &qualified,
+                ));
+                continue;
+            };
+            if new_function.visibility != "public" {
+                errors.push(mismatch(
+                    "FunctionLostPublicVisibility",
+                    format!(
+                        "`{}` changed from `public` to `{}`",
+                        qualified, new_function.visibility
+                    ),
+                    &qualified,
+                ));
+            } else if normalize(&new_function.signature) != normalize(&old_function.signature) {
+                errors.push(mismatch(
+                    "FunctionSignatureMismatch",
+                    format!(
+                        "Signature of `{}` changed from `{}` to `{}`",
+                        qualified, old_function.signature, new_function.signature
+                    ),
+                    &qualified,
+                ));
+            }
+        }
+    }
+    errors
+}
+
+fn mismatch(code: &str, message: String, declaration: &str) -> CompatibilityError {
+    CompatibilityError {
+        code: code.to_string(),
+        message,
+        file: None,
+        line: None,
+        declaration: Some(declaration.to_string()),
+        notes: vec![],
+    }
+}
+
+/// Splits a disassembled struct name like `Pool<phantom T0>` into `Pool` and `<phantom T0>`.
+fn split_type_params(name: &str) -> (&str, &str) {
+    match name.find('<') {
+        Some(index) => (name[..index].trim(), &name[index..]),
+        None => (name.trim(), ""),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
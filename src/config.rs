@@ -0,0 +1,75 @@
+/// Settings shared by every session, read from the environment at startup.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub project_folder: String,
+    pub movefmt_cmd: String,
+    /// Passed to the formatter as `--config-path` when set.
+    pub movefmt_config: Option<String>,
+    /// Named formatter command lines a `format_project` call may choose instead of
+    /// `movefmt_cmd`. Clients only pick a name; the commands come from the operator.
+    pub formatters: Vec<(String, String)>,
+    /// Directories, relative to the project, that `format_project` formats. `None` formats
+    /// the package's standard layout directories that exist.
+    pub format_dirs: Option<Vec<String>>,
+    /// Build and test commands are killed after this long, returning partial diagnostics.
+    pub command_timeout: Option<std::time::Duration>,
+    /// Endpoint for read-only RPC queries, overriding the CLI's active environment.
+    pub rpc_url: Option<String>,
+    /// Build with `--json-errors`, taking diagnostic codes and locations from the JSON output
+    /// and falling back to the text parser when it is missing.
+    pub json_errors: bool,
+    /// Glob patterns over `address::module::function` test names whose failures
+    /// `validate_project` reports separately instead of failing on.
+    pub validate_exclude: Vec<glob::Pattern>,
+    /// How many times publishing, faucet requests and dependency fetches are retried after a
+    /// transient network failure.
+    pub network_retries: u32,
+    /// Offer `build_memory`, which runs builds under `/usr/bin/time` to report their peak
+    /// memory.
+    pub measure_build_memory: bool,
+    /// Run in the project folder after every successful build.
+    pub post_build_hook: Option<Hook>,
+    /// Hide and reject tools that modify files or chain state.
+    pub read_only: bool,
+    /// When set, only these tools are offered; all others are hidden and rejected.
+    pub enabled_tools: Option<Vec<String>>,
+    /// Tools that are hidden and rejected, applied after `enabled_tools`.
+    pub disabled_tools: Vec<String>,
+    /// Canonical directories that tools may read from or write to; every user-supplied or
+    /// deleted path must resolve inside one of them.
+    pub allowed_dirs: Vec<std::path::PathBuf>,
+}
+
+/// An operator-supplied command, run directly rather than through a shell.
+#[derive(Debug, Clone)]
+pub struct Hook {
+    /// Canonical path of the executable, inside one of the allowed directories.
+    pub program: std::path::PathBuf,
+    pub args: Vec<String>,
+}
+
+#[cfg(test)]
+impl Config {
+    /// Defaults for unit tests, with `project_folder` as the only allowed directory.
+    pub fn for_project(project_folder: &std::path::Path) -> Self {
+        let project_folder = std::fs::canonicalize(project_folder).unwrap();
+        Self {
+            project_folder: project_folder.to_string_lossy().to_string(),
+            movefmt_cmd: "movefmt".to_string(),
+            movefmt_config: None,
+            formatters: vec![],
+            format_dirs: None,
+            command_timeout: None,
+            rpc_url: None,
+            json_errors: false,
+            validate_exclude: vec![],
+            network_retries: 0,
+            measure_build_memory: false,
+            post_build_hook: None,
+            read_only: false,
+            enabled_tools: None,
+            disabled_tools: vec![],
+            allowed_dirs: vec![project_folder],
+        }
+    }
+}
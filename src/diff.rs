@@ -0,0 +1,220 @@
+/// Lines of context around each change, as in `git diff`.
+const CONTEXT: usize = 3;
+
+/// Above this many line pairs the changed region is not aligned line by line, and is
+/// instead replaced as a whole, which keeps the patch valid but less minimal.
+const MAX_ALIGNED: usize = 4_000_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Renders a git-style unified diff of one file, or `None` if the contents are equal. Each
+/// hunk is returned separately, starting with its `@@` header; the file's patch is the
+/// `diff --git` header followed by the hunks.
+pub fn file_patch(path: &str, before: &str, after: &str) -> Option<(String, Vec<String>)> {
+    if before == after {
+        return None;
+    }
+    let old = before.split_inclusive('\n').collect::<Vec<_>>();
+    let new = after.split_inclusive('\n').collect::<Vec<_>>();
+    let ops = edit_script(&old, &new);
+
+    let mut hunks = vec![];
+    let mut index = 0;
+    while let Some(first_change) = ops[index..].iter().position(|op| *op != Op::Equal) {
+        // Extend the hunk while changes are close enough for their context to touch.
+        let start = (index + first_change).saturating_sub(CONTEXT);
+        let mut end = index + first_change;
+        loop {
+            while end < ops.len() && ops[end] != Op::Equal {
+                end += 1;
+            }
+            let next_change = ops[end..].iter().position(|op| *op != Op::Equal);
+            match next_change {
+                Some(gap) if gap <= 2 * CONTEXT => end += gap,
+                _ => break,
+            }
+        }
+        let end = (end + CONTEXT).min(ops.len());
+        hunks.push(render_hunk(&ops, &old, &new, start, end));
+        index = end;
+    }
+
+    let mut patch = format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", path);
+    for hunk in &hunks {
+        patch.push_str(hunk);
+    }
+    Some((patch, hunks))
+}
+
+/// Line-level edit script turning `old` into `new`.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut ops = vec![Op::Equal; prefix];
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_ALIGNED {
+        ops.extend(std::iter::repeat_n(Op::Delete, old_middle.len()));
+        ops.extend(std::iter::repeat_n(Op::Insert, new_middle.len()));
+    } else {
+        ops.extend(align(old_middle, new_middle));
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    ops
+}
+
+/// Aligns two line sequences along their longest common subsequence.
+fn align(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let width = new.len() + 1;
+    // `lcs[i * width + j]`: length of the LCS of `old[i..]` and `new[j..]`.
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            // Ties delete first, so removed lines come before their replacements as in git.
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
+
+fn render_hunk(ops: &[Op], old: &[&str], new: &[&str], start: usize, end: usize) -> String {
+    // Line positions at the start of the hunk.
+    let old_start = ops[..start].iter().filter(|op| **op != Op::Insert).count();
+    let new_start = ops[..start].iter().filter(|op| **op != Op::Delete).count();
+    let (mut i, mut j) = (old_start, new_start);
+    let mut body = String::new();
+    for op in &ops[start..end] {
+        let (marker, line) = match op {
+            Op::Equal => {
+                i += 1;
+                j += 1;
+                (' ', old[i - 1])
+            }
+            Op::Delete => {
+                i += 1;
+                ('-', old[i - 1])
+            }
+            Op::Insert => {
+                j += 1;
+                ('+', new[j - 1])
+            }
+        };
+        body.push(marker);
+        body.push_str(line);
+        if !line.ends_with('\n') {
+            body.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+    format!(
+        "@@ -{} +{} @@\n{}",
+        range(old_start, i - old_start),
+        range(new_start, j - new_start),
+        body
+    )
+}
+
+/// A hunk's `start,count` range; an empty range names the line before it.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(lines: std::ops::Range<usize>) -> String {
+        lines.map(|n| format!("line {}\n", n)).collect()
+    }
+
+    #[test]
+    fn equal_contents_have_no_patch() {
+        assert!(file_patch("a.move", "same\n", "same\n").is_none());
+    }
+
+    #[test]
+    fn nearby_changes_share_a_hunk() {
+        let before = numbered(1..21);
+        // Lines 5 and 11 are six lines apart, so their context touches.
+        let after = before
+            .replace("line 5\n", "line five\n")
+            .replace("line 11\n", "line eleven\n");
+        let (patch, hunks) = file_patch("a.move", &before, &after).unwrap();
+        assert_eq!(hunks.len(), 1);
+        assert!(hunks[0].starts_with("@@ -2,13 +2,13 @@\n"));
+        assert!(patch.starts_with("diff --git a/a.move b/a.move\n--- a/a.move\n+++ b/a.move\n"));
+    }
+
+    #[test]
+    fn distant_changes_get_their_own_hunks() {
+        let before = numbered(1..31);
+        let after = before
+            .replace("line 5\n", "line five\n")
+            .replace("line 25\n", "line twenty-five\n");
+        let (_, hunks) = file_patch("a.move", &before, &after).unwrap();
+        assert_eq!(
+            hunks,
+            vec![
+                "@@ -2,7 +2,7 @@\n line 2\n line 3\n line 4\n-line 5\n+line five\n line 6\n line 7\n line 8\n",
+                "@@ -22,7 +22,7 @@\n line 22\n line 23\n line 24\n-line 25\n+line twenty-five\n line 26\n line 27\n line 28\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_ranges_name_the_line_before() {
+        let (_, hunks) = file_patch("a.move", "", "added\n").unwrap();
+        assert_eq!(hunks, vec!["@@ -0,0 +1 @@\n+added\n"]);
+
+        let (_, hunks) = file_patch("a.move", "a\nb\n", "").unwrap();
+        assert_eq!(hunks, vec!["@@ -1,2 +0,0 @@\n-a\n-b\n"]);
+
+        let (_, hunks) = file_patch("a.move", "a\nc\n", "a\nb\nc\n").unwrap();
+        assert_eq!(hunks, vec!["@@ -1,2 +1,3 @@\n a\n+b\n c\n"]);
+    }
+
+    #[test]
+    fn missing_trailing_newlines_are_marked() {
+        let (_, hunks) = file_patch("a.move", "a\nb", "a\nb\n").unwrap();
+        assert_eq!(
+            hunks,
+            vec!["@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"]
+        );
+    }
+}
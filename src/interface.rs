@@ -0,0 +1,313 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Default)]
+pub struct ModuleInterface {
+    pub address: String,
+    pub name: String,
+    pub functions: Vec<FunctionInterface>,
+    pub structs: Vec<StructInterface>,
+    pub constants: Vec<ConstantInterface>,
+    /// `use` declarations of the disassembly, with `0x`-prefixed addresses.
+    #[serde(skip)]
+    pub uses: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FunctionInterface {
+    pub name: String,
+    pub visibility: String,
+    pub entry: bool,
+    pub signature: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StructInterface {
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub fields: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConstantInterface {
+    pub index: u32,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub value: String,
+}
+
+/// Parses the output of `sui move disassemble` into the module's public interface.
+pub fn parse_disassembly(input: &str) -> Option<ModuleInterface> {
+    let mut module: Option<ModuleInterface> = None;
+    let mut depth = 0usize;
+    let mut in_constants = false;
+    let mut current_struct: Option<StructInterface> = None;
+
+    for line in input.lines() {
+        let trimmed = line.trim();
+
+        if depth == 0 {
+            if let Some(rest) = trimmed.strip_prefix("module ") {
+                let id = rest.trim_end_matches('{').trim();
+                let (address, name) = id
+                    .rsplit_once("::")
+                    .or_else(|| id.rsplit_once('.'))
+                    .unwrap_or(("", id));
+                module = Some(ModuleInterface {
+                    address: address.to_string(),
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                depth = 1;
+            }
+            continue;
+        }
+
+        let module = module.as_mut()?;
+
+        if in_constants {
+            if trimmed == "]" {
+                in_constants = false;
+            } else if let Some(constant) = parse_constant(trimmed) {
+                module.constants.push(constant);
+            }
+            continue;
+        }
+
+        if let Some(mut item) = current_struct.take() {
+            if trimmed == "}" {
+                depth -= 1;
+                module.structs.push(item);
+            } else {
+                item.fields.push(trimmed.trim_end_matches(',').to_string());
+                current_struct = Some(item);
+            }
+            continue;
+        }
+
+        if depth > 1 {
+            if trimmed == "}" {
+                depth -= 1;
+            } else if trimmed.ends_with('{') {
+                depth += 1;
+            }
+            continue;
+        }
+
+        if trimmed == "}" {
+            depth = 0;
+            continue;
+        }
+
+        if let Some(path) = trimmed.strip_prefix("use ") {
+            let path = if path.starts_with("0x") {
+                path.to_string()
+            } else {
+                format!("0x{}", path)
+            };
+            module.uses.push(format!("use {}", path));
+            continue;
+        }
+
+        if trimmed.starts_with("Constants [") {
+            in_constants = true;
+            continue;
+        }
+
+        let opens_block = trimmed.ends_with('{');
+
+        if let Some(item) = parse_struct(trimmed) {
+            if opens_block {
+                depth += 1;
+                current_struct = Some(item);
+            } else {
+                module.structs.push(item);
+            }
+            continue;
+        }
+
+        if let Some(function) = parse_function(trimmed) {
+            if function.visibility != "private" || function.entry {
+                module.functions.push(function);
+            }
+        }
+
+        if opens_block {
+            depth += 1;
+        }
+    }
+
+    module
+}
+
+fn parse_struct(line: &str) -> Option<StructInterface> {
+    let rest = line.strip_prefix("public ").unwrap_or(line);
+    let rest = rest
+        .strip_prefix("native struct ")
+        .or_else(|| rest.strip_prefix("struct "))?;
+    let rest = rest.trim_end_matches('{').trim();
+    let (name, abilities) = match rest.split_once(" has ") {
+        Some((name, abilities)) => (
+            name,
+            abilities
+                .split(',')
+                .map(|ability| ability.trim().to_string())
+                .filter(|ability| !ability.is_empty())
+                .collect(),
+        ),
+        None => (rest, vec![]),
+    };
+    Some(StructInterface {
+        name: name.trim().to_string(),
+        abilities,
+        fields: vec![],
+    })
+}
+
+fn parse_function(line: &str) -> Option<FunctionInterface> {
+    if !line.contains('(') {
+        return None;
+    }
+    let mut rest = line.trim_end_matches('{').trim();
+    let mut entry = false;
+    let mut visibility = "private";
+
+    loop {
+        if let Some(next) = rest.strip_prefix("entry ") {
+            entry = true;
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("native ") {
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("public(friend) ") {
+            visibility = "friend";
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("public(package) ") {
+            visibility = "package";
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("public ") {
+            visibility = "public";
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("fun ") {
+            rest = next;
+        } else {
+            break;
+        }
+    }
+
+    let name_end = rest.find(['(', '<'])?;
+    let name = rest[..name_end].trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    Some(FunctionInterface {
+        name: name.to_string(),
+        visibility: visibility.to_string(),
+        entry,
+        signature: rest.to_string(),
+    })
+}
+
+fn parse_constant(line: &str) -> Option<ConstantInterface> {
+    let (index, rest) = line.split_once("=>")?;
+    let (type_, value) = rest.trim().split_once(": ")?;
+    Some(ConstantInterface {
+        index: index.trim().parse().ok()?,
+        type_: type_.trim().to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+/// Generates a `#[test_only]` module named `{module}_mock` with a stub for every public
+/// function of `module`. Stubs returning a primitive or vector return its default value;
+/// all others abort, since values of other types cannot be conjured.
+pub fn generate_mock(module: &ModuleInterface) -> String {
+    let mut out = format!(
+        "#[test_only]\nmodule {}::{}_mock {{\n",
+        address_literal(&module.address),
+        module.name
+    );
+    for use_ in &module.uses {
+        out.push_str(&format!("    {}\n", use_));
+    }
+    if !module.structs.is_empty() {
+        let own_types = module
+            .structs
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+        out.push_str(&format!(
+            "    use {}::{}::{{{}}};\n",
+            address_literal(&module.address),
+            module.name,
+            own_types.join(", ")
+        ));
+    }
+    out.push('\n');
+    out.push_str("    const EUnimplemented: u64 = 0;\n");
+
+    for function in module
+        .functions
+        .iter()
+        .filter(|function| function.visibility == "public")
+    {
+        let (parameters, return_type) = split_signature(&function.signature);
+        let body = match return_type.as_deref() {
+            None => " {}".to_string(),
+            Some(type_) => format!(
+                ": {} {{\n        {}\n    }}",
+                type_,
+                default_value(type_).unwrap_or_else(|| "abort EUnimplemented".to_string())
+            ),
+        };
+        out.push_str(&format!("\n    public fun {}{}\n", parameters, body));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Splits a disassembled signature into `name<T>(params)`, with parameters renamed to
+/// unused `_argN`, and the return type.
+fn split_signature(signature: &str) -> (String, Option<String>) {
+    let mut depth = 0;
+    let mut close = signature.len();
+    for (index, c) in signature.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = index;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let parameter = regex::Regex::new(r"\bArg(\d+):").expect("valid regex");
+    let head = parameter.replace_all(&signature[..close], "_arg$1:");
+    let return_type = signature
+        .get(close + 1..)
+        .and_then(|rest| rest.trim().strip_prefix(':'))
+        .map(|type_| type_.trim().to_string())
+        .filter(|type_| !type_.is_empty());
+    (format!("{})", head), return_type)
+}
+
+/// A Move expression producing the default value of a primitive or vector type.
+fn default_value(type_: &str) -> Option<String> {
+    match type_ {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "u256" => Some("0".to_string()),
+        "bool" => Some("false".to_string()),
+        "address" => Some("@0x0".to_string()),
+        _ if type_.starts_with("vector<") => Some("vector[]".to_string()),
+        _ => None,
+    }
+}
+
+fn address_literal(address: &str) -> String {
+    if address.starts_with("0x") || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        address.to_string()
+    } else {
+        format!("0x{}", address)
+    }
+}
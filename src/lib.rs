@@ -1 +1,27 @@
+pub mod addresses;
+pub mod analyzer;
+pub mod client;
+pub mod command;
+pub mod compatibility;
+pub mod config;
+pub mod diff;
+pub mod interface;
+pub mod localnet;
+pub mod locks;
+pub mod manifest;
+pub mod memory;
+pub mod outline;
+pub mod progress;
+pub mod ptb;
+pub mod repro;
+pub mod retry;
+pub mod rpc;
+pub mod scratch;
 pub mod service;
+pub mod source_verification;
+pub mod sources;
+pub mod stats;
+pub mod test_output;
+pub mod type_layout;
+pub mod units;
+pub mod validate;
@@ -0,0 +1,98 @@
+use crate::command;
+use anyhow::Context;
+use std::{process::Stdio, sync::Arc};
+use tokio::sync::Mutex;
+
+pub const RPC_URL: &str = "http://127.0.0.1:9000";
+pub const FAUCET_URL: &str = "http://127.0.0.1:9123";
+const RPC_ADDR: &str = "127.0.0.1:9000";
+
+/// A local Sui network started by this server, shared by every session so that it can be
+/// stopped from any of them and cleaned up on shutdown.
+#[derive(Clone, Default)]
+pub struct Localnet {
+    process: Arc<Mutex<Option<tokio::process::Child>>>,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalnetStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub rpc_reachable: bool,
+    pub rpc_url: &'static str,
+    pub faucet_url: &'static str,
+}
+
+impl Localnet {
+    /// Starts `sui start` with a fresh genesis and faucet, refusing if a network is already up.
+    pub async fn start(&self) -> anyhow::Result<LocalnetStatus> {
+        let mut process = self.process.lock().await;
+        if let Some(child) = process.as_mut() {
+            if child.try_wait()?.is_none() {
+                anyhow::bail!("Localnet is already running (pid {:?})", child.id());
+            }
+        }
+        if rpc_reachable().await {
+            anyhow::bail!(
+                "Another process is already serving RPC on {}; stop it first",
+                RPC_URL
+            );
+        }
+
+        let child = command::with_env(&mut tokio::process::Command::new("sui"))
+            .arg("start")
+            .arg("--with-faucet")
+            .arg("--force-regenesis")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to run `sui start`")?;
+        let pid = child.id();
+        tracing::info!(?pid, "started localnet");
+        *process = Some(child);
+
+        Ok(LocalnetStatus {
+            running: true,
+            pid,
+            rpc_reachable: false,
+            rpc_url: RPC_URL,
+            faucet_url: FAUCET_URL,
+        })
+    }
+
+    /// Stops the tracked network, returning whether one was running.
+    pub async fn stop(&self) -> anyhow::Result<bool> {
+        let Some(mut child) = self.process.lock().await.take() else {
+            return Ok(false);
+        };
+        let pid = child.id();
+        if child.try_wait()?.is_some() {
+            return Ok(false);
+        }
+        child.kill().await.context("Failed to stop localnet")?;
+        tracing::info!(?pid, "stopped localnet");
+        Ok(true)
+    }
+
+    pub async fn status(&self) -> anyhow::Result<LocalnetStatus> {
+        let mut process = self.process.lock().await;
+        let pid = match process.as_mut() {
+            Some(child) if child.try_wait()?.is_none() => child.id(),
+            _ => None,
+        };
+        Ok(LocalnetStatus {
+            running: pid.is_some(),
+            pid,
+            rpc_reachable: rpc_reachable().await,
+            rpc_url: RPC_URL,
+            faucet_url: FAUCET_URL,
+        })
+    }
+}
+
+async fn rpc_reachable() -> bool {
+    tokio::net::TcpStream::connect(RPC_ADDR).await.is_ok()
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::OwnedMutexGuard;
+use tokio_util::sync::CancellationToken;
+
+/// Per-package locks shared by every session. Operations that rewrite a package's sources or
+/// build output hold its lock, so they run one at a time per package while different
+/// packages proceed concurrently.
+#[derive(Debug, Default)]
+pub struct PackageLocks {
+    locks: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl PackageLocks {
+    /// Waits for the lock of the package at `folder`, keyed by its canonical path. Returns
+    /// `None` if `ct` is cancelled first.
+    pub async fn lock(&self, folder: &str, ct: &CancellationToken) -> Option<OwnedMutexGuard<()>> {
+        let key = std::fs::canonicalize(folder).unwrap_or_else(|_| PathBuf::from(folder));
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key)
+            .or_default()
+            .clone();
+
+        if let Ok(guard) = lock.clone().try_lock_owned() {
+            return Some(guard);
+        }
+        tracing::debug!(package = %folder, "waiting for package lock");
+        tokio::select! {
+            guard = lock.lock_owned() => Some(guard),
+            _ = ct.cancelled() => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn folder(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("package-locks-{}-{}", name, uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn different_packages_lock_concurrently() {
+        let locks = PackageLocks::default();
+        let ct = CancellationToken::new();
+        let (first, second) = (folder("first"), folder("second"));
+
+        let first_guard = locks.lock(&first, &ct).await;
+        let second_guard =
+            tokio::time::timeout(Duration::from_secs(1), locks.lock(&second, &ct)).await;
+
+        assert!(first_guard.is_some());
+        assert!(matches!(second_guard, Ok(Some(_))));
+    }
+
+    #[tokio::test]
+    async fn same_package_waits_for_the_lock() {
+        let locks = PackageLocks::default();
+        let ct = CancellationToken::new();
+        let package = folder("same");
+
+        let guard = locks.lock(&package, &ct).await;
+        assert!(guard.is_some());
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(100), locks.lock(&package, &ct)).await;
+        assert!(waiting.is_err());
+
+        drop(guard);
+        assert!(locks.lock(&package, &ct).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn cancelled_wait_returns_none() {
+        let locks = PackageLocks::default();
+        let ct = CancellationToken::new();
+        let package = folder("cancelled");
+
+        let _guard = locks.lock(&package, &ct).await;
+        let waiting = CancellationToken::new();
+        waiting.cancel();
+        assert!(locks.lock(&package, &waiting).await.is_none());
+    }
+}
@@ -1,25 +1,278 @@
+use anyhow::Context;
 use rmcp::transport::sse_server::{SseServer, SseServerConfig};
-use sui_dev_mcp::service::SuiService;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use sui_dev_mcp::{
+    command,
+    config::{Config, Hook},
+    localnet::Localnet,
+    locks::PackageLocks,
+    manifest,
+    service::SuiService,
+    stats::Stats,
+};
+use tracing_subscriber::{Layer, Registry, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(serde::Deserialize)]
 struct Env {
     port: u16,
     project_folder: String,
     movefmt_cmd: String,
+    movefmt_config: Option<String>,
+    /// `NAME=COMMAND` entries a `format_project` call may select by name.
+    formatters: Option<Vec<String>>,
+    format_dirs: Option<Vec<String>>,
+    command_timeout_secs: Option<u64>,
+    /// Walk up from `project_folder` to the nearest `Move.toml` (default true), keeping
+    /// `project_folder` when there is none.
+    discover_project_root: Option<bool>,
+    rpc_url: Option<String>,
+    json_errors: Option<bool>,
+    validate_exclude: Option<Vec<String>>,
+    /// Retries of network-dependent commands after a transient failure (default 2).
+    network_retries: Option<u32>,
+    /// Enable `build_memory` (default false).
+    measure_build_memory: Option<bool>,
+    /// Executable (and whitespace-separated arguments) run after each successful build,
+    /// relative to the project folder unless absolute. Unset by default.
+    post_build_hook: Option<String>,
+    read_only: Option<bool>,
+    /// Tools to offer (default: all).
+    enabled_tools: Option<Vec<String>>,
+    disabled_tools: Option<Vec<String>>,
+    /// `KEY=VALUE` entries set for every spawned `sui`/formatter command.
+    command_env: Option<Vec<String>>,
+    /// Client config/keystore directory, passed to the CLI as `SUI_CONFIG_DIR`.
+    sui_config_dir: Option<String>,
+    /// Directories tools may touch (default: the project folder).
+    allowed_dirs: Option<Vec<String>>,
+    /// Cap on captured stdout/stderr per command, in bytes.
+    max_output_bytes: Option<usize>,
+    #[serde(default)]
+    log_format: LogFormat,
+    log_file: Option<String>,
+}
+
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+fn fmt_layer<W>(format: &LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+    match format {
+        LogFormat::Pretty => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let env = envy::from_env::<Env>()?;
+
+    // Console logs always go to stderr: stdout is reserved for MCP framing
+    // when the server is run as a stdio subprocess.
+    let mut layers = vec![fmt_layer(&env.log_format, std::io::stderr, true)];
+
+    // Held until exit so buffered lines are flushed to the log file.
+    let _log_guard = match &env.log_file {
+        Some(log_file) => {
+            let path = std::path::Path::new(log_file);
+            let file_name = path
+                .file_name()
+                .with_context(|| format!("Invalid log file path: `{}`", log_file))?;
+            let dir = path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(std::path::Path::new("."));
+            let appender = tracing_appender::rolling::daily(dir, file_name);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            layers.push(fmt_layer(&env.log_format, writer, false));
+            Some(guard)
+        }
+        None => None,
+    };
+
     tracing_subscriber::registry()
+        .with(layers)
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "debug".to_string().into()),
         )
-        .with(tracing_subscriber::fmt::layer())
         .init();
 
+    if let Some(movefmt_config) = &env.movefmt_config {
+        anyhow::ensure!(
+            std::path::Path::new(movefmt_config).is_file(),
+            "movefmt config file not found: `{}`",
+            movefmt_config
+        );
+    }
+
+    let mut formatters: Vec<(String, String)> = vec![];
+    for entry in env.formatters.iter().flatten() {
+        let (name, cmd) = entry
+            .split_once('=')
+            .with_context(|| format!("Expected `NAME=COMMAND` in FORMATTERS, got `{}`", entry))?;
+        let (name, cmd) = (name.trim(), cmd.trim());
+        anyhow::ensure!(
+            !name.is_empty() && !cmd.is_empty(),
+            "Expected `NAME=COMMAND` in FORMATTERS, got `{}`",
+            entry
+        );
+        anyhow::ensure!(
+            formatters.iter().all(|(other, _)| other != name),
+            "Formatter `{}` is listed twice in FORMATTERS",
+            name
+        );
+        formatters.push((name.to_string(), cmd.to_string()));
+    }
+
+    let validate_exclude = env
+        .validate_exclude
+        .iter()
+        .flatten()
+        .map(|pattern| {
+            glob::Pattern::new(pattern)
+                .with_context(|| format!("Invalid VALIDATE_EXCLUDE pattern: `{}`", pattern))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if let Some(rpc_url) = &env.rpc_url {
+        let url = reqwest::Url::parse(rpc_url)
+            .with_context(|| format!("Invalid RPC URL: `{}`", rpc_url))?;
+        anyhow::ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "RPC URL must use http or https: `{}`",
+            rpc_url
+        );
+    }
+
+    let mut command_env = env
+        .command_env
+        .iter()
+        .flatten()
+        .map(|entry| command::parse_env_var(entry))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if let Some(sui_config_dir) = &env.sui_config_dir {
+        std::fs::create_dir_all(sui_config_dir)
+            .with_context(|| format!("Failed to create `{}`", sui_config_dir))?;
+        let sui_config_dir = std::fs::canonicalize(sui_config_dir)?;
+        tracing::info!(sui_config_dir = %sui_config_dir.display(), "using isolated sui config");
+        command_env.retain(|(key, _)| key != "SUI_CONFIG_DIR");
+        command_env.push((
+            "SUI_CONFIG_DIR".to_string(),
+            sui_config_dir.to_string_lossy().to_string(),
+        ));
+    }
+    command::set_env(command_env);
+    if let Some(max_output_bytes) = env.max_output_bytes {
+        anyhow::ensure!(max_output_bytes > 0, "MAX_OUTPUT_BYTES must be positive");
+        command::set_max_output(max_output_bytes);
+    }
+
+    let discover = env.discover_project_root.unwrap_or(true);
+    let discovered_root = discover
+        .then(|| manifest::find_package_root(std::path::Path::new(&env.project_folder)))
+        .flatten()
+        .map(|root| root.to_string_lossy().to_string());
+    let project_folder = match discovered_root {
+        Some(root) => {
+            tracing::info!(project_folder = %root, "resolved package root");
+            root
+        }
+        None => {
+            if discover {
+                tracing::warn!(
+                    project_folder = %env.project_folder,
+                    "no Move.toml found in the project folder or its parents, using it as is"
+                );
+            }
+            env.project_folder
+        }
+    };
+
+    let allowed_dirs = env
+        .allowed_dirs
+        .unwrap_or_else(|| vec![project_folder.clone()])
+        .iter()
+        .map(|dir| {
+            std::fs::canonicalize(dir)
+                .with_context(|| format!("Invalid allowed directory: `{}`", dir))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let canonical_project = std::fs::canonicalize(&project_folder)
+        .with_context(|| format!("Invalid project folder: `{}`", project_folder))?;
+    anyhow::ensure!(
+        allowed_dirs
+            .iter()
+            .any(|dir| canonical_project.starts_with(dir)),
+        "Project folder `{}` is outside ALLOWED_DIRS",
+        project_folder
+    );
+
+    let post_build_hook = env
+        .post_build_hook
+        .as_deref()
+        .map(|hook| {
+            let mut words = hook.split_whitespace();
+            let program = words.next().context("POST_BUILD_HOOK is empty")?;
+            let path = std::fs::canonicalize(std::path::Path::new(&project_folder).join(program))
+                .with_context(|| format!("Post-build hook not found: `{}`", program))?;
+            anyhow::ensure!(
+                path.is_file(),
+                "Post-build hook is not a file: `{}`",
+                program
+            );
+            anyhow::ensure!(
+                allowed_dirs.iter().any(|dir| path.starts_with(dir)),
+                "Post-build hook `{}` is outside ALLOWED_DIRS",
+                program
+            );
+            tracing::info!(hook = %path.display(), "using post-build hook");
+            Ok(Hook {
+                program: path,
+                args: words.map(str::to_string).collect(),
+            })
+        })
+        .transpose()?;
+
+    let tool_names = SuiService::tool_names();
+    if let Some(unknown) = env
+        .enabled_tools
+        .iter()
+        .flatten()
+        .chain(env.disabled_tools.iter().flatten())
+        .find(|name| !tool_names.contains(*name))
+    {
+        anyhow::bail!("Unknown tool `{}` in ENABLED_TOOLS/DISABLED_TOOLS", unknown);
+    }
+
+    let service_config = std::sync::Arc::new(Config {
+        project_folder,
+        movefmt_cmd: env.movefmt_cmd,
+        movefmt_config: env.movefmt_config,
+        formatters,
+        format_dirs: env.format_dirs,
+        command_timeout: env.command_timeout_secs.map(std::time::Duration::from_secs),
+        rpc_url: env.rpc_url,
+        json_errors: env.json_errors.unwrap_or(false),
+        validate_exclude,
+        network_retries: env.network_retries.unwrap_or(2),
+        measure_build_memory: env.measure_build_memory.unwrap_or(false),
+        post_build_hook,
+        read_only: env.read_only.unwrap_or(false),
+        enabled_tools: env.enabled_tools,
+        disabled_tools: env.disabled_tools.unwrap_or_default(),
+        allowed_dirs,
+    });
+
     let bind_address = format!("127.0.0.1:{}", env.port);
 
     let config = SseServerConfig {
@@ -32,6 +285,15 @@ async fn main() -> anyhow::Result<()> {
 
     let (sse_server, router) = SseServer::new(config);
 
+    let stats = std::sync::Arc::new(Stats::default());
+    let router = router.route(
+        "/stats",
+        axum::routing::get({
+            let stats = stats.clone();
+            move || async move { axum::Json(stats.snapshot()) }
+        }),
+    );
+
     let listener = tokio::net::TcpListener::bind(sse_server.config.bind).await?;
 
     let ct = sse_server.config.ct.child_token();
@@ -47,10 +309,23 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
-    let ct =
-        sse_server.with_service(move || SuiService::new(&env.project_folder, &env.movefmt_cmd));
+    let localnet = Localnet::default();
+    let package_locks = std::sync::Arc::new(PackageLocks::default());
+
+    let ct = sse_server.with_service({
+        let localnet = localnet.clone();
+        move || {
+            SuiService::new(
+                service_config.clone(),
+                localnet.clone(),
+                package_locks.clone(),
+                stats.connect(),
+            )
+        }
+    });
 
     tokio::signal::ctrl_c().await?;
     ct.cancel();
+    localnet.stop().await?;
     Ok(())
 }
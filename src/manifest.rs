@@ -0,0 +1,627 @@
+use anyhow::Context;
+
+pub fn read_manifest(project_folder: &str) -> anyhow::Result<toml::Table> {
+    let path = format!("{}/Move.toml", project_folder);
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read `{}`", path))?;
+    contents
+        .parse::<toml::Table>()
+        .with_context(|| format!("Failed to parse `{}`", path))
+}
+
+/// Returns the nearest directory at or above `folder` that contains a `Move.toml`.
+pub fn find_package_root(folder: &std::path::Path) -> Option<&std::path::Path> {
+    folder
+        .ancestors()
+        .find(|dir| dir.join("Move.toml").is_file())
+}
+
+pub fn package_name(manifest: &toml::Table) -> anyhow::Result<String> {
+    manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+        .context("Move.toml is missing `[package] name`")
+}
+
+/// Directories of the standard Move package layout that may hold Move code.
+const LAYOUT_DIRS: &[&str] = &["sources", "tests", "examples", "scripts"];
+
+/// Lists the directories of the standard layout that exist in the package. Move.toml has no
+/// keys to relocate them, so the layout is all there is to read.
+pub fn layout_dirs(project_folder: &str) -> Vec<String> {
+    LAYOUT_DIRS
+        .iter()
+        .filter(|dir| std::path::Path::new(project_folder).join(dir).is_dir())
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
+/// Reads `Move.lock`, returning `None` if the package has not been built yet.
+pub fn read_lockfile(project_folder: &str) -> anyhow::Result<Option<toml::Table>> {
+    let path = format!("{}/Move.lock", project_folder);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read `{}`", path)),
+    };
+    contents
+        .parse::<toml::Table>()
+        .map(Some)
+        .with_context(|| format!("Failed to parse `{}`", path))
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct LockedPackage {
+    pub name: String,
+    pub source: Option<toml::Value>,
+    pub dependencies: Vec<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    pub lockfile_version: Option<i64>,
+    pub root_dependencies: Vec<String>,
+    pub packages: Vec<LockedPackage>,
+    pub toolchain: Option<toml::Value>,
+}
+
+/// Extracts the resolved dependency graph from a parsed `Move.lock`.
+pub fn dependency_graph(lockfile: &toml::Table) -> DependencyGraph {
+    let section = lockfile.get("move");
+    let packages = section
+        .and_then(|section| section.get("package"))
+        .and_then(|packages| packages.as_array())
+        .map(|packages| {
+            packages
+                .iter()
+                .filter_map(|package| {
+                    Some(LockedPackage {
+                        name: dependency_id(package)?,
+                        source: package.get("source").cloned(),
+                        dependencies: dependency_ids(package.get("dependencies")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    DependencyGraph {
+        lockfile_version: section
+            .and_then(|section| section.get("version"))
+            .and_then(|version| version.as_integer()),
+        root_dependencies: dependency_ids(section.and_then(|section| section.get("dependencies"))),
+        packages,
+        toolchain: section
+            .and_then(|section| section.get("toolchain-version"))
+            .cloned(),
+    }
+}
+
+/// Dependencies are listed as `{ id = "Sui", name = "Sui" }` in newer lockfiles and as
+/// `{ name = "Sui" }` or plain strings in older ones.
+fn dependency_id(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(name) => Some(name.clone()),
+        toml::Value::Table(table) => table
+            .get("id")
+            .or_else(|| table.get("name"))
+            .and_then(|id| id.as_str())
+            .map(|id| id.to_string()),
+        _ => None,
+    }
+}
+
+fn dependency_ids(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|deps| deps.as_array())
+        .map(|deps| deps.iter().filter_map(dependency_id).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the ID the package was last published at in `env`, as recorded by the CLI in
+/// `Move.lock`. `published-at` in `Move.toml` is not used, as it does not say which
+/// environment it belongs to.
+pub fn published_id(lockfile: Option<&toml::Table>, env: &str) -> Option<String> {
+    lockfile
+        .and_then(|lockfile| lockfile.get("env"))
+        .and_then(|envs| envs.get(env))
+        .and_then(|env| {
+            env.get("latest-published-id")
+                .or_else(|| env.get("original-published-id"))
+        })
+        .and_then(|id| id.as_str())
+        .filter(|id| id.trim_start_matches("0x").chars().any(|c| c != '0'))
+        .map(|id| id.to_string())
+}
+
+/// Collects the git URLs of all dependencies declared in `Move.toml` or resolved in `Move.lock`.
+pub fn git_dependency_urls(manifest: &toml::Table, lockfile: Option<&toml::Table>) -> Vec<String> {
+    let declared = manifest
+        .get("dependencies")
+        .and_then(|deps| deps.as_table())
+        .into_iter()
+        .flat_map(|deps| deps.values());
+    let resolved = lockfile
+        .and_then(|lockfile| lockfile.get("move"))
+        .and_then(|section| section.get("package"))
+        .and_then(|packages| packages.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|package| package.get("source"));
+
+    let mut urls: Vec<String> = declared
+        .chain(resolved)
+        .filter_map(|source| source.get("git"))
+        .filter_map(|git| git.as_str())
+        .map(|git| git.to_string())
+        .collect();
+    urls.sort();
+    urls.dedup();
+    urls
+}
+
+/// Maps the git URL of each git dependency in `[dependencies]` to its `rev`.
+pub fn git_revisions(manifest: &toml::Table) -> std::collections::HashMap<String, String> {
+    table_entries(manifest, "dependencies")
+        .filter_map(|(_, dependency)| {
+            let git = dependency.get("git")?.as_str()?;
+            let rev = dependency.get("rev")?.as_str()?;
+            Some((git.to_string(), rev.to_string()))
+        })
+        .collect()
+}
+
+/// Named addresses in `[addresses]` that are unassigned (`_`) or left at `0x0`. The package's
+/// own address is excluded, since it must be `0x0` when publishing.
+pub fn unset_addresses(manifest: &toml::Table) -> Vec<String> {
+    let own_name = package_name(manifest).ok().map(|name| name.to_lowercase());
+    manifest
+        .get("addresses")
+        .and_then(|addresses| addresses.as_table())
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| Some(name.to_lowercase()) != own_name)
+        .filter(|(_, address)| {
+            address.as_str().is_some_and(|address| {
+                address == "_" || address.trim_start_matches("0x").chars().all(|c| c == '0')
+            })
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct AddressBinding {
+    pub name: String,
+    /// `None` for addresses left unassigned (`_`).
+    pub address: Option<String>,
+    /// Where the binding comes from: `addresses`, `dev-addresses`, `addr-subst`,
+    /// `dependency <name>` or `framework`.
+    pub source: String,
+}
+
+/// Resolves the named addresses visible to the package the way the build does: bindings from
+/// local dependencies' manifests, overridden by the root `[addresses]`, then `[dev-addresses]`
+/// when `dev` is set, then any `addr-subst` on the root's dependencies. `std` and `sui` fall back
+/// to the framework addresses when nothing declares them.
+///
+/// Git dependencies are not fetched, so their own addresses are only included when the root
+/// also names them.
+pub fn address_bindings(project_folder: &str, dev: bool) -> anyhow::Result<Vec<AddressBinding>> {
+    let manifest = read_manifest(project_folder)?;
+    let mut bindings = std::collections::BTreeMap::new();
+    let mut visited = std::collections::HashSet::new();
+    dependency_addresses(
+        std::path::Path::new(project_folder),
+        &manifest,
+        &mut bindings,
+        &mut visited,
+    );
+
+    let mut sections = vec!["addresses"];
+    if dev {
+        sections.push("dev-addresses");
+    }
+    for section in sections {
+        for (name, address) in table_entries(&manifest, section) {
+            bindings.insert(name.clone(), binding(name, address, section));
+        }
+    }
+    for (_, dependency) in table_entries(&manifest, "dependencies") {
+        for (name, address) in dependency
+            .get("addr-subst")
+            .and_then(|subst| subst.as_table())
+            .into_iter()
+            .flatten()
+        {
+            bindings.insert(name.clone(), binding(name, address, "addr-subst"));
+        }
+    }
+    for (name, address) in [("std", "0x1"), ("sui", "0x2")] {
+        bindings
+            .entry(name.to_string())
+            .or_insert_with(|| AddressBinding {
+                name: name.to_string(),
+                address: Some(address.to_string()),
+                source: "framework".to_string(),
+            });
+    }
+    Ok(bindings.into_values().collect())
+}
+
+/// Collects the `[addresses]` of local dependencies, depth first, so that nearer packages
+/// override the bindings of their own dependencies.
+fn dependency_addresses(
+    folder: &std::path::Path,
+    manifest: &toml::Table,
+    bindings: &mut std::collections::BTreeMap<String, AddressBinding>,
+    visited: &mut std::collections::HashSet<std::path::PathBuf>,
+) {
+    for (_, dependency) in table_entries(manifest, "dependencies") {
+        let Some(local) = dependency.get("local").and_then(|local| local.as_str()) else {
+            continue;
+        };
+        let Ok(path) = folder.join(local).canonicalize() else {
+            continue;
+        };
+        if !visited.insert(path.clone()) {
+            continue;
+        }
+        let Ok(dep_manifest) = read_manifest(&path.to_string_lossy()) else {
+            continue;
+        };
+        dependency_addresses(&path, &dep_manifest, bindings, visited);
+        let source = format!(
+            "dependency {}",
+            package_name(&dep_manifest).unwrap_or_else(|_| local.to_string())
+        );
+        for (name, address) in table_entries(&dep_manifest, "addresses") {
+            bindings.insert(name.clone(), binding(name, address, &source));
+        }
+    }
+}
+
+/// Canonical folders of the package's local (`local = "..."`) dependencies, including its
+/// `[dev-dependencies]`, and of their own local dependencies in turn.
+pub fn local_dependencies(project_folder: &str) -> Vec<std::path::PathBuf> {
+    let mut folders = vec![];
+    let mut pending = vec![(std::path::PathBuf::from(project_folder), true)];
+    while let Some((folder, is_root)) = pending.pop() {
+        let Ok(manifest) = read_manifest(&folder.to_string_lossy()) else {
+            continue;
+        };
+        let sections: &[&str] = if is_root {
+            &["dependencies", "dev-dependencies"]
+        } else {
+            &["dependencies"]
+        };
+        for section in sections {
+            for (_, dependency) in table_entries(&manifest, section) {
+                let Some(local) = dependency.get("local").and_then(|local| local.as_str()) else {
+                    continue;
+                };
+                let Ok(path) = folder.join(local).canonicalize() else {
+                    continue;
+                };
+                if !folders.contains(&path) {
+                    folders.push(path.clone());
+                    pending.push((path, false));
+                }
+            }
+        }
+    }
+    folders.sort();
+    folders
+}
+
+fn table_entries<'a>(
+    manifest: &'a toml::Table,
+    section: &str,
+) -> impl Iterator<Item = (&'a String, &'a toml::Value)> {
+    manifest
+        .get(section)
+        .and_then(|table| table.as_table())
+        .into_iter()
+        .flatten()
+}
+
+fn binding(name: &str, address: &toml::Value, source: &str) -> AddressBinding {
+    AddressBinding {
+        name: name.to_string(),
+        address: address
+            .as_str()
+            .filter(|address| *address != "_")
+            .map(|address| address.to_string()),
+        source: source.to_string(),
+    }
+}
+
+pub fn package_version(manifest: &toml::Table) -> Option<String> {
+    manifest
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.as_str())
+        .map(|version| version.to_string())
+}
+
+/// Parses `MAJOR.MINOR.PATCH` with an optional `-prerelease` and `+build` suffix.
+pub fn parse_semver(version: &str) -> anyhow::Result<(u64, u64, u64)> {
+    let core = version.split_once('+').map_or(version, |(core, _)| core);
+    let core = core.split_once('-').map_or(core, |(core, _)| core);
+    let parts = core
+        .split('.')
+        .map(|part| {
+            anyhow::ensure!(
+                !part.is_empty()
+                    && part.chars().all(|c| c.is_ascii_digit())
+                    && (part == "0" || !part.starts_with('0')),
+                "Invalid version component `{}`",
+                part
+            );
+            part.parse::<u64>().map_err(anyhow::Error::from)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .with_context(|| format!("`{}` is not a semantic version", version))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => anyhow::bail!(
+            "`{}` is not a semantic version (MAJOR.MINOR.PATCH)",
+            version
+        ),
+    }
+}
+
+/// Returns `version` bumped at `level` (`major`, `minor` or `patch`), dropping any
+/// prerelease or build suffix.
+pub fn bump_version(version: &str, level: &str) -> anyhow::Result<String> {
+    let (major, minor, patch) = parse_semver(version)?;
+    match level {
+        "major" => Ok(format!("{}.0.0", major + 1)),
+        "minor" => Ok(format!("{}.{}.0", major, minor + 1)),
+        "patch" => Ok(format!("{}.{}.{}", major, minor, patch + 1)),
+        _ => anyhow::bail!("Unknown bump level `{}`", level),
+    }
+}
+
+/// Rewrites the `version` value in the `[package]` section of `Move.toml` in place, leaving
+/// every other line (including comments and spacing) untouched.
+pub fn set_package_version(project_folder: &str, version: &str) -> anyhow::Result<()> {
+    let path = format!("{}/Move.toml", project_folder);
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read `{}`", path))?;
+    let updated = replace_field(&contents, "package", "version", version)
+        .with_context(|| format!("`{}` has no `[package] version` to update", path))?;
+    std::fs::write(&path, updated).with_context(|| format!("Failed to write `{}`", path))
+}
+
+/// Returns `Move.toml` contents with `[package] edition` set to `edition`, adding the key
+/// under the `[package]` header when it is missing.
+pub fn with_edition(contents: &str, edition: &str) -> anyhow::Result<String> {
+    if let Some(updated) = replace_field(contents, "package", "edition", edition) {
+        return Ok(updated);
+    }
+    let mut inserted = false;
+    let updated = contents
+        .split_inclusive('\n')
+        .map(|line| {
+            if !inserted && line.trim_start().starts_with("[package]") {
+                inserted = true;
+                let newline = if line.ends_with('\n') { "" } else { "\n" };
+                format!("{}{}edition = \"{}\"\n", line, newline, edition)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<String>();
+    anyhow::ensure!(inserted, "Move.toml has no `[package]` section");
+    Ok(updated)
+}
+
+/// Returns `Move.toml` contents with relative `local = "..."` dependency paths made absolute
+/// against `folder`, so the manifest still resolves from a copy of the package elsewhere.
+pub fn with_absolute_local_paths(contents: &str, folder: &std::path::Path) -> String {
+    let local = regex::Regex::new(r#"(\blocal\s*=\s*)"([^"]*)""#).expect("valid regex");
+    local
+        .replace_all(contents, |captures: &regex::Captures| {
+            let path = std::path::Path::new(&captures[2]);
+            if path.is_absolute() {
+                return captures[0].to_string();
+            }
+            let absolute = folder.join(path).to_string_lossy().replace('\\', "/");
+            format!("{}\"{}\"", &captures[1], absolute)
+        })
+        .into_owned()
+}
+
+/// Git repository and subdirectory of the Sui framework package, as `sui move new` declares it.
+const FRAMEWORK_GIT: &str = "https://github.com/MystenLabs/sui.git";
+const FRAMEWORK_SUBDIR: &str = "crates/sui-framework/packages/sui-framework";
+
+/// Returns `Move.toml` contents with the `Sui` framework dependency pinned to `rev`. An inline
+/// `Sui = { git = ..., rev = ... }` entry or a `[dependencies.Sui]` table is edited in place;
+/// when the manifest leaves the framework implicit, an explicit dependency is added.
+pub fn with_framework_rev(contents: &str, rev: &str) -> anyhow::Result<String> {
+    if let Some(updated) = replace_field(contents, "dependencies.Sui", "rev", rev) {
+        return Ok(updated);
+    }
+    anyhow::ensure!(
+        !contents
+            .lines()
+            .any(|line| line.trim_start().starts_with("[dependencies.Sui]")),
+        "The `Sui` dependency is not pinned by `rev`"
+    );
+
+    let inline_rev = regex::Regex::new(r#"(\brev\s*=\s*)"[^"]*""#).expect("valid regex");
+    let mut in_dependencies = false;
+    let mut found = false;
+    let mut inserted = false;
+    let mut updated = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            in_dependencies = trimmed.starts_with("[dependencies]");
+            updated.push_str(line);
+            continue;
+        }
+        let is_framework = in_dependencies
+            && line
+                .split_once('=')
+                .is_some_and(|(name, _)| name.trim() == "Sui");
+        if !is_framework {
+            updated.push_str(line);
+            continue;
+        }
+        found = true;
+        anyhow::ensure!(
+            inline_rev.is_match(line),
+            "The `Sui` dependency is not pinned by `rev`"
+        );
+        updated.push_str(&inline_rev.replace(line, |captures: &regex::Captures| {
+            format!("{}\"{}\"", &captures[1], rev)
+        }));
+    }
+    if found {
+        return Ok(updated);
+    }
+
+    let dependency = format!(
+        "Sui = {{ git = \"{}\", subdir = \"{}\", rev = \"{}\" }}\n",
+        FRAMEWORK_GIT, FRAMEWORK_SUBDIR, rev
+    );
+    let mut with_dependency = String::with_capacity(updated.len() + dependency.len());
+    for line in updated.split_inclusive('\n') {
+        with_dependency.push_str(line);
+        if !inserted && line.trim_start().starts_with("[dependencies]") {
+            if !line.ends_with('\n') {
+                with_dependency.push('\n');
+            }
+            with_dependency.push_str(&dependency);
+            inserted = true;
+        }
+    }
+    if !inserted {
+        if !with_dependency.is_empty() && !with_dependency.ends_with('\n') {
+            with_dependency.push('\n');
+        }
+        with_dependency.push_str("\n[dependencies]\n");
+        with_dependency.push_str(&dependency);
+    }
+    Ok(with_dependency)
+}
+
+/// The `rev` the `Sui` framework dependency is pinned to, if it is declared with one.
+pub fn framework_rev(manifest: &toml::Table) -> Option<String> {
+    manifest
+        .get("dependencies")?
+        .get("Sui")?
+        .get("rev")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Replaces the quoted value of `key` in the `[section]` table, leaving every other line
+/// (including comments and spacing) untouched. Returns `None` if the key is not there.
+fn replace_field(contents: &str, section: &str, key: &str, value: &str) -> Option<String> {
+    let header = format!("[{}]", section);
+    let mut in_section = false;
+    let mut replaced = false;
+    let lines = contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') {
+                in_section = trimmed.starts_with(&header);
+                return line.to_string();
+            }
+            if !in_section || replaced {
+                return line.to_string();
+            }
+            let Some((name, current)) = line.split_once('=') else {
+                return line.to_string();
+            };
+            if name.trim() != key {
+                return line.to_string();
+            }
+            let Some(open) = current.find('"') else {
+                return line.to_string();
+            };
+            let Some(close) = current[open + 1..].find('"') else {
+                return line.to_string();
+            };
+            replaced = true;
+            format!(
+                "{}={}{}{}",
+                name,
+                &current[..=open],
+                value,
+                &current[open + 1 + close..]
+            )
+        })
+        .collect::<String>();
+    replaced.then_some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_dirs_lists_only_existing_layout_directories() {
+        let folder = std::env::temp_dir().join(format!("layout-{}", uuid::Uuid::new_v4()));
+        // No `sources`, code in `tests` and `examples`, and a non-standard `src` directory.
+        for dir in ["tests", "examples", "src"] {
+            std::fs::create_dir_all(folder.join(dir)).unwrap();
+        }
+        std::fs::write(folder.join("scripts"), "not a directory").unwrap();
+        std::fs::write(
+            folder.join("Move.toml"),
+            "[package]\nname = \"vault\"\nedition = \"2024\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            layout_dirs(&folder.to_string_lossy()),
+            vec!["tests", "examples"]
+        );
+
+        std::fs::create_dir_all(folder.join("sources")).unwrap();
+        assert_eq!(
+            layout_dirs(&folder.to_string_lossy()),
+            vec!["sources", "tests", "examples"]
+        );
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn published_id_only_reads_the_requested_env() {
+        let lockfile = toml::from_str::<toml::Table>(
+            r#"
+[move]
+version = 3
+
+[env.mainnet]
+chain-id = "35834a8a"
+original-published-id = "0x5a3c"
+latest-published-id = "0x5a3c"
+
+[env.localnet]
+chain-id = "f1b2c3d4"
+original-published-id = "0x0"
+latest-published-id = "0x0"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            published_id(Some(&lockfile), "mainnet").as_deref(),
+            Some("0x5a3c")
+        );
+        assert_eq!(published_id(Some(&lockfile), "testnet"), None);
+        assert_eq!(published_id(Some(&lockfile), "localnet"), None);
+        assert_eq!(published_id(None, "mainnet"), None);
+    }
+}
@@ -0,0 +1,60 @@
+/// `time` binary that reports the resource usage of the command it runs. The shell builtin
+/// of the same name does not report memory.
+const TIME_PATH: &str = "/usr/bin/time";
+
+/// Returns a command running `program` under `/usr/bin/time` with its resource usage report
+/// enabled, or why peak memory cannot be measured on this machine. Run it through
+/// [`crate::command::run`], which kills `time` and the measured command together.
+pub fn measured_command(program: &str) -> Result<tokio::process::Command, String> {
+    let flag = if cfg!(target_os = "linux") {
+        "-v"
+    } else if cfg!(target_os = "macos") {
+        "-l"
+    } else {
+        return Err(format!(
+            "Measuring memory is not supported on {}",
+            std::env::consts::OS
+        ));
+    };
+    if !std::path::Path::new(TIME_PATH).is_file() {
+        return Err(format!(
+            "`{}` is not installed (on Debian/Ubuntu, install the `time` package)",
+            TIME_PATH
+        ));
+    }
+    let mut cmd = tokio::process::Command::new(TIME_PATH);
+    cmd.arg(flag).arg(program);
+    Ok(cmd)
+}
+
+/// Reads the peak resident set size, in bytes, from the report `time` appends to stderr:
+/// `Maximum resident set size (kbytes): N` from GNU time, or `N  maximum resident set size`
+/// (in bytes) from BSD time. BusyBox and other variants without the report yield `None`.
+pub fn parse_peak_rss(stderr: &str) -> Option<u64> {
+    stderr.lines().rev().find_map(|line| {
+        let line = line.trim();
+        if let Some(kbytes) = line.strip_prefix("Maximum resident set size (kbytes):") {
+            return kbytes
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .map(|kbytes| kbytes * 1024);
+        }
+        line.strip_suffix("maximum resident set size")?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
+
+/// The signal that killed the command, from GNU time's `Command terminated by signal N`. A
+/// `SIGKILL` (9) during a large build usually means the kernel's OOM killer stepped in.
+pub fn terminating_signal(stderr: &str) -> Option<i32> {
+    stderr.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("Command terminated by signal ")?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
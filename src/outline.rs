@@ -0,0 +1,566 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct Outline {
+    pub modules: Vec<ModuleOutline>,
+    /// Set when the file could not be fully parsed; `modules` then holds everything read
+    /// before the error.
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ModuleOutline {
+    pub name: String,
+    pub span: Span,
+    pub functions: Vec<FunctionOutline>,
+    pub structs: Vec<StructOutline>,
+    pub constants: Vec<ConstantOutline>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FunctionOutline {
+    pub name: String,
+    pub visibility: String,
+    pub entry: bool,
+    pub span: Span,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StructOutline {
+    pub name: String,
+    /// `struct` or `enum`.
+    pub kind: String,
+    pub abilities: Vec<String>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConstantOutline {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub span: Span,
+}
+
+/// Parses the declarations of each module in a Move source file, without type checking or
+/// resolving anything, so it works on files that do not compile.
+pub fn parse(source: &str) -> Outline {
+    let (tokens, lex_error) = tokenize(source);
+    let mut parser = Parser {
+        source,
+        tokens: &tokens,
+        pos: 0,
+    };
+    let mut outline = Outline::default();
+    let parse_error = parser.file(&mut outline.modules).err();
+    outline.error = parse_error.or(lex_error);
+    outline
+}
+
+#[derive(Debug)]
+pub(crate) enum Kind {
+    /// Identifiers, keywords and numbers.
+    Word(String),
+    Punct(char),
+    Str,
+}
+
+#[derive(Debug)]
+pub(crate) struct Token {
+    pub(crate) kind: Kind,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// Byte offsets into the source.
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+/// Splits the source into tokens, skipping whitespace and comments. On an unterminated
+/// string or comment, returns the tokens before it along with the error.
+pub(crate) fn tokenize(source: &str) -> (Vec<Token>, Option<String>) {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    let (mut line, mut column) = (1, 1);
+
+    while let Some((start, c)) = chars.next() {
+        let (token_line, token_column) = (line, column);
+        let mut advance = |c: char| {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        };
+        advance(c);
+
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let kind = if c == '/' && chars.peek().is_some_and(|(_, next)| *next == '/') {
+            for (_, c) in chars.by_ref() {
+                advance(c);
+                if c == '\n' {
+                    break;
+                }
+            }
+            continue;
+        } else if c == '/' && chars.peek().is_some_and(|(_, next)| *next == '*') {
+            // The opening `*` cannot also close the comment, as in `/*/`.
+            if let Some((_, c)) = chars.next() {
+                advance(c);
+            }
+            // Block comments nest, as in the compiler's lexer.
+            let mut depth = 1;
+            let mut previous = ' ';
+            let mut closed = false;
+            for (_, c) in chars.by_ref() {
+                advance(c);
+                match (previous, c) {
+                    ('/', '*') => depth += 1,
+                    ('*', '/') => depth -= 1,
+                    _ => {
+                        previous = c;
+                        continue;
+                    }
+                }
+                if depth == 0 {
+                    closed = true;
+                    break;
+                }
+                // A delimiter's characters do not start another one, as in `/*/` or `*/*`.
+                previous = ' ';
+            }
+            if !closed {
+                let error = format!(
+                    "Unterminated block comment starting at {}:{}",
+                    token_line, token_column
+                );
+                return (tokens, Some(error));
+            }
+            continue;
+        } else if c == '"' {
+            let mut escaped = false;
+            let mut closed = false;
+            for (_, c) in chars.by_ref() {
+                advance(c);
+                if c == '"' && !escaped {
+                    closed = true;
+                    break;
+                }
+                escaped = c == '\\' && !escaped;
+            }
+            if !closed {
+                let error = format!(
+                    "Unterminated string starting at {}:{}",
+                    token_line, token_column
+                );
+                return (tokens, Some(error));
+            }
+            Kind::Str
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut word = c.to_string();
+            while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                advance(c);
+                word.push(c);
+            }
+            Kind::Word(word)
+        } else {
+            Kind::Punct(c)
+        };
+
+        let end = chars.peek().map_or(source.len(), |(index, _)| *index);
+        tokens.push(Token {
+            kind,
+            line: token_line,
+            column: token_column,
+            start,
+            end,
+        });
+    }
+
+    (tokens, None)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn advance(&mut self) -> Result<&'a Token, String> {
+        self.next()
+            .ok_or_else(|| "Unexpected end of file".to_string())
+    }
+
+    fn peek_word(&self) -> Option<&'a str> {
+        match &self.peek()?.kind {
+            Kind::Word(word) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn peek_punct(&self) -> Option<char> {
+        match self.peek()?.kind {
+            Kind::Punct(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Consumes a bracketed group starting at the current `(`, `[` or `{`, returning the
+    /// closing token.
+    fn group(&mut self) -> Result<&'a Token, String> {
+        let open = self.advance()?;
+        let mut stack = vec![open];
+        while let Some(token) = self.next() {
+            match token.kind {
+                Kind::Punct('(' | '[' | '{') => stack.push(token),
+                Kind::Punct(close @ (')' | ']' | '}')) => {
+                    let opened = stack.pop().ok_or("Unbalanced brackets")?;
+                    let expected = match opened.kind {
+                        Kind::Punct('(') => ')',
+                        Kind::Punct('[') => ']',
+                        _ => '}',
+                    };
+                    if close != expected {
+                        return Err(format!(
+                            "Expected `{}` at {}:{} to close {}:{}, found `{}`",
+                            expected, token.line, token.column, opened.line, opened.column, close
+                        ));
+                    }
+                    if stack.is_empty() {
+                        return Ok(token);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let opened = stack.last().copied().unwrap_or(open);
+        Err(format!(
+            "Unclosed bracket opened at {}:{}",
+            opened.line, opened.column
+        ))
+    }
+
+    /// Consumes tokens up to and including the next `;` or bracketed body outside of any
+    /// group, returning the last token consumed.
+    fn item_end(&mut self, stop_at_body: bool) -> Result<&'a Token, String> {
+        loop {
+            match self.peek_punct() {
+                Some('{') if stop_at_body => return self.group(),
+                Some('(' | '[' | '{') => {
+                    self.group()?;
+                }
+                Some(';') => return self.advance(),
+                Some('}') => {
+                    let token = self.advance()?;
+                    return Err(format!(
+                        "Unexpected `}}` at {}:{}",
+                        token.line, token.column
+                    ));
+                }
+                _ => {
+                    self.advance()?;
+                }
+            }
+        }
+    }
+
+    fn file(&mut self, modules: &mut Vec<ModuleOutline>) -> Result<(), String> {
+        while let Some(token) = self.peek() {
+            if self.peek_word() != Some("module") {
+                self.next();
+                continue;
+            }
+            self.next();
+
+            let mut name = String::new();
+            while let Some(token) = self.peek() {
+                match &token.kind {
+                    Kind::Word(word) => name.push_str(word),
+                    Kind::Punct(':') => name.push(':'),
+                    _ => break,
+                }
+                self.next();
+            }
+            let mut module = ModuleOutline {
+                name,
+                span: span(token, token),
+                functions: vec![],
+                structs: vec![],
+                constants: vec![],
+            };
+
+            // `module a::b;` declares a module spanning the rest of the file.
+            let label = match self.next() {
+                Some(Token {
+                    kind: Kind::Punct('{'),
+                    ..
+                }) => false,
+                Some(Token {
+                    kind: Kind::Punct(';'),
+                    ..
+                }) => true,
+                _ => {
+                    let error = format!("Expected `{{` after module `{}`", module.name);
+                    modules.push(module);
+                    return Err(error);
+                }
+            };
+
+            let result = self.module_body(&mut module, label);
+            modules.push(module);
+            result?;
+        }
+        Ok(())
+    }
+
+    fn module_body(&mut self, module: &mut ModuleOutline, label: bool) -> Result<(), String> {
+        let mut start = None;
+        let mut visibility = "private".to_string();
+        let mut entry = false;
+
+        loop {
+            let Some(token) = self.peek() else {
+                if let Some(last) = self.tokens.last() {
+                    module.span = span_between(module.span, last);
+                }
+                if label {
+                    return Ok(());
+                }
+                return Err(format!("Unclosed module `{}`", module.name));
+            };
+
+            match (&token.kind, self.peek_word()) {
+                (Kind::Punct('}'), _) if !label => {
+                    self.next();
+                    module.span = span_between(module.span, token);
+                    return Ok(());
+                }
+                (Kind::Punct('#'), _) => {
+                    self.next();
+                    if self.peek_punct() == Some('[') {
+                        self.group()?;
+                    }
+                    continue;
+                }
+                (_, Some("public")) => {
+                    start.get_or_insert(token);
+                    self.next();
+                    visibility = "public".to_string();
+                    if let Some(open) = self.peek().filter(|_| self.peek_punct() == Some('(')) {
+                        let close = self.group()?;
+                        visibility = self.source[open.end..close.start].trim().to_string();
+                    }
+                    continue;
+                }
+                (_, Some("entry")) => {
+                    start.get_or_insert(token);
+                    self.next();
+                    entry = true;
+                    continue;
+                }
+                (_, Some("native" | "macro")) => {
+                    start.get_or_insert(token);
+                    self.next();
+                    continue;
+                }
+                (_, Some("fun")) => {
+                    self.next();
+                    let name = self.peek_word().unwrap_or_default().to_string();
+                    let end = self.item_end(true)?;
+                    module.functions.push(FunctionOutline {
+                        name,
+                        visibility: visibility.clone(),
+                        entry,
+                        span: span(start.unwrap_or(token), end),
+                    });
+                }
+                (_, Some(kind @ ("struct" | "enum"))) => {
+                    self.next();
+                    let name = self.peek_word().unwrap_or_default().to_string();
+                    let mut abilities = vec![];
+                    let end = loop {
+                        match (self.peek_word(), self.peek_punct()) {
+                            (Some("has"), _) => {
+                                self.next();
+                                while let Some(ability) = self.peek_word() {
+                                    abilities.push(ability.to_string());
+                                    self.next();
+                                    if self.peek_punct() == Some(',') {
+                                        self.next();
+                                    }
+                                }
+                            }
+                            (_, Some('{')) => {
+                                let close = self.group()?;
+                                // Move 2024 allows abilities after the body.
+                                if self.peek_word() != Some("has") {
+                                    break close;
+                                }
+                            }
+                            (_, Some('(')) => {
+                                self.group()?;
+                            }
+                            (_, Some(';')) => break self.advance()?,
+                            (None, None) if self.peek().is_none() => {
+                                return Err(format!("Unterminated {} `{}`", kind, name));
+                            }
+                            _ => {
+                                self.next();
+                            }
+                        }
+                    };
+                    module.structs.push(StructOutline {
+                        name,
+                        kind: kind.to_string(),
+                        abilities,
+                        span: span(start.unwrap_or(token), end),
+                    });
+                }
+                (_, Some("const")) => {
+                    self.next();
+                    let name = self.peek_word().unwrap_or_default().to_string();
+                    self.next();
+                    let type_start = self.peek().map_or(self.source.len(), |token| token.end);
+                    while self.peek().is_some() && self.peek_punct() != Some('=') {
+                        self.next();
+                    }
+                    let type_end = self.peek().map_or(self.source.len(), |token| token.start);
+                    let end = self.item_end(false)?;
+                    module.constants.push(ConstantOutline {
+                        name,
+                        type_: self
+                            .source
+                            .get(type_start..type_end)
+                            .unwrap_or_default()
+                            .trim()
+                            .to_string(),
+                        span: span(start.unwrap_or(token), end),
+                    });
+                }
+                // `use`, `friend` and anything unrecognised
+                _ => {
+                    self.item_end(true)?;
+                }
+            }
+
+            start = None;
+            visibility = "private".to_string();
+            entry = false;
+        }
+    }
+}
+
+fn span(start: &Token, end: &Token) -> Span {
+    Span {
+        start_line: start.line,
+        start_column: start.column,
+        end_line: end.line,
+        end_column: end.column,
+    }
+}
+
+fn span_between(span: Span, end: &Token) -> Span {
+    Span {
+        end_line: end.line,
+        end_column: end.column,
+        ..span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(source: &str) -> Vec<String> {
+        let (tokens, error) = tokenize(source);
+        assert_eq!(error, None);
+        tokens
+            .into_iter()
+            .filter_map(|token| match token.kind {
+                Kind::Word(word) => Some(word),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        assert_eq!(words("a /* b /* c */ d */ e"), vec!["a", "e"]);
+        assert_eq!(words("a /** doc **/ b"), vec!["a", "b"]);
+        assert_eq!(words("a /**/ b"), vec!["a", "b"]);
+        assert_eq!(words("a /* b */* c */ d"), vec!["a", "c", "d"]);
+    }
+
+    #[test]
+    fn unterminated_block_comments_are_errors() {
+        // The opening `*` does not close the comment.
+        let (tokens, error) = tokenize("a /*/ b");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            error.as_deref(),
+            Some("Unterminated block comment starting at 1:3")
+        );
+
+        let (_, error) = tokenize("a\n/* b /* c */ d");
+        assert_eq!(
+            error.as_deref(),
+            Some("Unterminated block comment starting at 2:1")
+        );
+        assert!(tokenize("/*").1.is_some());
+    }
+
+    #[test]
+    fn byte_strings_hide_delimiters() {
+        let outline = parse(
+            r#"module vault::vault {
+    const MESSAGE: vector<u8> = b"} // /* \" {";
+    const HEX: vector<u8> = x"0aff";
+
+    public fun message(): vector<u8> { MESSAGE }
+}
+"#,
+        );
+        assert_eq!(outline.error, None);
+        let module = &outline.modules[0];
+        let constants = module
+            .constants
+            .iter()
+            .map(|constant| (constant.name.as_str(), constant.type_.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constants,
+            vec![("MESSAGE", "vector<u8>"), ("HEX", "vector<u8>")]
+        );
+        assert_eq!(module.functions[0].name, "message");
+        assert_eq!(module.span.end_line, 6);
+
+        let (_, error) = tokenize(r#"const E: vector<u8> = b"open \";"#);
+        assert_eq!(
+            error.as_deref(),
+            Some("Unterminated string starting at 1:24")
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Cloning or updating a git dependency.
+    Fetching,
+    /// Resolving and including a dependency in the build.
+    Compiling,
+    /// Compiling the package itself.
+    Building,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    pub action: Action,
+    /// Git URL of a fetched dependency, or the package name.
+    pub name: String,
+    /// Revision of a git dependency, from Move.toml.
+    pub rev: Option<String>,
+    /// Milliseconds since the command started.
+    pub at_ms: u64,
+}
+
+/// Reads a dependency resolution line printed by `sui move build`/`test`, e.g.
+/// `UPDATING GIT DEPENDENCY https://github.com/MystenLabs/sui.git`, `INCLUDING DEPENDENCY Sui`
+/// or `BUILDING my_package`.
+pub fn parse_line(line: &str) -> Option<(Action, String)> {
+    let line = strip_ansi_escapes::strip_str(line);
+    let line = line.trim();
+    [
+        ("UPDATING GIT DEPENDENCY", Action::Fetching),
+        ("FETCHING GIT DEPENDENCY", Action::Fetching),
+        ("INCLUDING DEPENDENCY", Action::Compiling),
+        ("BUILDING", Action::Building),
+    ]
+    .iter()
+    .find_map(|(prefix, action)| {
+        let name = line.strip_prefix(prefix)?;
+        name.starts_with(char::is_whitespace)
+            .then(|| (*action, name.trim().to_string()))
+    })
+}
+
+struct Run {
+    command: &'static str,
+    started: Instant,
+    last_output: Instant,
+    /// Set once the command exits.
+    duration: Option<Duration>,
+    /// Git URL to revision, for labelling fetches.
+    revisions: HashMap<String, String>,
+    steps: Vec<Step>,
+}
+
+/// Progress of the latest build or test command of a session, updated from the command's
+/// output while it runs so that a slow first dependency fetch can be told apart from a hang.
+#[derive(Default)]
+pub struct BuildProgress {
+    run: Mutex<Option<Run>>,
+}
+
+/// Marks the run finished when dropped, however the command ended.
+pub struct RunGuard<'a> {
+    progress: &'a BuildProgress,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(run) = self.progress.lock().as_mut() {
+            run.duration.get_or_insert_with(|| run.started.elapsed());
+        }
+    }
+}
+
+impl BuildProgress {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Run>> {
+        self.run.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Starts tracking a new run, replacing the previous one. `revisions` maps the git URLs of
+    /// dependencies to their revisions.
+    pub fn start(&self, command: &'static str, revisions: HashMap<String, String>) -> RunGuard<'_> {
+        let now = Instant::now();
+        *self.lock() = Some(Run {
+            command,
+            started: now,
+            last_output: now,
+            duration: None,
+            revisions,
+            steps: vec![],
+        });
+        RunGuard { progress: self }
+    }
+
+    /// Records a line of the running command's output.
+    pub fn observe(&self, line: &str) {
+        let mut run = self.lock();
+        let Some(run) = run.as_mut() else {
+            return;
+        };
+        run.last_output = Instant::now();
+        if let Some((action, name)) = parse_line(line) {
+            let rev = match action {
+                Action::Fetching => run.revisions.get(&name).cloned(),
+                _ => None,
+            };
+            run.steps.push(Step {
+                action,
+                name,
+                rev,
+                at_ms: run.started.elapsed().as_millis() as u64,
+            });
+        }
+    }
+
+    pub fn snapshot(&self) -> serde_json::Value {
+        let run = self.lock();
+        let Some(run) = run.as_ref() else {
+            return serde_json::json!({
+                "running": false,
+                "steps": [],
+            });
+        };
+        let names = |action| {
+            run.steps
+                .iter()
+                .filter(|step| step.action == action)
+                .map(|step| step.name.clone())
+                .collect::<Vec<_>>()
+        };
+        serde_json::json!({
+            "running": run.duration.is_none(),
+            "command": run.command,
+            "elapsedMs": run.duration.unwrap_or_else(|| run.started.elapsed()).as_millis() as u64,
+            "sinceLastOutputMs": run
+                .duration
+                .is_none()
+                .then(|| run.last_output.elapsed().as_millis() as u64),
+            "lastStep": run.steps.last(),
+            "fetched": names(Action::Fetching),
+            "compiled": names(Action::Compiling),
+            "steps": run.steps,
+        })
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{type_layout, validate};
+
+/// A reusable programmable transaction block, instantiated into `sui client ptb` arguments.
+pub struct Template {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// JSON schema of the template's parameters.
+    pub parameters: fn() -> serde_json::Value,
+    build: fn(&serde_json::Value) -> Result<Vec<String>, String>,
+}
+
+impl Template {
+    /// Validates `params` and builds the `sui client ptb` arguments, without `--gas-budget`.
+    pub fn build(&self, params: &serde_json::Value) -> Result<Vec<String>, String> {
+        (self.build)(params)
+    }
+}
+
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "transfer",
+        description: "Transfers one or more objects to a recipient",
+        parameters: || {
+            serde_json::json!({
+                "type": "object",
+                "required": ["objects", "recipient"],
+                "properties": {
+                    "objects": { "type": "array", "items": { "type": "string" }, "minItems": 1, "description": "IDs of the objects to transfer" },
+                    "recipient": { "type": "string", "description": "Recipient address" },
+                },
+            })
+        },
+        build: build_transfer,
+    },
+    Template {
+        name: "split_and_transfer",
+        description: "Splits amounts off a coin (the gas coin by default) and sends each to a recipient, or all of them to a single recipient",
+        parameters: || {
+            serde_json::json!({
+                "type": "object",
+                "required": ["amounts", "recipients"],
+                "properties": {
+                    "coin": { "type": "string", "description": "Coin object ID to split, or `gas` (default)" },
+                    "amounts": { "type": "array", "items": { "type": "integer", "minimum": 1 }, "minItems": 1, "description": "Amounts to split off, in the coin's base unit" },
+                    "recipients": { "type": "array", "items": { "type": "string" }, "minItems": 1, "description": "One recipient per amount, or a single recipient for all" },
+                },
+            })
+        },
+        build: build_split_and_transfer,
+    },
+    Template {
+        name: "move_call_chain",
+        description: "Runs Move calls in order, where arguments can use the results of earlier calls (`r0` for the first call's result, `r0.1` for its second return value), optionally transferring the last result",
+        parameters: || {
+            serde_json::json!({
+                "type": "object",
+                "required": ["calls"],
+                "properties": {
+                    "calls": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "required": ["target"],
+                            "properties": {
+                                "target": { "type": "string", "description": "`0xPACKAGE::module::function`" },
+                                "type_args": { "type": "array", "items": { "type": "string" } },
+                                "args": { "type": "array", "items": { "type": "string" }, "description": "PTB values: `@0x..` for objects and addresses, `5u64`, `true`, `\"text\"`, `vector[..]`, `gas`, or `rN`/`rN.M` for earlier results" },
+                            },
+                        },
+                    },
+                    "transfer_result_to": { "type": "string", "description": "Address to send the last call's result to" },
+                },
+            })
+        },
+        build: build_move_call_chain,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|template| template.name == name)
+}
+
+fn build_transfer(params: &serde_json::Value) -> Result<Vec<String>, String> {
+    let objects = strings(params, "objects")?
+        .iter()
+        .map(|object| object_ref("objects", object))
+        .collect::<Result<Vec<_>, _>>()?;
+    if objects.is_empty() {
+        return Err("`objects` must not be empty".to_string());
+    }
+    let recipient = address_ref("recipient", string(params, "recipient")?)?;
+    Ok(vec![
+        "--transfer-objects".to_string(),
+        format!("[{}]", objects.join(", ")),
+        recipient,
+    ])
+}
+
+fn build_split_and_transfer(params: &serde_json::Value) -> Result<Vec<String>, String> {
+    let coin = match params.get("coin").and_then(|coin| coin.as_str()) {
+        None | Some("gas") => "gas".to_string(),
+        Some(coin) => object_ref("coin", coin)?,
+    };
+    let amounts = params["amounts"]
+        .as_array()
+        .ok_or("`amounts` must be an array of integers")?
+        .iter()
+        .map(|amount| {
+            amount
+                .as_u64()
+                .filter(|amount| *amount > 0)
+                .ok_or_else(|| format!("`amounts` must be positive integers, got `{}`", amount))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let recipients = strings(params, "recipients")?
+        .iter()
+        .map(|recipient| address_ref("recipients", recipient))
+        .collect::<Result<Vec<_>, _>>()?;
+    if amounts.is_empty() || recipients.is_empty() {
+        return Err("`amounts` and `recipients` must not be empty".to_string());
+    }
+    if recipients.len() != 1 && recipients.len() != amounts.len() {
+        return Err(format!(
+            "Expected one recipient or one per amount ({}), got {}",
+            amounts.len(),
+            recipients.len()
+        ));
+    }
+
+    let amounts = amounts.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    let mut args = vec![
+        "--split-coins".to_string(),
+        coin,
+        format!("[{}]", amounts.join(", ")),
+        "--assign".to_string(),
+        "coins".to_string(),
+    ];
+    if let [recipient] = recipients.as_slice() {
+        let coins = (0..amounts.len())
+            .map(|index| format!("coins.{}", index))
+            .collect::<Vec<_>>();
+        args.extend([
+            "--transfer-objects".to_string(),
+            format!("[{}]", coins.join(", ")),
+            recipient.clone(),
+        ]);
+    } else {
+        for (index, recipient) in recipients.into_iter().enumerate() {
+            args.extend([
+                "--transfer-objects".to_string(),
+                format!("[coins.{}]", index),
+                recipient,
+            ]);
+        }
+    }
+    Ok(args)
+}
+
+fn build_move_call_chain(params: &serde_json::Value) -> Result<Vec<String>, String> {
+    let calls = params["calls"]
+        .as_array()
+        .filter(|calls| !calls.is_empty())
+        .ok_or("`calls` must be a non-empty array")?;
+    let result_ref = regex::Regex::new(r"\br(\d+)(\.\d+)?\b").expect("valid regex");
+
+    let mut args = vec![];
+    for (index, call) in calls.iter().enumerate() {
+        let target = string(call, "target")?;
+        let parts = target.split("::").collect::<Vec<_>>();
+        let valid_target = matches!(parts.as_slice(), [package, module, function]
+            if type_layout::normalize_address(package).is_some()
+                && validate::identifier("module", module).is_ok()
+                && validate::identifier("function", function).is_ok());
+        if !valid_target {
+            return Err(format!(
+                "`calls[{}].target` must be `0xPACKAGE::module::function`, got `{}`",
+                index, target
+            ));
+        }
+        args.push("--move-call".to_string());
+        args.push(target.to_string());
+
+        let type_args = optional_strings(call, "type_args")?;
+        for type_arg in &type_args {
+            type_layout::parse(type_arg)
+                .map_err(|e| format!("`calls[{}].type_args`: {}", index, e))?;
+        }
+        if !type_args.is_empty() {
+            args.push(format!("<{}>", type_args.join(", ")));
+        }
+
+        for arg in optional_strings(call, "args")? {
+            // `sui client ptb` would read it as one of its own options.
+            if arg.starts_with('-') {
+                return Err(format!(
+                    "`calls[{}].args` cannot start with `-`, got `{}`",
+                    index, arg
+                ));
+            }
+            for reference in result_ref.captures_iter(&arg) {
+                let earlier = reference[1].parse::<usize>().map_err(|e| e.to_string())?;
+                if earlier >= index {
+                    return Err(format!(
+                        "`calls[{}]` uses `{}`, but only results of earlier calls are available",
+                        index, &reference[0]
+                    ));
+                }
+            }
+            args.push(arg);
+        }
+        args.push("--assign".to_string());
+        args.push(format!("r{}", index));
+    }
+
+    if let Some(recipient) = params.get("transfer_result_to") {
+        let recipient = recipient
+            .as_str()
+            .ok_or("`transfer_result_to` must be an address")?;
+        args.extend([
+            "--transfer-objects".to_string(),
+            format!("[r{}]", calls.len() - 1),
+            address_ref("transfer_result_to", recipient)?,
+        ]);
+    }
+    Ok(args)
+}
+
+fn string<'a>(params: &'a serde_json::Value, name: &str) -> Result<&'a str, String> {
+    params[name]
+        .as_str()
+        .ok_or_else(|| format!("`{}` must be a string", name))
+}
+
+fn strings(params: &serde_json::Value, name: &str) -> Result<Vec<String>, String> {
+    params[name]
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| format!("`{}` must be an array of strings", name))
+}
+
+fn optional_strings(params: &serde_json::Value, name: &str) -> Result<Vec<String>, String> {
+    match params.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(vec![]),
+        Some(_) => strings(params, name),
+    }
+}
+
+fn object_ref(name: &str, id: &str) -> Result<String, String> {
+    type_layout::normalize_address(id)
+        .map(|id| format!("@{}", id))
+        .ok_or_else(|| {
+            format!(
+                "`{}` must contain 0x-prefixed object IDs, got `{}`",
+                name, id
+            )
+        })
+}
+
+fn address_ref(name: &str, address: &str) -> Result<String, String> {
+    type_layout::normalize_address(address)
+        .map(|address| format!("@{}", address))
+        .ok_or_else(|| {
+            format!(
+                "`{}` must be a 0x-prefixed address, got `{}`",
+                name, address
+            )
+        })
+}
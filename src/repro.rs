@@ -0,0 +1,149 @@
+use crate::outline::{self, Kind, Span};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Repro {
+    pub module: String,
+    /// The function, struct or constant containing the requested line.
+    pub item: String,
+    /// Items of the module the snippet keeps, in source order.
+    pub included: Vec<String>,
+    /// The module reduced to its `use` declarations and the included items.
+    pub snippet: String,
+}
+
+struct Item<'a> {
+    kind: &'static str,
+    name: &'a str,
+    span: Span,
+}
+
+/// Cuts the module around `line` down to the item containing it, the other items of the
+/// module it refers to (transitively), and the `use` declarations those need. References are
+/// found by name without resolving anything, so the snippet may keep more than necessary.
+pub fn extract(source: &str, line: usize) -> Result<Repro, String> {
+    let parsed = outline::parse(source);
+    let module = parsed
+        .modules
+        .iter()
+        .find(|module| module.span.start_line <= line && line <= module.span.end_line)
+        .ok_or_else(|| format!("Line {} is not inside a module", line))?;
+    let items = module
+        .functions
+        .iter()
+        .map(|function| Item {
+            kind: "function",
+            name: &function.name,
+            span: function.span,
+        })
+        .chain(module.structs.iter().map(|item| Item {
+            kind: "struct",
+            name: &item.name,
+            span: item.span,
+        }))
+        .chain(module.constants.iter().map(|constant| Item {
+            kind: "constant",
+            name: &constant.name,
+            span: constant.span,
+        }))
+        .collect::<Vec<_>>();
+    let target = items
+        .iter()
+        .position(|item| item.span.start_line <= line && line <= item.span.end_line)
+        .ok_or_else(|| {
+            format!(
+                "Line {} of module `{}` is not inside a function, struct or constant",
+                line, module.name
+            )
+        })?;
+
+    let lines = source.lines().collect::<Vec<_>>();
+    let text = |start: usize, end: usize| lines[start - 1..end.min(lines.len())].join("\n");
+
+    let mut included = BTreeSet::from([target]);
+    let mut pending = vec![target];
+    let mut referenced = BTreeSet::new();
+    while let Some(index) = pending.pop() {
+        let span = items[index].span;
+        let names = words(&text(span.start_line, span.end_line));
+        for (other, item) in items.iter().enumerate() {
+            if names.contains(item.name) && included.insert(other) {
+                pending.push(other);
+            }
+        }
+        referenced.extend(names);
+    }
+
+    // Module-level `use` declarations importing any name the kept items refer to.
+    let inside_item = |number: usize| {
+        items
+            .iter()
+            .any(|item| item.span.start_line <= number && number <= item.span.end_line)
+    };
+    let uses = (module.span.start_line + 1..module.span.end_line)
+        .filter(|number| !inside_item(*number))
+        .filter_map(|number| Some((number, *lines.get(number - 1)?)))
+        .filter(|(_, line)| line.trim_start().starts_with("use "))
+        .filter(|(_, line)| {
+            words(line)
+                .iter()
+                .any(|word| *word != "use" && referenced.contains(word))
+        })
+        .map(|(_, line)| line.to_string())
+        .collect::<Vec<_>>();
+
+    let mut ordered = included
+        .iter()
+        .map(|index| &items[*index])
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|item| item.span.start_line);
+    let mut snippet = format!("module {} {{\n", module.name);
+    for line in &uses {
+        snippet.push_str(line);
+        snippet.push('\n');
+    }
+    for item in &ordered {
+        snippet.push('\n');
+        let start = with_attributes(&lines, item.span.start_line);
+        snippet.push_str(&text(start, item.span.end_line));
+        snippet.push('\n');
+    }
+    snippet.push_str("}\n");
+
+    Ok(Repro {
+        module: module.name.clone(),
+        item: format!("{} {}", items[target].kind, items[target].name),
+        included: ordered
+            .iter()
+            .map(|item| format!("{} {}", item.kind, item.name))
+            .collect(),
+        snippet,
+    })
+}
+
+/// Identifiers in a piece of source, ignoring comments and strings.
+fn words(source: &str) -> BTreeSet<String> {
+    let (tokens, _) = outline::tokenize(source);
+    tokens
+        .into_iter()
+        .filter_map(|token| match token.kind {
+            Kind::Word(word) => Some(word),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Moves an item's first line up over the attributes and doc comments directly above it.
+fn with_attributes(lines: &[&str], start_line: usize) -> usize {
+    let mut start = start_line;
+    while start > 1 {
+        let above = lines[start - 2].trim_start();
+        if !(above.starts_with("#[") || above.starts_with("///")) {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Wait before the first retry, doubled for each one after it.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Error messages of failures worth retrying: the request never got a usable answer from the
+/// network, rather than being rejected.
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "broken pipe",
+    "error sending request",
+    "dns error",
+    "could not resolve host",
+    "failed to connect",
+    "temporarily unavailable",
+    "service unavailable",
+    "bad gateway",
+    "too many requests",
+    // git, while fetching dependencies
+    "unable to access",
+    "early eof",
+    "the remote end hung up",
+];
+
+/// Error messages of failures that happened before the request was sent, so that even a
+/// request that is not idempotent, such as a publish, is safe to repeat.
+const UNSENT_PATTERNS: &[&str] = &[
+    "connection refused",
+    "dns error",
+    "could not resolve host",
+    "failed to connect",
+    // git, while fetching dependencies before building
+    "unable to access",
+    "early eof",
+    "the remote end hung up",
+];
+
+/// Whether an error message describes a transient network failure.
+pub fn is_transient(message: &str) -> bool {
+    matches_error(message, TRANSIENT_PATTERNS)
+}
+
+/// Whether an error message describes a network failure from before the request was sent.
+pub fn is_unsent(message: &str) -> bool {
+    matches_error(message, UNSENT_PATTERNS)
+}
+
+/// Matches `patterns` against the error lines of `message` only, so that logs or source
+/// snippets printed around the error cannot make it look transient. Without a line
+/// mentioning an error, the last line is taken as the error.
+fn matches_error(message: &str, patterns: &[&str]) -> bool {
+    let message = message.to_ascii_lowercase();
+    let lines = message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let is_error = |line: &str| line.contains("error") || line.starts_with("fatal:");
+    let matches = |line: &str| patterns.iter().any(|pattern| line.contains(pattern));
+    if lines.iter().any(|line| is_error(line)) {
+        lines
+            .iter()
+            .filter(|line| is_error(line))
+            .any(|line| matches(line))
+    } else {
+        lines.last().is_some_and(|line| matches(line))
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails with an error that is not `retryable`, or `retries`
+/// retries have been made, backing off exponentially in between. Returns the last result and the
+/// number of attempts made. Cancelling `ct` stops waiting and returns the last error.
+pub async fn with_retries<T, F, Fut>(
+    label: &str,
+    retries: u32,
+    retryable: fn(&str) -> bool,
+    ct: &CancellationToken,
+    mut attempt: F,
+) -> (anyhow::Result<T>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let mut attempts = 0;
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        attempts += 1;
+        let result = attempt().await;
+        let error = match &result {
+            Err(e) if attempts <= retries && retryable(&format!("{:#}", e)) => e,
+            _ => return (result, attempts),
+        };
+        tracing::warn!(
+            label,
+            attempts,
+            ?backoff,
+            "retrying after transient error: {:#}",
+            error
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = ct.cancelled() => return (result, attempts),
+        }
+        backoff *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_error_lines_are_matched() {
+        let log = "INFO fetching with a 30s timeout\nError: insufficient gas";
+        assert!(!is_transient(log));
+        assert!(is_transient("Compiling\nError: request timed out"));
+        assert!(is_transient(
+            "fatal: unable to access 'https://github.com/'"
+        ));
+        // Without an error line, the last line is the error.
+        assert!(is_transient("Fetching\nconnection reset by peer"));
+        assert!(!is_transient("connection reset by peer\nAborted"));
+    }
+
+    #[test]
+    fn timeouts_may_have_been_sent() {
+        assert!(is_transient("Error: request timed out"));
+        assert!(!is_unsent("Error: request timed out"));
+        assert!(is_unsent("Error: error sending request: dns error"));
+        assert!(is_unsent("Error: Connection refused (os error 111)"));
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::Context;
+use tokio_util::sync::CancellationToken;
+
+/// Sends a JSON-RPC request to a Sui fullnode and returns its `result`.
+pub async fn call(
+    url: &str,
+    method: &str,
+    params: serde_json::Value,
+    ct: &CancellationToken,
+) -> anyhow::Result<serde_json::Value> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let send = async {
+        reqwest::Client::new()
+            .post(url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await
+    };
+    let mut response = tokio::select! {
+        response = send => {
+            response.with_context(|| format!("`{}` request to {} failed", method, url))?
+        }
+        _ = ct.cancelled() => anyhow::bail!("Request cancelled"),
+    };
+
+    if let Some(error) = response.get("error") {
+        anyhow::bail!(
+            "`{}` failed: {}",
+            method,
+            error["message"].as_str().unwrap_or("unknown error")
+        );
+    }
+    Ok(response["result"].take())
+}
+
+/// Decodes standard (RFC 4648) base64, as used for BCS bytes in RPC responses.
+pub fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
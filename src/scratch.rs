@@ -0,0 +1,74 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Temporary directory owned by one session, for temporary builds and other scratch files.
+/// Created under the system temp directory on first use and removed with everything in it
+/// when the session's service is dropped, i.e. when the client disconnects.
+#[derive(Debug, Default)]
+pub struct ScratchDir {
+    root: Mutex<Option<PathBuf>>,
+}
+
+impl ScratchDir {
+    /// Returns the session's directory, creating it on first use.
+    pub fn root(&self) -> std::io::Result<PathBuf> {
+        let mut root = self.root.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(root) = root.as_ref() {
+            return Ok(root.clone());
+        }
+        let path = std::env::temp_dir().join(format!("sui-dev-mcp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path)?;
+        tracing::debug!(path = %path.display(), "created scratch directory");
+        *root = Some(path.clone());
+        Ok(path)
+    }
+
+    /// Creates a new, empty directory named `{prefix}-{uuid}` inside the scratch directory.
+    pub fn create_dir(&self, prefix: &str) -> std::io::Result<PathBuf> {
+        let path = self
+            .root()?
+            .join(format!("{}-{}", prefix, uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path)?;
+        Ok(path)
+    }
+
+    /// Copies the package at `folder` into a new scratch directory and returns the copy's
+    /// path. The `build` output and hidden directories are left out, and local dependencies
+    /// given as relative paths will not resolve from the copy.
+    pub fn copy_package(&self, folder: &Path) -> std::io::Result<PathBuf> {
+        let target = self.create_dir("package")?;
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(relative) = dirs.pop() {
+            for entry in std::fs::read_dir(folder.join(&relative))? {
+                let entry = entry?;
+                let name = entry.file_name();
+                let path = relative.join(&name);
+                if entry.file_type()?.is_dir() {
+                    let skipped = name.to_string_lossy().starts_with('.')
+                        || (relative.as_os_str().is_empty() && name == "build");
+                    if !skipped {
+                        std::fs::create_dir(target.join(&path))?;
+                        dirs.push(path);
+                    }
+                } else {
+                    std::fs::copy(entry.path(), target.join(&path))?;
+                }
+            }
+        }
+        Ok(target)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let root = self.root.get_mut().unwrap_or_else(|e| e.into_inner());
+        let Some(root) = root.take() else {
+            return;
+        };
+        if let Err(e) = std::fs::remove_dir_all(&root) {
+            tracing::warn!(path = %root.display(), "failed to remove scratch directory: {}", e);
+        }
+    }
+}
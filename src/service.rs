@@ -1,114 +1,4699 @@
+use crate::{
+    addresses,
+    analyzer::{self, HoverError},
+    client,
+    command::{self, Completion},
+    compatibility,
+    config::Config,
+    diff, interface,
+    localnet::Localnet,
+    locks::PackageLocks,
+    manifest, memory, outline,
+    progress::{self, BuildProgress},
+    ptb, repro, retry, rpc,
+    scratch::ScratchDir,
+    source_verification, sources,
+    stats::Connection,
+    test_output::{self, FailureKind},
+    type_layout, units, validate,
+};
 use rmcp::{
+    handler::server::tool::ToolCallContext,
     model::{
-        CallToolResult, Content, Implementation, InitializeRequestParam, InitializeResult,
-        ProtocolVersion, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, Implementation, InitializeRequestParam,
+        InitializeResult, ListToolsResult, PaginatedRequestParam, ProtocolVersion,
+        ServerCapabilities, ServerInfo,
     },
+    schemars,
     service::RequestContext,
     tool,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path, sync::Arc};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BootstrapRequest {
+    #[schemars(description = "Gas budget in MIST for each transaction")]
+    pub gas_budget: Option<u64>,
+    #[schemars(
+        description = "Module of the function to call after publishing. Requires `function`"
+    )]
+    pub module: Option<String>,
+    #[schemars(description = "Function to call after publishing. Requires `module`")]
+    pub function: Option<String>,
+    #[schemars(description = "Arguments for the initial call")]
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[schemars(description = "Type arguments for the initial call")]
+    #[serde(default)]
+    pub type_args: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct EstimateGasRequest {
+    #[schemars(
+        description = "Package to call. Defaults to the project's published package on the active network"
+    )]
+    pub package_id: Option<String>,
+    #[schemars(description = "Module of the function")]
+    pub module: String,
+    #[schemars(description = "Function to dry-run")]
+    pub function: String,
+    #[schemars(description = "Representative argument values")]
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[schemars(description = "Type arguments")]
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    #[schemars(description = "Gas budget in MIST for the simulated transaction")]
+    pub gas_budget: Option<u64>,
+}
+
+/// Tools that write project files or change chain or localnet state, disabled in read-only
+/// mode.
+const MUTATING_TOOLS: &[&str] = &[
+    "format_project",
+    "format_and_validate",
+    "validate_changed",
+    "start_localnet",
+    "stop_localnet",
+    "publish_project",
+    "bootstrap",
+    "clear_build_cache",
+    "upgrade_project",
+    "generate_address",
+    "import_key",
+    "bump_version",
+    "format_staged",
+];
+
+/// Move editions `check_editions` can build under.
+const SUPPORTED_EDITIONS: &[&str] = &["legacy", "2024.alpha", "2024.beta", "2024"];
+
+/// Most framework revisions `check_framework_revs` builds against in one call; each one not
+/// yet cached fetches the Sui repository.
+const MAX_FRAMEWORK_REVS: usize = 5;
+
+const MAX_SEARCH_PATTERN_LEN: usize = 1000;
+
+/// Reserved argument, accepted by every tool, carrying the client's timeout in milliseconds.
+const DEADLINE_ARGUMENT: &str = "_timeoutMs";
+
+/// How `validate_project` lays out its diagnostics.
+#[derive(Clone, Copy, Debug)]
+enum DiagnosticsLayout {
+    /// `warnings` and `buildErrors` arrays.
+    Flat,
+    /// `diagnosticsByFile`: `{ file: { warnings, errors } }`.
+    Grouped,
+    /// `publishDiagnostics`: LSP `textDocument/publishDiagnostics` params per file.
+    Lsp,
+    /// Flat diagnostics, with `testResults` as a JUnit XML report.
+    Junit,
+}
+
+/// Held while a command reads or writes the package's build output; see `lock_package`.
+type PackageGuard = tokio::sync::OwnedMutexGuard<()>;
+
+struct BuildOutput {
+    warnings: HashMap<LineNotice, String>,
+    errors: HashMap<LineNotice, String>,
+    /// The build was killed by the command timeout, so the diagnostics may be incomplete.
+    timed_out: bool,
+    /// Output of the post-build hook, when it ran and failed.
+    hook_failure: Option<serde_json::Value>,
+}
+
+struct TestOutput {
+    stdout: String,
+    stderr: String,
+    /// The run was killed by the command timeout, so the output may be incomplete.
+    timed_out: bool,
+}
+
+struct LastValidation {
+    finished_at: std::time::SystemTime,
+    /// Hash of the sources right after validating, to tell whether they have changed since.
+    source_hash: Option<String>,
+    success: bool,
+    body: serde_json::Value,
+}
 
 #[derive(Clone)]
 pub struct SuiService {
-    project_folder: String,
-    movefmt_cmd: String,
+    config: Arc<Config>,
+    localnet: Localnet,
+    package_locks: Arc<PackageLocks>,
+    /// Build/test summary from the last `status` call, keyed by the source hash it was
+    /// computed for.
+    status_cache: Arc<Mutex<Option<(String, serde_json::Value)>>>,
+    /// Output of `sui --version`, looked up on first use.
+    sui_version: Arc<tokio::sync::OnceCell<Option<String>>>,
+    /// Counts this session as an active connection until the last clone is dropped.
+    connection: Arc<Connection>,
+    /// Source hash of each module as of the last `validate_project` call.
+    module_hashes: Arc<Mutex<Option<HashMap<String, String>>>>,
+    /// Result of the last `validate_project`/`format_and_validate` call in this session.
+    last_validation: Arc<Mutex<Option<LastValidation>>>,
+    /// Last completed validation, keyed by the source hash and options it ran with.
+    validation_cache: Arc<Mutex<Option<(String, (bool, serde_json::Value))>>>,
+    /// Temporary files of this session, removed when it ends.
+    scratch: Arc<ScratchDir>,
+    /// Dependency fetch/compile progress of the session's latest build or test run.
+    build_progress: Arc<BuildProgress>,
+    /// Tests that failed or timed out in the latest `validate_project` or `rerun_failed` run,
+    /// or `None` before any tests ran.
+    failed_tests: Arc<Mutex<Option<Vec<String>>>>,
 }
 
 #[tool(tool_box)]
 impl SuiService {
-    pub fn new(project_folder: &str, movefmt_cmd: &str) -> Self {
+    pub fn new(
+        config: Arc<Config>,
+        localnet: Localnet,
+        package_locks: Arc<PackageLocks>,
+        connection: Connection,
+    ) -> Self {
         Self {
-            project_folder: project_folder.to_string(),
-            movefmt_cmd: movefmt_cmd.to_string(),
+            config,
+            localnet,
+            package_locks,
+            status_cache: Default::default(),
+            sui_version: Default::default(),
+            connection: Arc::new(connection),
+            module_hashes: Default::default(),
+            last_validation: Default::default(),
+            validation_cache: Default::default(),
+            scratch: Default::default(),
+            build_progress: Default::default(),
+            failed_tests: Default::default(),
         }
     }
 
     #[tool(description = "Format project")]
-    async fn format_project(&self) -> Result<CallToolResult, rmcp::Error> {
-        let mut cmd = build_fmt_command(&self.movefmt_cmd);
-        cmd.arg(&format!("{}/sources", &self.project_folder))
-            .output()
-            .map_err(|e| {
-                rmcp::Error::internal_error(
-                    format!("Failed to run formatter on `sources`: {}", e),
+    async fn format_project(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Glob patterns relative to the project folder (e.g. `sources/token/*.move`) selecting the files to format. Formats the whole project when empty"
+        )]
+        patterns: Option<Vec<String>>,
+        #[tool(param)]
+        #[schemars(
+            description = "Name of a server-configured formatter (from `FORMATTERS`) to use instead of the default, e.g. a version-pinned movefmt"
+        )]
+        formatter: Option<String>,
+        #[tool(param)]
+        #[schemars(
+            description = "Leave the files untouched and return the changes as a git-style unified diff (`patch`, plus the hunks of each file under `patches`) for the client to apply"
+        )]
+        patch: Option<bool>,
+        #[tool(param)]
+        #[schemars(
+            description = "Package directory relative to the project folder (e.g. `packages/token` in a monorepo). Defaults to the project folder"
+        )]
+        package: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let patterns = patterns.unwrap_or_default();
+        let patch = patch.unwrap_or(false);
+        let (success, body) = self
+            .for_package(package.as_deref())?
+            .format(&patterns, formatter.as_deref(), patch, patch, &context.ct)
+            .await?;
+        json_result(success, body)
+    }
+
+    #[tool(description = "Builds the project and runs tests")]
+    async fn validate_project(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Treat build warnings as failures")]
+        strict: Option<bool>,
+        #[tool(param)]
+        #[schemars(
+            description = "Maximum gas (instructions) each test may use, so runaway loops fail fast"
+        )]
+        gas_limit: Option<u64>,
+        #[tool(param)]
+        #[schemars(
+            description = "`json` (default) for flat `warnings`/`buildErrors` arrays, `grouped` for `diagnosticsByFile` (`{ file: { warnings, errors } }`, in line order), `lsp` for a `publishDiagnostics` list of `{ uri, diagnostics }` per file, or `junit` for flat diagnostics with `testResults` as a JUnit XML report including per-test timings"
+        )]
+        format: Option<String>,
+        #[tool(param)]
+        #[schemars(
+            description = "Rebuild and rerun the tests even if the sources are unchanged since the last validation"
+        )]
+        no_cache: Option<bool>,
+        #[tool(param)]
+        #[schemars(
+            description = "Also return `details`: each diagnostic's labels (e.g. the expected and given types of a type error) and notes, parsed out of the rendered diagnostic"
+        )]
+        verbose: Option<bool>,
+        #[tool(param)]
+        #[schemars(
+            description = "Package directory relative to the project folder (e.g. `packages/token` in a monorepo). Defaults to the project folder"
+        )]
+        package: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let layout = match format.as_deref() {
+            None | Some("json") => DiagnosticsLayout::Flat,
+            Some("grouped") => DiagnosticsLayout::Grouped,
+            Some("lsp") => DiagnosticsLayout::Lsp,
+            Some("junit") => DiagnosticsLayout::Junit,
+            Some(other) => {
+                return Err(rmcp::Error::invalid_params(
+                    format!(
+                        "`format` must be `json`, `grouped`, `lsp` or `junit`, got `{}`",
+                        other
+                    ),
                     None,
+                ));
+            }
+        };
+        let (success, body) = self
+            .for_package(package.as_deref())?
+            .validate(
+                strict.unwrap_or(false),
+                gas_limit,
+                layout,
+                verbose.unwrap_or(false),
+                !no_cache.unwrap_or(false),
+                &context.ct,
+            )
+            .await?;
+        json_result(success, body)
+    }
+
+    #[tool(
+        description = "Builds the project and returns the public interface (functions, structs and constants) of each compiled module"
+    )]
+    async fn get_package_interface(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let manifest = manifest::read_manifest(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let package_name = manifest::package_name(&manifest)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        // Held until the bytecode has been read, so another build cannot replace it.
+        let guard = self.lock_package(&context.ct).await?;
+        let BuildOutput {
+            errors: build_errors,
+            timed_out,
+            ..
+        } = self.build_locked(&guard, false, &context.ct).await?;
+
+        if timed_out || !build_errors.is_empty() {
+            let body = serde_json::json!({
+                "buildErrors": build_errors.values().collect::<Vec<_>>(),
+                "modules": null,
+                "timedOut": timed_out
+            });
+            let out = Content::json(body)?;
+            return Ok(CallToolResult::success(vec![out]));
+        }
+
+        let bytecode_dir = Path::new(&self.config.project_folder)
+            .join("build")
+            .join(package_name)
+            .join("bytecode_modules");
+        let modules = self.disassemble_dir(&bytecode_dir, &context.ct).await?;
+
+        let body = serde_json::json!({
+            "buildErrors": [],
+            "modules": modules
+        });
+        let out = Content::json(body)?;
+        Ok(CallToolResult::success(vec![out]))
+    }
+
+    #[tool(
+        description = "Simulates publishing the project without broadcasting, returning the expected package ID, gas breakdown with the required gas budget, compiled package size, whether the active address can afford it, and any verification errors"
+    )]
+    async fn preview_publish(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Gas budget in MIST for the simulated transaction")]
+        gas_budget: Option<u64>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::gas_budget(gas_budget)?;
+        let mut cmd = tokio::process::Command::new("sui");
+        cmd.arg("client")
+            .arg("publish")
+            .arg("--dry-run")
+            .arg("--json")
+            .current_dir(&self.config.project_folder);
+        if let Some(gas_budget) = gas_budget {
+            cmd.arg("--gas-budget").arg(gas_budget.to_string());
+        }
+        let _guard = self.lock_package(&context.ct).await?;
+        let output = command::output(&mut cmd, &context.ct).await.map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run publish dry-run: {}", e), None)
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (warnings, build_errors) = extract_build_output(&stderr);
+
+        let Ok(response) = serde_json::from_str::<serde_json::Value>(&stdout) else {
+            let body = serde_json::json!({
+                "warnings": warnings.values().collect::<Vec<_>>(),
+                "buildErrors": build_errors.values().collect::<Vec<_>>(),
+                "error": strip_ansi_escapes::strip_str(stderr.trim()),
+            });
+            let out = Content::json(body)?;
+            return Ok(CallToolResult::error(vec![out]));
+        };
+
+        let package_id = client::published_package_id(&response);
+        let status = &response["effects"]["status"];
+        let gas = gas_report(&response["effects"]["gasUsed"]);
+
+        // The dry run builds the package, so its bytecode is on disk by now.
+        let package_size = manifest::read_manifest(&self.config.project_folder)
+            .and_then(|manifest| manifest::package_name(&manifest))
+            .ok()
+            .and_then(|name| {
+                let dir = Path::new(&self.config.project_folder)
+                    .join("build")
+                    .join(name)
+                    .join("bytecode_modules");
+                let entries = std::fs::read_dir(dir).ok()?;
+                Some(
+                    entries
+                        .filter_map(Result::ok)
+                        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "mv"))
+                        .filter_map(|entry| entry.metadata().ok())
+                        .map(|metadata| metadata.len())
+                        .sum::<u64>(),
                 )
-            })?;
-        cmd.arg(&format!("{}/tests", &self.project_folder))
-            .output()
-            .map_err(|e| {
+            });
+        let balance = match client::text(
+            &self.config.project_folder,
+            ["active-address"],
+            &context.ct,
+        )
+        .await
+        {
+            Ok(address) => self
+                .rpc_query("suix_getBalance", serde_json::json!([address]), &context.ct)
+                .await
+                .ok()
+                .and_then(|balance| balance["totalBalance"].as_str()?.parse::<u64>().ok()),
+            Err(_) => None,
+        };
+        let sufficient_balance = balance
+            .zip(gas["requiredGasBudget"].as_u64())
+            .map(|(balance, required)| balance >= required);
+
+        let body = serde_json::json!({
+            "warnings": warnings.values().collect::<Vec<_>>(),
+            "buildErrors": [],
+            "status": status["status"],
+            "error": status["error"],
+            "packageId": package_id,
+            "gasUsed": response["effects"]["gasUsed"],
+            "gas": gas,
+            "packageSizeBytes": package_size,
+            "balanceMist": balance,
+            "sufficientBalance": sufficient_balance,
+        });
+        let out = Content::json(body)?;
+        if status["status"] == "success" {
+            Ok(CallToolResult::success(vec![out]))
+        } else {
+            Ok(CallToolResult::error(vec![out]))
+        }
+    }
+
+    #[tool(
+        description = "Finds the UpgradeCap owned by the active address for a published package"
+    )]
+    async fn find_upgrade_cap(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "ID of the published package")]
+        package_id: String,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::object_id("package_id", &package_id)?;
+        let output = command::output(
+            tokio::process::Command::new("sui")
+                .arg("client")
+                .arg("objects")
+                .arg("--json")
+                .current_dir(&self.config.project_folder),
+            &context.ct,
+        )
+        .await
+        .map_err(|e| rmcp::Error::internal_error(format!("Failed to list objects: {}", e), None))?;
+
+        let objects: Vec<serde_json::Value> =
+            serde_json::from_slice(&output.stdout).map_err(|e| {
                 rmcp::Error::internal_error(
-                    format!("Failed to run formatter on `tests`: {}", e),
+                    format!(
+                        "Failed to parse object list: {}\n{}",
+                        e,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
                     None,
                 )
             })?;
-        Ok(CallToolResult::success(vec![Content::text("OK")]))
+
+        let package_id = normalize_address(&package_id);
+        let cap = objects
+            .iter()
+            .map(|object| object.get("data").unwrap_or(object))
+            .find(|data| {
+                let fields = &data["content"]["fields"];
+                data["type"].as_str().is_some_and(is_upgrade_cap_type)
+                    && fields["package"]
+                        .as_str()
+                        .is_some_and(|id| normalize_address(id) == package_id)
+            });
+
+        let Some(cap) = cap else {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "No UpgradeCap found for package {} owned by the active address",
+                package_id
+            ))]));
+        };
+
+        let fields = &cap["content"]["fields"];
+        let policy = fields["policy"]
+            .as_u64()
+            .or_else(|| fields["policy"].as_str().and_then(|p| p.parse().ok()));
+        let body = serde_json::json!({
+            "objectId": cap["objectId"],
+            "version": fields["version"],
+            "policy": policy,
+            "policyName": policy.map(upgrade_policy_name),
+        });
+        let out = Content::json(body)?;
+        Ok(CallToolResult::success(vec![out]))
     }
 
-    #[tool(description = "Builds the project and runs tests")]
-    async fn validate_project(&self) -> Result<CallToolResult, rmcp::Error> {
-        let build_output = std::process::Command::new("sui")
-            .arg("move")
-            .arg("build")
-            .arg("--force")
-            .current_dir(&self.project_folder)
-            .output()
+    #[tool(
+        description = "Formats and builds only the Move files changed according to git, returning diagnostics for those files. Falls back to the whole project outside a git repository"
+    )]
+    async fn validate_changed(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let guard = self.lock_package(&context.ct).await?;
+        let changed_files = self.changed_move_files(&context.ct).await;
+
+        let mut cmd = build_fmt_command(&self.config.movefmt_cmd, &self.config);
+        match &changed_files {
+            Some(files) if files.is_empty() => {
+                let body = serde_json::json!({
+                    "changedFiles": files,
+                    "warnings": [],
+                    "buildErrors": []
+                });
+                let out = Content::json(body)?;
+                return Ok(CallToolResult::success(vec![out]));
+            }
+            Some(files) => {
+                cmd.args(
+                    files
+                        .iter()
+                        .map(|file| format!("{}/{}", self.config.project_folder, file)),
+                );
+            }
+            None => {
+                let (dirs, _) = self.format_dirs();
+                cmd.args(
+                    dirs.iter()
+                        .map(|dir| format!("{}/{}", self.config.project_folder, dir)),
+                );
+            }
+        }
+        command::output(&mut cmd, &context.ct).await.map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run formatter: {}", e), None)
+        })?;
+
+        let BuildOutput {
+            mut warnings,
+            errors: mut build_errors,
+            timed_out,
+            hook_failure,
+        } = self.build_locked(&guard, true, &context.ct).await?;
+        if let Some(files) = &changed_files {
+            let is_changed = |notice: &LineNotice| {
+                let file = notice.file.trim_start_matches("./");
+                files.iter().any(|changed| changed == file)
+            };
+            warnings.retain(|notice, _| is_changed(notice));
+            build_errors.retain(|notice, _| is_changed(notice));
+        }
+
+        let mut body = serde_json::json!({
+            "changedFiles": changed_files,
+            "warnings": warnings.values().collect::<Vec<_>>(),
+            "buildErrors": build_errors.values().collect::<Vec<_>>(),
+            "timedOut": timed_out
+        });
+        if let Some(failure) = hook_failure {
+            body["postBuildHook"] = failure;
+        }
+        let out = Content::json(body)?;
+        Ok(CallToolResult::success(vec![out]))
+    }
+
+    #[tool(
+        description = "Returns the resolved dependency graph (package names, sources and revisions) from Move.lock"
+    )]
+    async fn get_dependency_graph(&self) -> Result<CallToolResult, rmcp::Error> {
+        let lockfile = manifest::read_lockfile(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let Some(lockfile) = lockfile else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Move.lock not found. Build the project (e.g. with `validate_project`) to generate it.",
+            )]));
+        };
+
+        let out = Content::json(manifest::dependency_graph(&lockfile))?;
+        Ok(CallToolResult::success(vec![out]))
+    }
+
+    #[tool(
+        description = "Starts a local Sui network with a faucet. Refuses if one is already running"
+    )]
+    async fn start_localnet(&self) -> Result<CallToolResult, rmcp::Error> {
+        match self.localnet.start().await {
+            Ok(status) => Ok(CallToolResult::success(vec![Content::json(status)?])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "{:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(description = "Stops the local Sui network started by `start_localnet`")]
+    async fn stop_localnet(&self) -> Result<CallToolResult, rmcp::Error> {
+        let stopped = self
+            .localnet
+            .stop()
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let body = serde_json::json!({ "stopped": stopped });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(description = "Reports whether the local Sui network is running and its RPC URL")]
+    async fn localnet_status(&self) -> Result<CallToolResult, rmcp::Error> {
+        let status = self
+            .localnet
+            .status()
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        Ok(CallToolResult::success(vec![Content::json(status)?]))
+    }
+
+    #[tool(description = "Publishes the project to the active network")]
+    async fn publish_project(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Gas budget in MIST for the publish transaction")]
+        gas_budget: Option<u64>,
+        #[tool(param)]
+        #[schemars(
+            description = "Publish even if the package already appears to be published on the active network"
+        )]
+        force: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Publish even if named addresses are unset or `0x0`")]
+        allow_unset_addresses: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::gas_budget(gas_budget)?;
+        if !allow_unset_addresses.unwrap_or(false) {
+            let manifest = manifest::read_manifest(&self.config.project_folder)
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            let unset = manifest::unset_addresses(&manifest);
+            if !unset.is_empty() {
+                let body = serde_json::json!({
+                    "unsetAddresses": unset,
+                    "error": "Named addresses in Move.toml are unset or `0x0`. Set them, or pass `allow_unset_addresses` to publish anyway.",
+                });
+                return Ok(CallToolResult::error(vec![Content::json(body)?]));
+            }
+        }
+
+        if !force.unwrap_or(false) {
+            let env = client::text(&self.config.project_folder, ["active-env"], &context.ct)
+                .await
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            let lockfile = manifest::read_lockfile(&self.config.project_folder)
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            if let Some(package_id) = manifest::published_id(lockfile.as_ref(), &env) {
+                tracing::warn!(%package_id, %env, "package already published, skipping");
+                let body = serde_json::json!({
+                    "skipped": true,
+                    "packageId": package_id,
+                    "warning": format!(
+                        "Package is already published on `{}`. Pass `force` to publish another copy.",
+                        env
+                    ),
+                });
+                return Ok(CallToolResult::success(vec![Content::json(body)?]));
+            }
+        }
+
+        let (response, attempts) = self.publish(gas_budget, &context.ct).await;
+        match response {
+            Ok(response) => {
+                let (success, error) = client::transaction_status(&response);
+                let body = serde_json::json!({
+                    "digest": response["digest"],
+                    "packageId": client::published_package_id(&response),
+                    "status": response["effects"]["status"]["status"],
+                    "error": error,
+                    "gasUsed": response["effects"]["gasUsed"],
+                    "attempts": attempts,
+                });
+                let out = Content::json(body)?;
+                if success {
+                    Ok(CallToolResult::success(vec![out]))
+                } else {
+                    Ok(CallToolResult::error(vec![out]))
+                }
+            }
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "{:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Sets up a test scenario in one call: requests gas from the faucet (on localnet/devnet/testnet), publishes the project, then optionally calls an initial function"
+    )]
+    async fn bootstrap(
+        &self,
+        #[tool(aggr)] request: BootstrapRequest,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::gas_budget(request.gas_budget)?;
+        if let Some(module) = &request.module {
+            validate::identifier("module", module)?;
+        }
+        if let Some(function) = &request.function {
+            validate::identifier("function", function)?;
+        }
+        if request.module.is_some() != request.function.is_some() {
+            return Err(rmcp::Error::invalid_params(
+                "`module` and `function` must be given together",
+                None,
+            ));
+        }
+        let ct = &context.ct;
+        let mut steps = serde_json::Map::new();
+
+        let env = match client::text(&self.config.project_folder, ["active-env"], ct).await {
+            Ok(env) => env,
+            Err(e) => return step_failure("active_env", format!("{:#}", e), steps),
+        };
+        if ["localnet", "devnet", "testnet"].contains(&env.as_str()) {
+            let (result, attempts) = retry::with_retries(
+                "faucet",
+                self.config.network_retries,
+                retry::is_transient,
+                ct,
+                || client::text(&self.config.project_folder, ["faucet"], ct),
+            )
+            .await;
+            if let Err(e) = result {
+                return step_failure("faucet", format!("{:#}", e), steps);
+            }
+            steps.insert(
+                "faucet".into(),
+                serde_json::json!({ "env": env, "attempts": attempts }),
+            );
+        }
+
+        let (response, attempts) = self.publish(request.gas_budget, ct).await;
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => return step_failure("publish", format!("{:#}", e), steps),
+        };
+        let (success, error) = client::transaction_status(&response);
+        let Some(package_id) = client::published_package_id(&response).filter(|_| success) else {
+            let error = error.unwrap_or_else(|| "No package was published".to_string());
+            return step_failure("publish", error, steps);
+        };
+        steps.insert(
+            "publish".into(),
+            serde_json::json!({
+                "digest": response["digest"],
+                "packageId": package_id,
+                "attempts": attempts,
+            }),
+        );
+
+        if let (Some(module), Some(function)) = (&request.module, &request.function) {
+            let args = call_args(
+                &package_id,
+                module,
+                function,
+                &request.args,
+                &request.type_args,
+                request.gas_budget,
+            );
+            let response = match client::json(&self.config.project_folder, &args, ct).await {
+                Ok(response) => response,
+                Err(e) => return step_failure("call", format!("{:#}", e), steps),
+            };
+            if let (false, error) = client::transaction_status(&response) {
+                return step_failure("call", error.unwrap_or_default(), steps);
+            }
+            steps.insert(
+                "call".into(),
+                serde_json::json!({ "digest": response["digest"] }),
+            );
+        }
+
+        let body = serde_json::json!({
+            "packageId": package_id,
+            "steps": steps,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Returns a stable hex hash of Move.toml and all Move sources, for detecting whether the package changed"
+    )]
+    async fn get_source_hash(&self) -> Result<CallToolResult, rmcp::Error> {
+        let hash = sources::package_hash(&self.config.project_folder).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to hash sources: {}", e), None)
+        })?;
+        let body = serde_json::json!({ "hash": hash });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Lists the package's tests (`module::function` names usable as filters) without running them, including expected-failure annotations"
+    )]
+    async fn list_tests(&self) -> Result<CallToolResult, rmcp::Error> {
+        let tests = sources::find_tests(&self.config.project_folder).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to scan sources: {}", e), None)
+        })?;
+        Ok(CallToolResult::success(vec![Content::json(tests)?]))
+    }
+
+    #[tool(
+        description = "Clears the project's build output and the cached git dependencies it uses under ~/.move. Lists what would be removed unless `confirm` is set"
+    )]
+    async fn clear_build_cache(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Actually delete the listed directories")]
+        confirm: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Re-fetch dependencies after clearing")]
+        refetch: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let manifest = manifest::read_manifest(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let lockfile = manifest::read_lockfile(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let mut targets = vec![];
+        let build_dir = std::path::Path::new(&self.config.project_folder).join("build");
+        if build_dir.is_dir() {
+            targets.push(build_dir);
+        }
+
+        // Dependency checkouts are named after the sanitised git URL followed by the revision.
+        let move_home = std::env::var_os("MOVE_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".move"))
+            });
+        if let Some(entries) = move_home.and_then(|dir| std::fs::read_dir(dir).ok()) {
+            let prefixes = manifest::git_dependency_urls(&manifest, lockfile.as_ref())
+                .iter()
+                .map(|url| {
+                    url.chars()
+                        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>();
+            for entry in entries.filter_map(Result::ok) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if entry.path().is_dir() && prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+                    targets.push(entry.path());
+                }
+            }
+        }
+
+        let (targets, outside): (Vec<_>, Vec<_>) = targets
+            .iter()
+            .partition(|path| validate::allowed_path(&self.config, path).is_ok());
+        let targets = targets
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        let outside = outside
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        if !confirm.unwrap_or(false) {
+            let body = serde_json::json!({
+                "removed": [],
+                "wouldRemove": targets,
+                "outsideAllowedDirs": outside,
+            });
+            return Ok(CallToolResult::success(vec![Content::json(body)?]));
+        }
+
+        for target in &targets {
+            tracing::info!(%target, "removing cache directory");
+            std::fs::remove_dir_all(target).map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to remove `{}`: {}", target, e), None)
+            })?;
+        }
+
+        let refetch_output = if refetch.unwrap_or(false) {
+            let output = command::output(
+                tokio::process::Command::new("sui")
+                    .arg("move")
+                    .arg("build")
+                    .arg("--fetch-deps-only")
+                    .current_dir(&self.config.project_folder),
+                &context.ct,
+            )
+            .await
             .map_err(|e| {
-                rmcp::Error::internal_error(format!("Failed to build project: {}", e), None)
+                rmcp::Error::internal_error(format!("Failed to fetch dependencies: {}", e), None)
             })?;
+            Some(serde_json::json!({
+                "success": output.status.success(),
+                "output": strip_ansi_escapes::strip_str(String::from_utf8_lossy(&output.stderr).trim()),
+            }))
+        } else {
+            None
+        };
+
+        let body = serde_json::json!({
+            "removed": targets,
+            "outsideAllowedDirs": outside,
+            "refetch": refetch_output,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
 
-        let output_data = String::from_utf8_lossy(&build_output.stderr);
+    #[tool(
+        description = "Returns the events emitted by a transaction, decoded into JSON using their Move struct layouts"
+    )]
+    async fn get_transaction_events(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Transaction digest")]
+        digest: String,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::digest(&digest)?;
+        let response = match self
+            .rpc_query(
+                "sui_getTransactionBlock",
+                serde_json::json!([digest, { "showEvents": true }]),
+                &context.ct,
+            )
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{:#}",
+                    e
+                ))]));
+            }
+        };
 
-        let (build_warnings, build_errors) = extract_build_output(&output_data);
+        let events = response["events"]
+            .as_array()
+            .map(|events| events.iter().map(decode_event).collect::<Vec<_>>())
+            .unwrap_or_default();
 
-        if !build_errors.is_empty() {
+        let body = serde_json::json!({
+            "digest": response["digest"],
+            "events": events,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Summarises the project state in one call: whether it builds, warning/error counts, whether tests pass, the active address, network and gas balance"
+    )]
+    async fn status(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let ct = &context.ct;
+        let folder = &self.config.project_folder;
+
+        let (address, env) = tokio::join!(
+            client::text(folder, ["active-address"], ct),
+            client::text(folder, ["active-env"], ct),
+        );
+        let gas_balance = match &address {
+            Ok(address) => self
+                .rpc_query("suix_getBalance", serde_json::json!([address]), ct)
+                .await
+                .ok()
+                .and_then(|balance| balance["totalBalance"].as_str()?.parse::<u64>().ok()),
+            Err(_) => None,
+        };
+
+        // Building and testing dominate the cost, so reuse the last summary while the
+        // sources are unchanged.
+        let hash = sources::package_hash(folder).ok();
+        let mut cache = self.status_cache.lock().await;
+        let cached = cache
+            .as_ref()
+            .filter(|(cached_hash, _)| Some(cached_hash) == hash.as_ref())
+            .map(|(_, project)| project.clone());
+
+        let project = match cached.clone() {
+            Some(project) => project,
+            None => {
+                let guard = self.lock_package(ct).await?;
+                let build = self.build_locked(&guard, false, ct).await?;
+                let tests_pass = if build.errors.is_empty() && !build.timed_out {
+                    let tests = self.test_locked(&guard, &[], ct).await?;
+                    (!tests.timed_out).then(|| tests.stdout.contains("Test result: OK"))
+                } else {
+                    None
+                };
+                let project = serde_json::json!({
+                    "builds": build.errors.is_empty() && !build.timed_out,
+                    "warnings": build.warnings.len(),
+                    "errors": build.errors.len(),
+                    "testsPass": tests_pass,
+                });
+                if let Some(hash) = hash {
+                    *cache = Some((hash, project.clone()));
+                }
+                project
+            }
+        };
+
+        let body = serde_json::json!({
+            "project": project,
+            "cached": cached.is_some(),
+            "activeAddress": address.ok(),
+            "network": env.ok(),
+            "gasBalanceMist": gas_balance,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Returns the type/signature information at a position in a Move file, using move-analyzer"
+    )]
+    async fn hover(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "File path relative to the project folder")]
+        file: String,
+        #[tool(param)]
+        #[schemars(description = "1-based line number")]
+        line: u32,
+        #[tool(param)]
+        #[schemars(description = "1-based column number")]
+        column: u32,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let path = validate::project_file(&self.config, &file)?;
+        let root = std::path::Path::new(&self.config.project_folder);
+        let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+
+        match analyzer::hover(&root, &path, line, column, &context.ct).await {
+            Ok(contents) => {
+                let body = serde_json::json!({ "hover": contents });
+                Ok(CallToolResult::success(vec![Content::json(body)?]))
+            }
+            Err(HoverError::NotInstalled) => Ok(CallToolResult::error(vec![Content::text(
+                "move-analyzer is not installed or not on PATH, so hover queries are unavailable.",
+            )])),
+            Err(HoverError::Cancelled) => Err(rmcp::Error::internal_error(
+                "Hover query cancelled by client",
+                None,
+            )),
+            Err(HoverError::Failed(e)) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "{:#}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Checks Move.toml for named addresses that are unset or `0x0` and would break a publish"
+    )]
+    async fn check_addresses(&self) -> Result<CallToolResult, rmcp::Error> {
+        let manifest = manifest::read_manifest(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let unset = manifest::unset_addresses(&manifest);
+        let body = serde_json::json!({ "unsetAddresses": unset });
+        if unset.is_empty() {
+            Ok(CallToolResult::success(vec![Content::json(body)?]))
+        } else {
+            Ok(CallToolResult::error(vec![Content::json(body)?]))
+        }
+    }
+
+    #[tool(
+        description = "Searches all Move files in the project for a regex, returning matching lines with file, line number and context"
+    )]
+    async fn search_sources(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Regular expression to search for")]
+        pattern: String,
+        #[tool(param)]
+        #[schemars(description = "Match case-sensitively (default true)")]
+        case_sensitive: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Maximum number of matches to return (default 100, max 500)")]
+        max_results: Option<usize>,
+        #[tool(param)]
+        #[schemars(description = "Lines of context around each match (default 2, max 10)")]
+        context_lines: Option<usize>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::non_empty("pattern", &pattern)?;
+        if pattern.len() > MAX_SEARCH_PATTERN_LEN {
+            return Err(rmcp::Error::invalid_params(
+                format!(
+                    "Pattern is too long (max {} characters)",
+                    MAX_SEARCH_PATTERN_LEN
+                ),
+                None,
+            ));
+        }
+        let regex = regex::RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive.unwrap_or(true))
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| rmcp::Error::invalid_params(format!("Invalid pattern: {}", e), None))?;
+
+        let (matches, truncated) = sources::search(
+            &self.config.project_folder,
+            &regex,
+            max_results.unwrap_or(100).min(500),
+            context_lines.unwrap_or(2).min(10),
+        )
+        .map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to search sources: {}", e), None)
+        })?;
+
+        let body = serde_json::json!({
+            "matches": matches,
+            "truncated": truncated,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Returns the version of this MCP server and of the installed `sui` CLI, for correlating behaviour with a deployment"
+    )]
+    async fn server_version(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let sui_version = self.sui_version(&context.ct).await;
+        let body = serde_json::json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "serverVersion": env!("CARGO_PKG_VERSION"),
+            "suiVersion": sui_version,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Upgrades the published package using an UpgradeCap. Compatibility violations that cause the upgrade to be rejected are returned under `compatibilityErrors`"
+    )]
+    async fn upgrade_project(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Object ID of the package's UpgradeCap (see `find_upgrade_cap`)")]
+        upgrade_capability: String,
+        #[tool(param)]
+        #[schemars(description = "Gas budget in MIST for the upgrade transaction")]
+        gas_budget: Option<u64>,
+        #[tool(param)]
+        #[schemars(description = "Simulate the upgrade without broadcasting it")]
+        dry_run: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::object_id("upgrade_capability", &upgrade_capability)?;
+        validate::gas_budget(gas_budget)?;
+        let mut cmd = tokio::process::Command::new("sui");
+        cmd.arg("client")
+            .arg("upgrade")
+            .arg("--upgrade-capability")
+            .arg(&upgrade_capability)
+            .arg("--json")
+            .current_dir(&self.config.project_folder);
+        if let Some(gas_budget) = gas_budget {
+            cmd.arg("--gas-budget").arg(gas_budget.to_string());
+        }
+        if dry_run.unwrap_or(false) {
+            cmd.arg("--dry-run");
+        }
+        let _guard = self.lock_package(&context.ct).await?;
+        let output = command::output(&mut cmd, &context.ct).await.map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run upgrade: {}", e), None)
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (warnings, build_errors) = extract_build_output(&stderr);
+        let build_errors = build_errors
+            .into_iter()
+            .filter(|(notice, _)| !notice.code.starts_with("Compatibility"))
+            .map(|(_, error)| error)
+            .collect::<Vec<_>>();
+
+        let Ok(response) = serde_json::from_str::<serde_json::Value>(&stdout) else {
             let body = serde_json::json!({
-                "warnings": build_warnings.values().collect::<Vec<_>>(),
-                "buildErrors": build_errors.values().collect::<Vec<_>>(),
-                "testResults": null
+                "warnings": warnings.values().collect::<Vec<_>>(),
+                "buildErrors": build_errors,
+                "compatibilityErrors": compatibility::parse_errors(&stderr),
+                "error": strip_ansi_escapes::strip_str(stderr.trim()),
             });
             let out = Content::json(body)?;
-            return Ok(CallToolResult::success(vec![out]));
+            return Ok(CallToolResult::error(vec![out]));
+        };
+
+        let (success, error) = client::transaction_status(&response);
+        let compatibility_errors = error
+            .as_deref()
+            .map(compatibility::parse_errors)
+            .unwrap_or_default();
+        let body = serde_json::json!({
+            "warnings": warnings.values().collect::<Vec<_>>(),
+            "status": response["effects"]["status"]["status"],
+            "error": error,
+            "compatibilityErrors": compatibility_errors,
+            "packageId": client::published_package_id(&response),
+            "digest": response["digest"],
+            "gasUsed": response["effects"]["gasUsed"],
+        });
+        let out = Content::json(body)?;
+        if success {
+            Ok(CallToolResult::success(vec![out]))
+        } else {
+            Ok(CallToolResult::error(vec![out]))
         }
+    }
+
+    #[tool(
+        description = "Lists the most recent transactions sent by the active address, newest first, with their status, gas used and the calls they made"
+    )]
+    async fn recent_transactions(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of transactions to return (default 10, max 50)")]
+        limit: Option<usize>,
+        #[tool(param)]
+        #[schemars(description = "`nextCursor` from a previous call, to fetch older transactions")]
+        cursor: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let ct = &context.ct;
+        let folder = &self.config.project_folder;
+        let address = client::text(folder, ["active-address"], ct)
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let page = self
+            .rpc_query(
+                "suix_queryTransactionBlocks",
+                serde_json::json!([
+                    {
+                        "filter": { "FromAddress": address },
+                        "options": { "showInput": true, "showEffects": true },
+                    },
+                    cursor,
+                    limit.unwrap_or(10).clamp(1, 50),
+                    true,
+                ]),
+                ct,
+            )
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let transactions = page["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(summarize_transaction)
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "address": address,
+            "transactions": transactions,
+            "nextCursor": page["nextCursor"],
+            "hasNextPage": page["hasNextPage"].as_bool().unwrap_or(false),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Maps a numeric abort code to the constant(s) declared with that value, by scanning the Move sources. Returns every candidate when several match"
+    )]
+    async fn decode_abort_code(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "The numeric abort code")]
+        abort_code: u64,
+        #[tool(param)]
+        #[schemars(
+            description = "Module that aborted, as `name` or `address::name`. Searches all modules when omitted"
+        )]
+        module: Option<String>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let constants = sources::find_constants(&self.config.project_folder).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to scan sources: {}", e), None)
+        })?;
+
+        let candidates = constants
+            .into_iter()
+            .filter(|constant| {
+                module.as_ref().is_none_or(|module| {
+                    constant.module == *module
+                        || constant.module.ends_with(&format!("::{}", module))
+                })
+            })
+            .filter(|constant| sources::parse_integer(&constant.value) == Some(abort_code.into()))
+            .collect::<Vec<_>>();
+
+        let body = serde_json::json!({
+            "abortCode": abort_code,
+            "candidates": candidates,
+        });
+        if candidates.is_empty() {
+            Ok(CallToolResult::error(vec![Content::json(body)?]))
+        } else {
+            Ok(CallToolResult::success(vec![Content::json(body)?]))
+        }
+    }
+
+    #[tool(
+        description = "Checks that the build output is gitignored and not committed, returning recommendations. Read-only unless `fix` is set, which appends `build/` to .gitignore"
+    )]
+    async fn check_gitignore(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Append `build/` to the project's .gitignore if it is not ignored"
+        )]
+        fix: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let folder = &self.config.project_folder;
+        let git = |args: &'static [&'static str]| {
+            let mut cmd = tokio::process::Command::new("git");
+            cmd.args(args).current_dir(folder);
+            cmd
+        };
+
+        // Exits 0 when ignored, 1 when not, and 128 outside a repository.
+        let ignored = command::output(&mut git(&["check-ignore", "-q", "build/"]), &context.ct)
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("Failed to run git: {}", e), None))?;
+        if ignored.status.code() == Some(128) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "The project is not inside a git repository",
+            )]));
+        }
+        let build_ignored = ignored.status.success();
+
+        let tracked = command::output(&mut git(&["ls-files", "--", "build"]), &context.ct)
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("Failed to run git: {}", e), None))?;
+        let tracked_artifacts = String::from_utf8_lossy(&tracked.stdout)
+            .lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+
+        let mut fixed = false;
+        if !build_ignored && fix.unwrap_or(false) {
+            if self.config.read_only {
+                return Err(rmcp::Error::invalid_request(
+                    "`fix` is unavailable because the server is in read-only mode",
+                    None,
+                ));
+            }
+            let path = validate::allowed_path(
+                &self.config,
+                &std::path::Path::new(folder).join(".gitignore"),
+            )?;
+            let existing = std::fs::read_to_string(&path).unwrap_or_default();
+            let separator = if existing.is_empty() || existing.ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            std::fs::write(&path, format!("{}{}build/\n", existing, separator)).map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to update .gitignore: {}", e), None)
+            })?;
+            fixed = true;
+        }
+
+        let mut recommendations = vec![];
+        if !build_ignored && !fixed {
+            recommendations.push("Add `build/` to .gitignore".to_string());
+        }
+        if !tracked_artifacts.is_empty() {
+            recommendations.push(format!(
+                "Untrack committed build artifacts with `git rm -r --cached build` ({} files)",
+                tracked_artifacts.len()
+            ));
+        }
+
+        let body = serde_json::json!({
+            "buildIgnored": build_ignored || fixed,
+            "trackedArtifacts": tracked_artifacts,
+            "recommendations": recommendations,
+            "fixed": fixed,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Estimates the gas cost of calling a function by dry-running it with the given arguments, returning the computation and storage breakdown in MIST"
+    )]
+    async fn estimate_gas(
+        &self,
+        #[tool(aggr)] request: EstimateGasRequest,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::gas_budget(request.gas_budget)?;
+        validate::identifier("module", &request.module)?;
+        validate::identifier("function", &request.function)?;
+        if let Some(package_id) = &request.package_id {
+            validate::object_id("package_id", package_id)?;
+        }
+        let ct = &context.ct;
+        let folder = &self.config.project_folder;
+
+        let package_id = match request.package_id {
+            Some(package_id) => package_id,
+            None => {
+                let lockfile = manifest::read_lockfile(folder)
+                    .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+                let env = client::text(folder, ["active-env"], ct)
+                    .await
+                    .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+                manifest::published_id(lockfile.as_ref(), &env).ok_or_else(|| {
+                    rmcp::Error::invalid_params(
+                        format!(
+                            "The package is not published on `{}`; pass `package_id`",
+                            env
+                        ),
+                        None,
+                    )
+                })?
+            }
+        };
+
+        let mut args = call_args(
+            &package_id,
+            &request.module,
+            &request.function,
+            &request.args,
+            &request.type_args,
+            request.gas_budget,
+        );
+        args.push("--dry-run".to_string());
+        let response = match client::json(folder, &args, ct).await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{:#}",
+                    e
+                ))]));
+            }
+        };
+
+        let (success, error) = client::transaction_status(&response);
+        let body = serde_json::json!({
+            "packageId": package_id,
+            "function": format!("{}::{}", request.module, request.function),
+            "status": response["effects"]["status"]["status"],
+            "error": error,
+            "gas": gas_report(&response["effects"]["gasUsed"]),
+        });
+        let out = Content::json(body)?;
+        if success {
+            Ok(CallToolResult::success(vec![out]))
+        } else {
+            Ok(CallToolResult::error(vec![out]))
+        }
+    }
+
+    #[tool(
+        description = "Parses a Move file into a structured outline of its modules, functions, structs, enums and constants with source spans. Files with syntax errors return the partial outline plus the error"
+    )]
+    async fn module_outline(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "File path relative to the project folder")]
+        file: String,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let path = validate::project_file(&self.config, &file)?;
+        let source = std::fs::read_to_string(&path).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to read `{}`: {}", file, e), None)
+        })?;
+
+        let outline = outline::parse(&source);
+        let failed = outline.error.is_some();
+        let out = Content::json(outline)?;
+        if failed {
+            Ok(CallToolResult::error(vec![out]))
+        } else {
+            Ok(CallToolResult::success(vec![out]))
+        }
+    }
+
+    #[tool(
+        description = "Formats the project, then builds and runs tests, returning both results. Skips validation if the formatter fails. In `check` mode the files are left unchanged and `filesChanged` lists what would be reformatted"
+    )]
+    async fn format_and_validate(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Glob patterns relative to the project folder selecting the files to format. Formats the whole project when empty"
+        )]
+        patterns: Option<Vec<String>>,
+        #[tool(param)]
+        #[schemars(description = "Report formatting changes without writing them")]
+        check: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Treat build warnings as failures")]
+        strict: Option<bool>,
+        #[tool(param)]
+        #[schemars(
+            description = "Maximum gas (instructions) each test may use, so runaway loops fail fast"
+        )]
+        gas_limit: Option<u64>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let patterns = patterns.unwrap_or_default();
+        let (formatted, format) = self
+            .format(&patterns, None, check.unwrap_or(false), false, &context.ct)
+            .await?;
+        // A formatter failure usually means the sources do not parse, so a build would only
+        // repeat the error.
+        if !formatted {
+            let body = serde_json::json!({ "format": format, "validation": null });
+            return json_result(false, body);
+        }
+
+        let (validated, validation) = self
+            .validate(
+                strict.unwrap_or(false),
+                gas_limit,
+                DiagnosticsLayout::Flat,
+                false,
+                true,
+                &context.ct,
+            )
+            .await?;
+        let body = serde_json::json!({ "format": format, "validation": validation });
+        json_result(validated, body)
+    }
+
+    #[tool(
+        description = "Generates a new keypair in the client keystore and returns its address and alias. The recovery phrase is kept out of the response"
+    )]
+    async fn generate_address(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Key scheme: `ed25519` (default), `secp256k1` or `secp256r1`")]
+        key_scheme: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Alias for the new address")]
+        alias: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let key_scheme = key_scheme.unwrap_or_else(|| "ed25519".to_string());
+        validate::key_scheme(&key_scheme)?;
+        let mut args = vec!["new-address".to_string(), key_scheme];
+        if let Some(alias) = alias {
+            validate::non_empty("alias", &alias)?;
+            args.push(alias);
+        }
+        let response = client::json(&self.config.project_folder, &args, &context.ct)
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        // Only copy public fields: the response also carries the recovery phrase.
+        let body = serde_json::json!({
+            "address": response["address"],
+            "alias": response["alias"],
+            "keyScheme": response["keyScheme"],
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Imports a key (Bech32 `suiprivkey...` or mnemonic phrase) into the client keystore and returns its address and alias. Key material is never echoed back"
+    )]
+    async fn import_key(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Bech32-encoded private key or mnemonic phrase")]
+        key: String,
+        #[tool(param)]
+        #[schemars(description = "Key scheme: `ed25519` (default), `secp256k1` or `secp256r1`")]
+        key_scheme: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Alias for the imported address")]
+        alias: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::non_empty("key", &key)?;
+        let key_scheme = key_scheme.unwrap_or_else(|| "ed25519".to_string());
+        validate::key_scheme(&key_scheme)?;
+
+        let mut cmd = tokio::process::Command::new("sui");
+        cmd.arg("keytool")
+            .arg("--json")
+            .arg("import")
+            .arg(&key)
+            .arg(&key_scheme)
+            .current_dir(&self.config.project_folder);
+        if let Some(alias) = &alias {
+            validate::non_empty("alias", alias)?;
+            cmd.arg("--alias").arg(alias);
+        }
+        let output = command::output(&mut cmd, &context.ct).await.map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run keytool: {}", e), None)
+        })?;
+
+        let Ok(response) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = strip_ansi_escapes::strip_str(stderr.trim()).replace(&key, "<redacted>");
+            return Ok(CallToolResult::error(vec![Content::text(error)]));
+        };
+        let body = serde_json::json!({
+            "address": response["suiAddress"],
+            "alias": response["alias"],
+            "keyScheme": response["keyScheme"],
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Lists the active address's SUI coins, largest balance first, for choosing a `--gas` object for `sui client call`"
+    )]
+    async fn gas_coins(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Only return coins holding at least this many MIST")]
+        min_balance: Option<u64>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let ct = &context.ct;
+        let folder = &self.config.project_folder;
+        let address = client::text(folder, ["active-address"], ct)
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let mut coins = vec![];
+        let mut cursor = serde_json::Value::Null;
+        loop {
+            let page = self
+                .rpc_query(
+                    "suix_getCoins",
+                    serde_json::json!([address, "0x2::sui::SUI", cursor, 50]),
+                    ct,
+                )
+                .await
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            coins.extend(
+                page["data"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|coin| {
+                        let balance = coin["balance"].as_str()?.parse::<u64>().ok()?;
+                        Some((balance, coin.clone()))
+                    }),
+            );
+            if page["hasNextPage"].as_bool() != Some(true) || page["nextCursor"].is_null() {
+                break;
+            }
+            cursor = page["nextCursor"].clone();
+        }
+
+        coins.retain(|(balance, _)| *balance >= min_balance.unwrap_or(0));
+        coins.sort_by(|a, b| b.0.cmp(&a.0));
+        let total = coins
+            .iter()
+            .map(|(balance, _)| u128::from(*balance))
+            .sum::<u128>();
+        let coins = coins
+            .into_iter()
+            .map(|(balance, coin)| {
+                serde_json::json!({
+                    "objectId": coin["coinObjectId"],
+                    "balance": balance,
+                    "version": coin["version"],
+                    "digest": coin["digest"],
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "address": address,
+            "coins": coins,
+            "totalBalance": total.to_string(),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Checks that a published package's on-chain bytecode matches the local sources with `sui client verify-source`, listing each mismatched or missing module"
+    )]
+    async fn verify_source(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Package ID to verify against. Defaults to the address the package was published at, from Move.toml/Move.lock"
+        )]
+        package_id: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Also verify the package's dependencies")]
+        verify_deps: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let mut cmd = tokio::process::Command::new("sui");
+        cmd.arg("client")
+            .arg("verify-source")
+            .current_dir(&self.config.project_folder);
+        if let Some(package_id) = &package_id {
+            validate::object_id("package_id", package_id)?;
+            cmd.arg("--address-override").arg(package_id);
+        }
+        if verify_deps.unwrap_or(false) {
+            cmd.arg("--verify-deps");
+        }
+        let output = command::output(&mut cmd, &context.ct).await.map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run verify-source: {}", e), None)
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (_, build_errors) = extract_build_output(&stderr);
+        let matches = output.status.success();
+        let body = serde_json::json!({
+            "matches": matches,
+            "packageId": package_id,
+            "mismatches": source_verification::parse_mismatches(&stderr),
+            "buildErrors": build_errors.into_iter().map(|(_, error)| error).collect::<Vec<_>>(),
+            "error": (!matches).then(|| strip_ansi_escapes::strip_str(stderr.trim())),
+            "output": strip_ansi_escapes::strip_str(stdout.trim()),
+        });
+        json_result(matches, body)
+    }
+
+    #[tool(
+        description = "Diagnoses the environment: the sui CLI, formatter, project manifest, active address, network and gas. Returns a pass/fail checklist with remediation hints; call this first when tools fail unexpectedly"
+    )]
+    async fn doctor(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let ct = &context.ct;
+        let folder = &self.config.project_folder;
+
+        let formatter = self
+            .config
+            .movefmt_cmd
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let formatter_check = check(
+            "formatter",
+            if program_exists(formatter) {
+                Ok(format!("`{}` found", formatter))
+            } else {
+                Err(format!("`{}` not found", formatter))
+            },
+            "Install the formatter or point MOVEFMT_CMD at it",
+        );
+        let project_check = check(
+            "project",
+            manifest::read_manifest(folder)
+                .and_then(|manifest| manifest::package_name(&manifest))
+                .map(|name| format!("package `{}` in {}", name, folder))
+                .map_err(|e| format!("{:#}", e)),
+            "Set PROJECT_FOLDER to a directory containing a valid Move.toml",
+        );
+
+        let (version, address, network) = tokio::join!(
+            self.sui_version(ct),
+            client::text(folder, ["active-address"], ct),
+            self.rpc_query("sui_getChainIdentifier", serde_json::json!([]), ct),
+        );
+        let gas = match &address {
+            Ok(address) => self
+                .rpc_query("suix_getBalance", serde_json::json!([address]), ct)
+                .await
+                .map_err(|e| format!("{:#}", e))
+                .and_then(|balance| {
+                    match balance["totalBalance"]
+                        .as_str()
+                        .and_then(|b| b.parse::<u64>().ok())
+                    {
+                        Some(0) | None => Err("no SUI balance".to_string()),
+                        Some(mist) => Ok(format!("{} MIST", mist)),
+                    }
+                }),
+            Err(_) => Err("no active address".to_string()),
+        };
+
+        let checks = vec![
+            check(
+                "sui",
+                version.ok_or_else(|| "`sui --version` failed".to_string()),
+                "Install the Sui CLI and make sure `sui` is on PATH",
+            ),
+            formatter_check,
+            project_check,
+            check(
+                "activeAddress",
+                address.map_err(|e| format!("{:#}", e)),
+                "Create one with `generate_address` or `sui client new-address ed25519`",
+            ),
+            check(
+                "network",
+                network
+                    .map(|chain| format!("chain {}", chain.as_str().unwrap_or_default()))
+                    .map_err(|e| format!("{:#}", e)),
+                "Check the active environment (`sui client active-env`) or RPC_URL, or start a localnet",
+            ),
+            check(
+                "gas",
+                gas,
+                "Request funds with `sui client faucet` or the `bootstrap` tool",
+            ),
+        ];
+        let healthy = checks.iter().all(|check| check["ok"] == true);
+        let body = serde_json::json!({
+            "healthy": healthy,
+            "checks": checks,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Runs the tests matching a filter several times to check for flakiness, reporting the pass rate and each distinct failure message with how often it occurred"
+    )]
+    async fn check_flaky(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Test name filter, as passed to `sui move test`")]
+        filter: String,
+        #[tool(param)]
+        #[schemars(description = "Number of runs (default 5, max 50)")]
+        runs: Option<u32>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::test_filter(&filter)?;
+        let runs = runs.unwrap_or(5).clamp(1, 50);
+
+        // Held across the runs, so no other build replaces the tests between them.
+        let guard = self.lock_package(&context.ct).await?;
+        let (mut passed, mut failed) = (0, 0);
+        let mut failures = Vec::<(test_output::TestFailure, u32)>::new();
+        for run in 1..=runs {
+            let TestOutput {
+                stdout,
+                stderr,
+                timed_out,
+            } = self
+                .test_locked(&guard, std::slice::from_ref(&filter), &context.ct)
+                .await?;
+
+            if stdout.contains("Total tests: 0") {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "No tests match `{}`",
+                    filter
+                ))]));
+            }
+            if stdout.contains("Test result: OK") {
+                passed += 1;
+                continue;
+            }
+            if timed_out || !stdout.contains("Test failures") {
+                // Neither passed nor failed: the tests did not build or the run was cut off.
+                let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+                let body = serde_json::json!({
+                    "run": run,
+                    "passed": passed,
+                    "failed": failed,
+                    "buildErrors": errors.values().collect::<Vec<_>>(),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+
+            failed += 1;
+            for failure in test_output::parse_failures(&stdout) {
+                match failures
+                    .iter_mut()
+                    .find(|(seen, _)| seen.name == failure.name && seen.message == failure.message)
+                {
+                    Some((_, count)) => *count += 1,
+                    None => failures.push((failure, 1)),
+                }
+            }
+        }
+
+        let failures = failures
+            .into_iter()
+            .map(|(failure, count)| {
+                serde_json::json!({
+                    "name": failure.name,
+                    "kind": failure.kind,
+                    "message": failure.message,
+                    "occurrences": count,
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "runs": runs,
+            "passed": passed,
+            "failed": failed,
+            "passRate": f64::from(passed) / f64::from(runs),
+            "flaky": passed > 0 && failed > 0,
+            "failures": failures,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Reports the address each named address resolves to at build time, and where the binding comes from (`[addresses]`, `[dev-addresses]`, `addr-subst`, a local dependency or the framework). Unassigned addresses have a null address"
+    )]
+    async fn named_addresses(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Apply `[dev-addresses]`, as in dev and test builds")]
+        dev: Option<bool>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let bindings =
+            manifest::address_bindings(&self.config.project_folder, dev.unwrap_or(false))
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let body = serde_json::json!({
+            "addresses": bindings
+                .iter()
+                .map(|binding| (binding.name.clone(), serde_json::json!(binding.address)))
+                .collect::<serde_json::Map<_, _>>(),
+            "bindings": bindings,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(description = "Returns the `[package] version` declared in Move.toml")]
+    async fn package_version(&self) -> Result<CallToolResult, rmcp::Error> {
+        let manifest = manifest::read_manifest(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let version = manifest::package_version(&manifest);
+        let valid = version
+            .as_deref()
+            .map(|version| manifest::parse_semver(version).is_ok());
+        let body = serde_json::json!({
+            "version": version,
+            "validSemver": valid,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Bumps the `[package] version` in Move.toml by `major`, `minor` or `patch`, or sets it to an explicit version, editing only that line. Returns the old and new versions"
+    )]
+    async fn bump_version(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "`major`, `minor` or `patch` (default)")]
+        level: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Exact semantic version to set instead of bumping")]
+        version: Option<String>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let folder = &self.config.project_folder;
+        let manifest = manifest::read_manifest(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let old = manifest::package_version(&manifest).ok_or_else(|| {
+            rmcp::Error::invalid_request("Move.toml has no `[package] version`", None)
+        })?;
+
+        let new = match (version, level.as_deref()) {
+            (Some(_), Some(_)) => {
+                return Err(rmcp::Error::invalid_params(
+                    "Pass either `level` or `version`, not both",
+                    None,
+                ));
+            }
+            (Some(version), None) => {
+                manifest::parse_semver(&version)
+                    .map_err(|e| rmcp::Error::invalid_params(format!("{:#}", e), None))?;
+                version
+            }
+            (None, level) => {
+                let level = level.unwrap_or("patch");
+                if !matches!(level, "major" | "minor" | "patch") {
+                    return Err(rmcp::Error::invalid_params(
+                        format!(
+                            "`level` must be `major`, `minor` or `patch`, got `{}`",
+                            level
+                        ),
+                        None,
+                    ));
+                }
+                manifest::bump_version(&old, level).map_err(|e| {
+                    rmcp::Error::invalid_request(format!("Current version: {:#}", e), None)
+                })?
+            }
+        };
+
+        manifest::set_package_version(folder, &new)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let body = serde_json::json!({
+            "oldVersion": old,
+            "newVersion": new,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Scans the `use` declarations of the package's modules and reports any dependency cycles (which Move forbids) as module paths, with the `use` line behind each step"
+    )]
+    async fn find_dependency_cycles(&self) -> Result<CallToolResult, rmcp::Error> {
+        let dependencies =
+            sources::module_dependencies(&self.config.project_folder).map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to scan sources: {}", e), None)
+            })?;
+        let cycles = sources::find_cycles(&dependencies)
+            .into_iter()
+            .map(|cycle| {
+                let steps = cycle
+                    .windows(2)
+                    .filter_map(|step| {
+                        dependencies.iter().find(|dependency| {
+                            dependency.from == step[0] && dependency.to == step[1]
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "path": cycle.join(" -> "),
+                    "modules": cycle,
+                    "uses": steps,
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "hasCycles": !cycles.is_empty(),
+            "cycles": cycles,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Checks each dependency in Move.lock against the bytecode currently published on the active network, using `sui client verify-source --verify-deps --skip-source`. Reports a per-dependency status so stale pins are caught before deploying"
+    )]
+    async fn verify_deps(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let folder = &self.config.project_folder;
+        let lockfile = manifest::read_lockfile(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?
+            .ok_or_else(|| {
+                rmcp::Error::invalid_request(
+                    "Move.lock not found; build the project first to resolve dependencies",
+                    None,
+                )
+            })?;
+        let graph = manifest::dependency_graph(&lockfile);
+
+        let output = command::output(
+            tokio::process::Command::new("sui")
+                .arg("client")
+                .arg("verify-source")
+                .arg("--verify-deps")
+                .arg("--skip-source")
+                .current_dir(folder),
+            &context.ct,
+        )
+        .await
+        .map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to run verify-source: {}", e), None)
+        })?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mismatches = source_verification::parse_mismatches(&stderr);
+        if !output.status.success() && mismatches.is_empty() {
+            // Failed before comparing anything, e.g. a build error or an unreachable network.
+            let (_, build_errors) = extract_build_output(&stderr);
+            let body = serde_json::json!({
+                "buildErrors": build_errors.into_iter().map(|(_, error)| error).collect::<Vec<_>>(),
+                "error": strip_ansi_escapes::strip_str(stderr.trim()),
+            });
+            return json_result(false, body);
+        }
+
+        let dependencies = graph
+            .packages
+            .iter()
+            .map(|package| {
+                let package_mismatches = mismatches
+                    .iter()
+                    .filter(|mismatch| mismatch.package.as_deref() == Some(package.name.as_str()))
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "name": package.name,
+                    "source": package.source,
+                    "status": if package_mismatches.is_empty() { "match" } else { "mismatch" },
+                    "mismatches": package_mismatches,
+                })
+            })
+            .collect::<Vec<_>>();
+        // Mismatches the CLI reports by address only cannot be tied to a locked package.
+        let unattributed = mismatches
+            .iter()
+            .filter(|mismatch| {
+                !graph
+                    .packages
+                    .iter()
+                    .any(|package| mismatch.package.as_deref() == Some(package.name.as_str()))
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "allMatch": output.status.success(),
+            "dependencies": dependencies,
+            "unattributedMismatches": unattributed,
+        });
+        json_result(output.status.success(), body)
+    }
+
+    #[tool(
+        description = "Runs `#[random_test]` tests with explicit random seeds, returning the seed of each run so failures can be reproduced. Later runs use fresh random seeds"
+    )]
+    async fn random_tests(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Test name filter, as passed to `sui move test`")]
+        filter: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Seed for the first run (random when omitted)")]
+        seed: Option<u64>,
+        #[tool(param)]
+        #[schemars(description = "Number of runs, each with a different seed (default 1, max 20)")]
+        runs: Option<u32>,
+        #[tool(param)]
+        #[schemars(
+            description = "Random inputs generated per test in each run (`--rand-num-iters`)"
+        )]
+        iterations: Option<u64>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        if let Some(filter) = &filter {
+            validate::test_filter(filter)?;
+        }
+        let runs = runs.unwrap_or(1).clamp(1, 20);
+        if iterations == Some(0) {
+            return Err(rmcp::Error::invalid_params(
+                "`iterations` must be positive",
+                None,
+            ));
+        }
+
+        let guard = self.lock_package(&context.ct).await?;
+        let mut results = vec![];
+        let mut failing_seeds = vec![];
+        for run in 0..runs {
+            let seed = match (run, seed) {
+                (0, Some(seed)) => seed,
+                _ => random_seed(),
+            };
+            let mut args = filter.iter().cloned().collect::<Vec<_>>();
+            args.extend(["--seed".to_string(), seed.to_string()]);
+            if let Some(iterations) = iterations {
+                args.extend(["--rand-num-iters".to_string(), iterations.to_string()]);
+            }
+            let TestOutput {
+                stdout,
+                stderr,
+                timed_out,
+            } = self.test_locked(&guard, &args, &context.ct).await?;
+
+            if !stdout.contains("Test result:") {
+                // The tests did not build or the run was cut off: later seeds would fare no
+                // better.
+                let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+                let body = serde_json::json!({
+                    "seed": seed,
+                    "results": results,
+                    "buildErrors": errors.values().collect::<Vec<_>>(),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+            let passed = stdout.contains("Test result: OK");
+            if !passed {
+                failing_seeds.push(seed);
+            }
+            results.push(serde_json::json!({
+                "seed": seed,
+                "passed": passed,
+                "testFailures": test_output::parse_failures(&stdout),
+            }));
+        }
+
+        let body = serde_json::json!({
+            "passed": failing_seeds.is_empty(),
+            "failingSeeds": failing_seeds,
+            "results": results,
+        });
+        json_result(failing_seeds.is_empty(), body)
+    }
+
+    #[tool(
+        description = "Returns the result of the most recent `validate_project` (or `format_and_validate`) call in this session without running anything, with when it finished and whether the sources changed since"
+    )]
+    async fn last_diagnostics(&self) -> Result<CallToolResult, rmcp::Error> {
+        let last = self.last_validation.lock().await;
+        let Some(last) = last.as_ref() else {
+            let body = serde_json::json!({
+                "state": "never_built",
+                "result": null,
+            });
+            return Ok(CallToolResult::success(vec![Content::json(body)?]));
+        };
+
+        let finished_at = last
+            .finished_at
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .and_then(|elapsed| u64::try_from(elapsed.as_millis()).ok());
+        let age_secs = last.finished_at.elapsed().map(|age| age.as_secs()).ok();
+        let current_hash = sources::package_hash(&self.config.project_folder).ok();
+        let sources_changed = match (&last.source_hash, &current_hash) {
+            (Some(before), Some(now)) => Some(before != now),
+            _ => None,
+        };
+        let body = serde_json::json!({
+            "state": "built",
+            "finishedAtMs": finished_at,
+            "ageSecs": age_secs,
+            "sourcesChanged": sources_changed,
+            "success": last.success,
+            "result": last.body,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Generates a `#[test_only]` mock of a module from its compiled ABI: a `<module>_mock` module with a stub for each public function, returning defaults for primitive and vector types and aborting otherwise. Returns the source, optionally writing it to `tests/<module>_mock.move` and formatting it"
+    )]
+    async fn generate_mock(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Name of the module to mock")]
+        module: String,
+        #[tool(param)]
+        #[schemars(
+            description = "Write the mock to `tests/<module>_mock.move` (fails if it exists)"
+        )]
+        write: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::identifier("module", &module)?;
+        let write = write.unwrap_or(false);
+        if write && self.config.read_only {
+            return Err(rmcp::Error::invalid_request(
+                "`write` is unavailable because the server is in read-only mode",
+                None,
+            ));
+        }
+        let folder = &self.config.project_folder;
+        let manifest = manifest::read_manifest(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let package_name = manifest::package_name(&manifest)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        // Held until the mock is written and formatted.
+        let guard = self.lock_package(&context.ct).await?;
+        let build = self.build_locked(&guard, false, &context.ct).await?;
+        if build.timed_out || !build.errors.is_empty() {
+            let body = serde_json::json!({
+                "buildErrors": build.errors.values().collect::<Vec<_>>(),
+                "timedOut": build.timed_out,
+            });
+            return json_result(false, body);
+        }
+
+        let path = Path::new(folder)
+            .join("build")
+            .join(&package_name)
+            .join("bytecode_modules")
+            .join(format!("{}.mv", module));
+        if !path.is_file() {
+            return Err(rmcp::Error::invalid_params(
+                format!(
+                    "Module `{}` not found in package `{}`",
+                    module, package_name
+                ),
+                None,
+            ));
+        }
+        let interface = self.disassemble(&path, &context.ct).await?.ok_or_else(|| {
+            rmcp::Error::internal_error(format!("Failed to parse module `{}`", module), None)
+        })?;
+        let mut source = interface::generate_mock(&interface);
+
+        let mut file = None;
+        let mut formatted = false;
+        if write {
+            // Checked before anything is created, and again once `tests/` exists.
+            let tests_dir = validate::allowed_path(&self.config, &Path::new(folder).join("tests"))?;
+            std::fs::create_dir_all(&tests_dir).map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to create tests/: {}", e), None)
+            })?;
+            let target = validate::allowed_path(
+                &self.config,
+                &tests_dir.join(format!("{}_mock.move", module)),
+            )?;
+            if target.exists() {
+                return Err(rmcp::Error::invalid_request(
+                    format!("`{}` already exists", target.display()),
+                    None,
+                ));
+            }
+            std::fs::write(&target, &source).map_err(|e| {
+                rmcp::Error::internal_error(
+                    format!("Failed to write `{}`: {}", target.display(), e),
+                    None,
+                )
+            })?;
+
+            let mut cmd = build_fmt_command(&self.config.movefmt_cmd, &self.config);
+            formatted = command::output(cmd.arg(&target), &context.ct)
+                .await
+                .is_ok_and(|output| output.status.success());
+            if formatted {
+                source = std::fs::read_to_string(&target).unwrap_or(source);
+            }
+            file = Some(format!("tests/{}_mock.move", module));
+        }
+
+        let body = serde_json::json!({
+            "module": format!("{}_mock", module),
+            "file": file,
+            "formatted": formatted,
+            "source": source,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Lists the available tools with their descriptions and arguments as a plain-text catalog, generated from the same metadata as `list_tools`"
+    )]
+    async fn help(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Only describe this tool")]
+        tool: Option<String>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let mut tools = self.available_tools();
+        if let Some(name) = &tool {
+            tools.retain(|tool| tool.name == name.as_str());
+            if tools.is_empty() {
+                return Err(rmcp::Error::invalid_params(
+                    format!("Unknown tool `{}`", name),
+                    None,
+                ));
+            }
+        }
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        let catalog = tools
+            .iter()
+            .map(describe_tool)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(CallToolResult::success(vec![Content::text(catalog)]))
+    }
+
+    #[tool(
+        description = "Formats only the Move files staged in git (`git diff --cached`), for pre-commit use. Formatting changes the working tree; set `restage` to stage the result too, which also stages any unstaged edits to those files"
+    )]
+    async fn format_staged(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "`git add` the formatted files afterwards")]
+        restage: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let Some(files) = self.staged_move_files(&context.ct).await else {
+            let body = serde_json::json!({
+                "error": "The project is not inside a git repository",
+                "formattedFiles": [],
+            });
+            return json_result(false, body);
+        };
+        if files.is_empty() {
+            let body = serde_json::json!({
+                "stagedFiles": files,
+                "formattedFiles": [],
+            });
+            return Ok(CallToolResult::success(vec![Content::json(body)?]));
+        }
+
+        let patterns = files
+            .iter()
+            .map(|file| glob::Pattern::escape(file))
+            .collect::<Vec<_>>();
+        let (success, mut body) = self
+            .format(&patterns, None, false, false, &context.ct)
+            .await?;
+
+        let mut restaged = false;
+        if success && restage.unwrap_or(false) {
+            let output = command::output(
+                tokio::process::Command::new("git")
+                    .arg("add")
+                    .arg("--")
+                    .args(&files)
+                    .current_dir(&self.config.project_folder),
+                &context.ct,
+            )
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("Failed to run git: {}", e), None))?;
+            restaged = output.status.success();
+        }
+
+        if let Some(fields) = body.as_object_mut() {
+            fields.insert("stagedFiles".to_string(), serde_json::json!(files));
+            fields.insert("restaged".to_string(), serde_json::json!(restaged));
+        }
+        json_result(success, body)
+    }
+
+    #[tool(
+        description = "Checks whether the project's public interface is upgrade-compatible with a previous version, given as a package directory or a published package ID. Builds both and reports struct layout, ability and public function signature changes the upgrade would reject"
+    )]
+    async fn compare_abi(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "The previous version: a package directory (relative to the project folder or absolute) or the on-chain ID of the published package"
+        )]
+        baseline: String,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::non_empty("baseline", &baseline)?;
+        let manifest = manifest::read_manifest(&self.config.project_folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let package_name = manifest::package_name(&manifest)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        // Held until the bytecode has been read, so another build cannot replace it.
+        let guard = self.lock_package(&context.ct).await?;
+        let BuildOutput {
+            errors: build_errors,
+            timed_out,
+            ..
+        } = self.build_locked(&guard, false, &context.ct).await?;
+        if timed_out || !build_errors.is_empty() {
+            let body = serde_json::json!({
+                "buildErrors": build_errors.values().collect::<Vec<_>>(),
+                "baselineBuildErrors": [],
+                "timedOut": timed_out,
+            });
+            return json_result(false, body);
+        }
+        let current = self
+            .disassemble_dir(
+                &Path::new(&self.config.project_folder)
+                    .join("build")
+                    .join(package_name)
+                    .join("bytecode_modules"),
+                &context.ct,
+            )
+            .await?;
+
+        let previous = if baseline.starts_with("0x") {
+            validate::object_id("baseline", &baseline)?;
+            self.published_interfaces(&baseline, &context.ct).await?
+        } else {
+            let folder = validate::allowed_path(
+                &self.config,
+                &Path::new(&self.config.project_folder).join(&baseline),
+            )?;
+            let manifest = manifest::read_manifest(&folder.to_string_lossy())
+                .map_err(|e| rmcp::Error::invalid_params(format!("{:#}", e), None))?;
+            let name = manifest::package_name(&manifest)
+                .map_err(|e| rmcp::Error::invalid_params(format!("{:#}", e), None))?;
+            let completion = command::run(
+                tokio::process::Command::new("sui")
+                    .arg("move")
+                    .arg("build")
+                    .current_dir(&folder),
+                &context.ct,
+                self.config.command_timeout,
+            )
+            .await
+            .map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to build baseline: {}", e), None)
+            })?;
+            let (success, stderr, timed_out) = match completion {
+                Completion::Finished(output) => (output.status.success(), output.stderr, false),
+                Completion::TimedOut { stderr, .. } => (false, stderr, true),
+            };
+            if !success {
+                let (_, errors) = extract_build_output(&String::from_utf8_lossy(&stderr));
+                let body = serde_json::json!({
+                    "buildErrors": [],
+                    "baselineBuildErrors": errors.into_values().collect::<Vec<_>>(),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+            self.disassemble_dir(
+                &folder.join("build").join(name).join("bytecode_modules"),
+                &context.ct,
+            )
+            .await?
+        };
+
+        let incompatibilities = compatibility::check_interfaces(&previous, &current);
+        let body = serde_json::json!({
+            "compatible": incompatibilities.is_empty(),
+            "incompatibilities": incompatibilities,
+            "modules": current.len(),
+            "baselineModules": previous.len(),
+        });
+        json_result(incompatibilities.is_empty(), body)
+    }
+
+    #[tool(
+        description = "Builds the project and reports how long each stage took: dependency resolution (git updates and included packages) and compilation. The CLI does not report finer compiler phases. Set `verbose` to force a full rebuild, which is slower but times every module rather than only changed ones"
+    )]
+    async fn build_timings(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Rebuild everything with `--force` instead of reusing the previous build"
+        )]
+        verbose: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let verbose = verbose.unwrap_or(false);
+        let _guard = self.lock_package(&context.ct).await?;
+        let mut cmd = tokio::process::Command::new("sui");
+        cmd.arg("move")
+            .arg("build")
+            .current_dir(&self.config.project_folder);
+        if verbose {
+            cmd.arg("--force");
+        }
+        let started = std::time::Instant::now();
+        let (status, lines) = command::timed_lines(&mut cmd, &context.ct)
+            .await
+            .map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to build project: {}", e), None)
+            })?;
+        let total = started.elapsed();
+
+        let output = lines
+            .iter()
+            .map(|(_, line)| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (_, build_errors) = extract_build_output(&output);
+        let body = serde_json::json!({
+            "success": status.success(),
+            "forced": verbose,
+            "totalMs": total.as_millis() as u64,
+            "stages": build_stages(&lines, total),
+            "buildErrors": build_errors.into_values().collect::<Vec<_>>(),
+        });
+        json_result(status.success(), body)
+    }
+
+    #[tool(
+        description = "Lists the dynamic fields of an object with their name, kind, value type and current value, paginated. Returns an empty list for objects without dynamic fields"
+    )]
+    async fn dynamic_fields(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "ID of the parent object")]
+        object_id: String,
+        #[tool(param)]
+        #[schemars(description = "Number of fields to return (default 20, max 50)")]
+        limit: Option<usize>,
+        #[tool(param)]
+        #[schemars(description = "`nextCursor` from a previous call, to fetch the next page")]
+        cursor: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::object_id("object_id", &object_id)?;
+        let ct = &context.ct;
+        let page = self
+            .rpc_query(
+                "suix_getDynamicFields",
+                serde_json::json!([object_id, cursor, limit.unwrap_or(20).clamp(1, 50)]),
+                ct,
+            )
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let entries = page["data"].as_array().cloned().unwrap_or_default();
+
+        // Field objects wrap the value as `Field { id, name, value }`; dynamic object fields
+        // point at the child object itself.
+        let ids = entries
+            .iter()
+            .filter_map(|entry| entry["objectId"].as_str())
+            .collect::<Vec<_>>();
+        let objects = if ids.is_empty() {
+            vec![]
+        } else {
+            self.rpc_query(
+                "sui_multiGetObjects",
+                serde_json::json!([ids, { "showContent": true }]),
+                ct,
+            )
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+        };
+
+        let fields = entries
+            .iter()
+            .map(|entry| {
+                let content = objects
+                    .iter()
+                    .find(|object| object["data"]["objectId"] == entry["objectId"])
+                    .map(|object| &object["data"]["content"]["fields"]);
+                let value = match (entry["type"].as_str(), content) {
+                    (Some("DynamicObject"), Some(fields)) => fields.clone(),
+                    (_, Some(fields)) => fields["value"].clone(),
+                    (_, None) => serde_json::Value::Null,
+                };
+                serde_json::json!({
+                    "name": entry["name"],
+                    "kind": entry["type"],
+                    "objectType": entry["objectType"],
+                    "objectId": entry["objectId"],
+                    "value": value,
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "objectId": object_id,
+            "fields": fields,
+            "nextCursor": page["nextCursor"],
+            "hasNextPage": page["hasNextPage"].as_bool().unwrap_or(false),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Resolves a fully-qualified Move type (e.g. `0x2::coin::Coin<0x2::sui::SUI>`) to its nested field layout in BCS order, using the on-chain struct definitions and substituting generic type arguments"
+    )]
+    async fn type_layout(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "The type, with addresses in hex and all type arguments given, e.g. `0x2::balance::Balance<0x2::sui::SUI>` or `vector<u8>`"
+        )]
+        type_name: String,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let tag = type_layout::parse(&type_name).map_err(|e| {
+            rmcp::Error::invalid_params(format!("Invalid `type_name`: {}", e), None)
+        })?;
+
+        // Fetch definitions breadth-first until every struct in the layout is known.
+        const MAX_STRUCTS: usize = 64;
+        let mut structs = HashMap::new();
+        loop {
+            let missing = type_layout::missing_structs(&tag, &structs);
+            if missing.is_empty() {
+                break;
+            }
+            if structs.len() + missing.len() > MAX_STRUCTS {
+                return Err(rmcp::Error::invalid_params(
+                    format!("`{}` nests more than {} structs", type_name, MAX_STRUCTS),
+                    None,
+                ));
+            }
+            for struct_tag in missing {
+                let definition = self
+                    .rpc_query(
+                        "sui_getNormalizedMoveStruct",
+                        serde_json::json!([struct_tag.address, struct_tag.module, struct_tag.name]),
+                        &context.ct,
+                    )
+                    .await
+                    .map_err(|e| {
+                        rmcp::Error::invalid_params(
+                            format!("Failed to resolve `{}`: {:#}", struct_tag.key(), e),
+                            None,
+                        )
+                    })?;
+                let expected = definition["typeParameters"]
+                    .as_array()
+                    .map_or(0, |params| params.len());
+                if struct_tag.type_args.len() != expected {
+                    return Err(rmcp::Error::invalid_params(
+                        format!(
+                            "`{}` takes {} type argument(s), got {}",
+                            struct_tag.key(),
+                            expected,
+                            struct_tag.type_args.len()
+                        ),
+                        None,
+                    ));
+                }
+                structs.insert(struct_tag.key(), definition);
+            }
+        }
+
+        let body = serde_json::json!({ "layout": type_layout::layout(&tag, &structs) });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Runs the full test suite twice and reports the tests whose outcome (pass, fail or timeout) differed between the runs, a sign of order dependence or randomness"
+    )]
+    async fn check_determinism(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        // Held across both runs, so a concurrent build cannot change what the second one tests.
+        let guard = self.lock_package(&context.ct).await?;
+        let mut runs = vec![];
+        for run in 1..=2 {
+            let TestOutput {
+                stdout,
+                stderr,
+                timed_out,
+            } = self.test_locked(&guard, &[], &context.ct).await?;
+            let statuses = test_output::parse_statuses(&stdout);
+            if timed_out || statuses.is_empty() {
+                // No per-test results: the tests did not build or the run was cut off.
+                let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+                let body = serde_json::json!({
+                    "run": run,
+                    "buildErrors": errors.values().collect::<Vec<_>>(),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+            let failures = test_output::parse_failures(&stdout);
+            runs.push((statuses.into_iter().collect::<HashMap<_, _>>(), failures));
+        }
+
+        let (first, first_failures) = &runs[0];
+        let (second, second_failures) = &runs[1];
+        let mut names = first.keys().chain(second.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let failure_message = |failures: &[test_output::TestFailure], name: &str| {
+            failures
+                .iter()
+                .find(|failure| failure.name == name)
+                .map(|failure| failure.message.clone())
+        };
+        let divergent = names
+            .iter()
+            .filter(|name| first.get(**name) != second.get(**name))
+            .map(|name| {
+                serde_json::json!({
+                    "name": name,
+                    "first": first.get(*name),
+                    "second": second.get(*name),
+                    "firstFailure": failure_message(first_failures, name),
+                    "secondFailure": failure_message(second_failures, name),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let body = serde_json::json!({
+            "tests": names.len(),
+            "deterministic": divergent.is_empty(),
+            "divergent": divergent,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Reusable programmable transaction block templates (transfer, split_and_transfer, move_call_chain). Without `template`, lists them with their parameter schemas. With `template` and `params`, validates the parameters and returns the `sui client ptb` arguments and command line; `dry_run` also simulates the transaction"
+    )]
+    async fn ptb_template(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Template to instantiate")]
+        template: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Template parameters, matching the template's schema")]
+        params: Option<serde_json::Value>,
+        #[tool(param)]
+        #[schemars(
+            description = "Simulate the transaction with `--dry-run` and report its status and gas"
+        )]
+        dry_run: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Gas budget in MIST for the transaction")]
+        gas_budget: Option<u64>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        validate::gas_budget(gas_budget)?;
+        let describe = |template: &ptb::Template| {
+            serde_json::json!({
+                "name": template.name,
+                "description": template.description,
+                "parameters": (template.parameters)(),
+            })
+        };
+        let Some(name) = template else {
+            let templates = ptb::TEMPLATES.iter().map(describe).collect::<Vec<_>>();
+            let body = serde_json::json!({ "templates": templates });
+            return Ok(CallToolResult::success(vec![Content::json(body)?]));
+        };
+        let template = ptb::find(&name).ok_or_else(|| {
+            rmcp::Error::invalid_params(format!("Unknown template `{}`", name), None)
+        })?;
+        let Some(params) = params else {
+            let body = serde_json::json!({ "template": describe(template) });
+            return Ok(CallToolResult::success(vec![Content::json(body)?]));
+        };
+
+        let mut args = template
+            .build(&params)
+            .map_err(|e| rmcp::Error::invalid_params(e, None))?;
+        if let Some(gas_budget) = gas_budget {
+            args.push("--gas-budget".to_string());
+            args.push(gas_budget.to_string());
+        }
+        let command = std::iter::once("sui client ptb".to_string())
+            .chain(args.iter().map(|arg| shell_quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut body = serde_json::json!({
+            "template": template.name,
+            "args": args,
+            "command": command,
+        });
+
+        if dry_run.unwrap_or(false) {
+            let mut dry_run_args = vec!["ptb".to_string()];
+            dry_run_args.extend(args);
+            dry_run_args.push("--dry-run".to_string());
+            let response = client::json(&self.config.project_folder, &dry_run_args, &context.ct)
+                .await
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            let (success, error) = client::transaction_status(&response);
+            body["dryRun"] = serde_json::json!({
+                "status": response["effects"]["status"]["status"],
+                "error": error,
+                "gas": gas_report(&response["effects"]["gasUsed"]),
+            });
+            return json_result(success, body);
+        }
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Builds a scratch copy of the package once per Move edition, overriding `[package] edition` in the copy's Move.toml, and reports which editions it compiles under with each edition's diagnostics. The project itself is not modified"
+    )]
+    async fn check_editions(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Editions to try: `legacy`, `2024.alpha`, `2024.beta` or `2024`. Defaults to all of them"
+        )]
+        editions: Option<Vec<String>>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let editions = editions
+            .filter(|editions| !editions.is_empty())
+            .unwrap_or_else(|| {
+                SUPPORTED_EDITIONS
+                    .iter()
+                    .map(|edition| edition.to_string())
+                    .collect()
+            });
+        if let Some(edition) = editions
+            .iter()
+            .find(|edition| !SUPPORTED_EDITIONS.contains(&edition.as_str()))
+        {
+            return Err(rmcp::Error::invalid_params(
+                format!(
+                    "Unsupported edition `{}`, expected one of {}",
+                    edition,
+                    SUPPORTED_EDITIONS.join(", ")
+                ),
+                None,
+            ));
+        }
+
+        let original = self.read_manifest_contents()?;
+        let current = manifest::read_manifest(&self.config.project_folder)
+            .ok()
+            .and_then(|manifest| {
+                manifest
+                    .get("package")?
+                    .get("edition")?
+                    .as_str()
+                    .map(str::to_string)
+            });
+        let variants = editions
+            .iter()
+            .map(|edition| {
+                let contents = manifest::with_edition(&original, edition)
+                    .map_err(|e| rmcp::Error::invalid_request(format!("{:#}", e), None))?;
+                Ok((format!("edition `{}`", edition), contents))
+            })
+            .collect::<Result<Vec<_>, rmcp::Error>>()?;
+        let mut results = self.build_manifest_variants(variants, &context.ct).await?;
+        for (result, edition) in results.iter_mut().zip(&editions) {
+            result["edition"] = serde_json::json!(edition);
+        }
+
+        let compiles_under = results
+            .iter()
+            .filter(|result| result["compiles"] == true)
+            .map(|result| result["edition"].clone())
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "currentEdition": current,
+            "compilesUnder": compiles_under,
+            "editions": results,
+        });
+        json_result(!compiles_under.is_empty(), body)
+    }
+
+    #[tool(
+        description = "Builds the package under `/usr/bin/time` and reports the peak memory (resident set size) of the build, for sizing machines that build large packages. Only offered when the server sets MEASURE_BUILD_MEMORY"
+    )]
+    async fn build_memory(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Rebuild everything with `--force`, measuring a full build instead of an incremental one"
+        )]
+        force: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let force = force.unwrap_or(false);
+        let _guard = self.lock_package(&context.ct).await?;
+        // Without the tooling the build still runs, reporting no peak memory.
+        let (mut cmd, unsupported) = match memory::measured_command("sui") {
+            Ok(cmd) => (cmd, None),
+            Err(reason) => (tokio::process::Command::new("sui"), Some(reason)),
+        };
+        cmd.arg("move")
+            .arg("build")
+            .current_dir(&self.config.project_folder);
+        if force {
+            cmd.arg("--force");
+        }
+        let completion = command::run(&mut cmd, &context.ct, self.config.command_timeout)
+            .await
+            .map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to build project: {}", e), None)
+            })?;
+        let (success, stderr, timed_out) = match completion {
+            Completion::Finished(output) => (output.status.success(), output.stderr, false),
+            Completion::TimedOut { stderr, .. } => (false, stderr, true),
+        };
+        let stderr = String::from_utf8_lossy(&stderr);
+        let (_, build_errors) = extract_build_output(&stderr);
+
+        let peak = memory::parse_peak_rss(&stderr);
+        let unsupported = unsupported.or_else(|| {
+            peak.is_none()
+                .then(|| "`/usr/bin/time` did not report a maximum resident set size".to_string())
+        });
+        let body = serde_json::json!({
+            "success": success,
+            "forced": force,
+            "peakMemoryBytes": peak,
+            "peakMemoryMb": peak.map(|bytes| bytes as f64 / (1024.0 * 1024.0)),
+            "unsupported": unsupported,
+            "terminatedBySignal": memory::terminating_signal(&stderr),
+            "timedOut": timed_out,
+            "buildErrors": build_errors.into_values().collect::<Vec<_>>(),
+        });
+        json_result(success, body)
+    }
+
+    #[tool(
+        description = "Scans Move sources for addresses written in more than one way (`0x1`, `0x01`, padded forms, or a named address such as `std` for the same value) and proposes rewriting hex literals in a canonical notation, as a unified diff. Report-only unless `apply` is set; named addresses are never rewritten. Files resolving outside the allowed directories are skipped and listed under `outsideAllowedDirs`"
+    )]
+    async fn address_notation(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Canonical notation for hex literals: `short` (`0x2`, default) or `long` (64 digits)"
+        )]
+        notation: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Write the canonicalized sources instead of only reporting")]
+        apply: Option<bool>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let notation_name = notation.as_deref().unwrap_or("short");
+        let notation = addresses::Notation::parse(notation_name).ok_or_else(|| {
+            rmcp::Error::invalid_params(
+                format!(
+                    "`notation` must be `short` or `long`, got `{}`",
+                    notation_name
+                ),
+                None,
+            )
+        })?;
+        let apply = apply.unwrap_or(false);
+        if apply && self.config.read_only {
+            return Err(rmcp::Error::invalid_request(
+                "`apply` is unavailable because the server is in read-only mode",
+                None,
+            ));
+        }
+        let _guard = if apply {
+            Some(self.lock_package(&context.ct).await?)
+        } else {
+            None
+        };
+
+        let folder = &self.config.project_folder;
+        let bindings = manifest::address_bindings(folder, false)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?
+            .into_iter()
+            .map(|binding| {
+                let address = binding
+                    .address
+                    .as_deref()
+                    .and_then(type_layout::normalize_address);
+                (binding.name, address)
+            })
+            .collect::<HashMap<_, _>>();
+        let files = sources::move_files(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        // Spellings of each address across the package, with how often each is used.
+        let mut spellings =
+            std::collections::BTreeMap::<_, std::collections::BTreeMap<_, _>>::new();
+        let mut changes = vec![];
+        let mut patches = vec![];
+        let mut outside = vec![];
+        for path in files {
+            let file = path.to_string_lossy().to_string();
+            // A symlinked source could lead outside the directories the server may touch.
+            let Ok(full_path) =
+                validate::allowed_path(&self.config, &Path::new(folder).join(&path))
+            else {
+                outside.push(file);
+                continue;
+            };
+            let source = std::fs::read_to_string(&full_path).map_err(|e| {
+                rmcp::Error::internal_error(format!("Failed to read `{}`: {}", file, e), None)
+            })?;
+            let uses = addresses::find_uses(&source, &bindings);
+            for address_use in &uses {
+                let Some(address) = &address_use.address else {
+                    continue;
+                };
+                *spellings
+                    .entry(address.clone())
+                    .or_default()
+                    .entry((address_use.text.clone(), address_use.named))
+                    .or_insert(0) += 1;
+                let canonical = notation.render(address);
+                if !address_use.named && address_use.text != canonical {
+                    changes.push(serde_json::json!({
+                        "file": file,
+                        "line": address_use.line,
+                        "column": address_use.column,
+                        "from": address_use.text,
+                        "to": canonical,
+                    }));
+                }
+            }
+
+            let canonicalized = addresses::canonicalize(&source, &uses, notation);
+            if let Some((patch, hunks)) = diff::file_patch(&file, &source, &canonicalized) {
+                if apply {
+                    std::fs::write(&full_path, &canonicalized).map_err(|e| {
+                        rmcp::Error::internal_error(
+                            format!("Failed to write `{}`: {}", file, e),
+                            None,
+                        )
+                    })?;
+                }
+                patches.push((file, patch, hunks));
+            }
+        }
+
+        let inconsistencies = spellings
+            .iter()
+            .filter(|(_, used)| used.len() > 1)
+            .map(|(address, used)| {
+                let named = bindings
+                    .iter()
+                    .filter(|(_, bound)| bound.as_ref() == Some(address))
+                    .map(|(name, _)| name.clone())
+                    .collect::<std::collections::BTreeSet<_>>();
+                serde_json::json!({
+                    "address": notation.render(address),
+                    "namedAddresses": named,
+                    "spellings": used
+                        .iter()
+                        .map(|((text, named), count)| serde_json::json!({
+                            "text": text,
+                            "named": named,
+                            "uses": count,
+                        }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "notation": notation_name,
+            "consistent": inconsistencies.is_empty() && changes.is_empty(),
+            "applied": apply && !patches.is_empty(),
+            "inconsistencies": inconsistencies,
+            "changes": changes,
+            "outsideAllowedDirs": outside,
+            "patch": patches
+                .iter()
+                .map(|(_, patch, _)| patch.as_str())
+                .collect::<String>(),
+            "patches": patches
+                .iter()
+                .map(|(file, _, hunks)| serde_json::json!({ "file": file, "hunks": hunks }))
+                .collect::<Vec<_>>(),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Returns the progress of this session's running (or latest) build or test: the git dependencies fetched and dependencies compiled so far with timings, and how long since the command last printed anything. Poll it while a slow first build fetches dependencies to tell progress from a hang"
+    )]
+    async fn build_progress(&self) -> Result<CallToolResult, rmcp::Error> {
+        Ok(CallToolResult::success(vec![Content::json(
+            self.build_progress.snapshot(),
+        )?]))
+    }
+
+    #[tool(
+        description = "Reruns only the tests that failed or timed out in the last `validate_project` (or `rerun_failed`) run, each by its full name, and reports which now pass. Runs the whole suite when there is no record of failures. The failed set is updated after each run"
+    )]
+    async fn rerun_failed(
+        &self,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let guard = self.lock_package(&context.ct).await?;
+        let previous = self
+            .failed_tests
+            .lock()
+            .await
+            .clone()
+            .filter(|failed| !failed.is_empty());
+        let Some(previous) = previous else {
+            let TestOutput {
+                stdout,
+                stderr,
+                timed_out,
+            } = self.test_locked(&guard, &[], &context.ct).await?;
+            let statuses = test_output::parse_statuses(&stdout);
+            if timed_out || statuses.is_empty() {
+                let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+                let body = serde_json::json!({
+                    "mode": "full",
+                    "buildErrors": errors.values().collect::<Vec<_>>(),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+            self.record_failed_tests(&statuses).await;
+            let failing = statuses
+                .iter()
+                .filter(|(_, status)| *status != test_output::TestStatus::Pass)
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            let body = serde_json::json!({
+                "mode": "full",
+                "ran": statuses.len(),
+                "stillFailing": failing,
+                "failures": test_output::parse_failures(&stdout),
+            });
+            return json_result(failing.is_empty(), body);
+        };
+
+        // The filter matches by substring, so each run is narrowed to the exact name.
+        let mut statuses = vec![];
+        let mut failures = vec![];
+        let mut missing = vec![];
+        for name in &previous {
+            let TestOutput {
+                stdout,
+                stderr,
+                timed_out,
+            } = self
+                .test_locked(&guard, std::slice::from_ref(name), &context.ct)
+                .await?;
+            let ran = test_output::parse_statuses(&stdout);
+            match ran.into_iter().find(|(ran, _)| ran == name) {
+                Some(status) => statuses.push(status),
+                None if stdout.contains("Total tests: 0") => missing.push(name.clone()),
+                None => {
+                    // Neither passed nor failed: the tests did not build or the run was cut off.
+                    let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+                    let body = serde_json::json!({
+                        "mode": "failed",
+                        "test": name,
+                        "buildErrors": errors.values().collect::<Vec<_>>(),
+                        "timedOut": timed_out,
+                    });
+                    return json_result(false, body);
+                }
+            }
+            failures.extend(
+                test_output::parse_failures(&stdout)
+                    .into_iter()
+                    .filter(|failure| &failure.name == name),
+            );
+        }
+
+        self.record_failed_tests(&statuses).await;
+        let (passed, still_failing): (Vec<_>, Vec<_>) = statuses
+            .iter()
+            .partition(|(_, status)| *status == test_output::TestStatus::Pass);
+        let names = |tests: &[&(String, test_output::TestStatus)]| {
+            tests
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>()
+        };
+        let body = serde_json::json!({
+            "mode": "failed",
+            "ran": statuses.len(),
+            "nowPassing": names(&passed),
+            "stillFailing": names(&still_failing),
+            "missing": missing,
+            "failures": failures,
+        });
+        json_result(still_failing.is_empty(), body)
+    }
+
+    #[tool(
+        description = "Builds the package and cuts the module with a diagnostic down to a minimal reproduction: the function, struct or constant containing it, the items of the module it refers to (transitively) and the `use` declarations they need. Best-effort and read-only; returns the snippet with the diagnostic, e.g. for an upstream bug report"
+    )]
+    async fn minimal_repro(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Source file of the diagnostic, relative to the project (e.g. `sources/vault.move`)"
+        )]
+        file: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Line of the diagnostic")]
+        line: Option<u32>,
+        #[tool(param)]
+        #[schemars(
+            description = "Diagnostic code (e.g. `E04007`), to pick the first error or warning with it, or to narrow `file`/`line`"
+        )]
+        code: Option<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        if code.is_none() && (file.is_none() || line.is_none()) {
+            return Err(rmcp::Error::invalid_params(
+                "Pass `file` and `line`, or a diagnostic `code`",
+                None,
+            ));
+        }
+        let BuildOutput {
+            warnings,
+            errors,
+            timed_out,
+            ..
+        } = {
+            let guard = self.lock_package(&context.ct).await?;
+            self.build_locked(&guard, false, &context.ct).await?
+        };
+
+        let file = file.map(|file| file.trim_start_matches("./").to_string());
+        let matches = |notice: &LineNotice| {
+            file.as_deref()
+                .is_none_or(|file| notice.file.trim_start_matches("./") == file)
+                && line.is_none_or(|line| notice.line_number == line)
+                && code.as_deref().is_none_or(|code| {
+                    notice.code == code || notice.code.split_whitespace().any(|part| part == code)
+                })
+        };
+        // Errors first, then the earliest location, so the pick does not depend on map order.
+        let mut candidates = errors
+            .iter()
+            .map(|(notice, message)| (0, notice, message))
+            .chain(
+                warnings
+                    .iter()
+                    .map(|(notice, message)| (1, notice, message)),
+            )
+            .filter(|(_, notice, _)| matches(notice))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(severity, notice, _)| {
+            (
+                *severity,
+                &notice.file,
+                notice.line_number,
+                notice.column_number,
+            )
+        });
+        let diagnostic = candidates.first();
+
+        let (file, line) = match (diagnostic, file, line) {
+            (Some((_, notice, _)), _, _) => (
+                notice.file.trim_start_matches("./").to_string(),
+                notice.line_number,
+            ),
+            (None, Some(file), Some(line)) => (file, line),
+            _ => {
+                let body = serde_json::json!({
+                    "error": format!(
+                        "No diagnostic with code `{}` in the build",
+                        code.unwrap_or_default()
+                    ),
+                    "timedOut": timed_out,
+                });
+                return json_result(false, body);
+            }
+        };
+        let path = validate::allowed_path(
+            &self.config,
+            &Path::new(&self.config.project_folder).join(&file),
+        )?;
+        let source = std::fs::read_to_string(&path).map_err(|e| {
+            rmcp::Error::invalid_params(format!("Failed to read `{}`: {}", file, e), None)
+        })?;
+        let repro = repro::extract(&source, line as usize)
+            .map_err(|e| rmcp::Error::invalid_params(e, None))?;
+
+        let body = serde_json::json!({
+            "file": file,
+            "line": line,
+            "diagnostic": diagnostic.map(|(_, _, message)| message),
+            "module": repro.module,
+            "item": repro.item,
+            "included": repro.included,
+            "snippet": repro.snippet,
+            "timedOut": timed_out,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Returns the package's friendship graph: each module's `friend` declarations, the modules that declare it a friend, and its `public(friend)` and `public(package)` functions. Flags dangling friend declarations naming modules that do not exist in the package"
+    )]
+    async fn friend_graph(&self) -> Result<CallToolResult, rmcp::Error> {
+        let folder = &self.config.project_folder;
+        let declarations = sources::friend_declarations(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let files = sources::move_files(folder)
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+
+        let mut modules = std::collections::BTreeMap::<String, sources::Friendship>::new();
+        for path in files {
+            let source = std::fs::read_to_string(Path::new(folder).join(&path))
+                .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+            for module in outline::parse(&source).modules {
+                let functions = |visibility: &str| {
+                    module
+                        .functions
+                        .iter()
+                        .filter(|function| function.visibility == visibility)
+                        .map(|function| function.name.clone())
+                        .collect::<Vec<_>>()
+                };
+                let friendship = modules.entry(module.name.clone()).or_default();
+                friendship.friend_functions = functions("friend");
+                friendship.package_functions = functions("package");
+            }
+        }
+        for declaration in &declarations {
+            modules
+                .entry(declaration.module.clone())
+                .or_default()
+                .friends
+                .push(declaration.friend.clone());
+            if !declaration.dangling {
+                modules
+                    .entry(declaration.friend.clone())
+                    .or_default()
+                    .friend_of
+                    .push(declaration.module.clone());
+            }
+        }
+
+        let dangling = declarations
+            .iter()
+            .filter(|declaration| declaration.dangling)
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "modules": modules,
+            "declarations": declarations,
+            "dangling": dangling,
+        });
+        json_result(dangling.is_empty(), body)
+    }
+
+    #[tool(
+        description = "Checks the package against specific Sui framework revisions: pins the `Sui` dependency to each revision in turn in a scratch copy of the package, builds it, and reports whether the code compiles with the diagnostics per revision. The project itself is not modified. Revisions not yet cached are fetched from GitHub, which can take several minutes"
+    )]
+    async fn check_framework_revs(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Git revisions of the Sui repository to build against: commit hashes, tags or branches such as `framework/testnet`. At most 5"
+        )]
+        revs: Vec<String>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        if revs.is_empty() || revs.len() > MAX_FRAMEWORK_REVS {
+            return Err(rmcp::Error::invalid_params(
+                format!("Expected between 1 and {} revisions", MAX_FRAMEWORK_REVS),
+                None,
+            ));
+        }
+        if let Some(rev) = revs.iter().find(|rev| {
+            rev.is_empty()
+                || rev.starts_with('-')
+                || !rev
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._/-".contains(c))
+        }) {
+            return Err(rmcp::Error::invalid_params(
+                format!("Invalid revision `{}`", rev),
+                None,
+            ));
+        }
+
+        let original = self.read_manifest_contents()?;
+        let current = manifest::read_manifest(&self.config.project_folder)
+            .ok()
+            .and_then(|manifest| manifest::framework_rev(&manifest));
+        let variants = revs
+            .iter()
+            .map(|rev| {
+                let contents = manifest::with_framework_rev(&original, rev)
+                    .map_err(|e| rmcp::Error::invalid_request(format!("{:#}", e), None))?;
+                Ok((format!("framework rev `{}`", rev), contents))
+            })
+            .collect::<Result<Vec<_>, rmcp::Error>>()?;
+        let mut results = self.build_manifest_variants(variants, &context.ct).await?;
+        for (result, rev) in results.iter_mut().zip(&revs) {
+            result["rev"] = serde_json::json!(rev);
+        }
+
+        let compatible_with = results
+            .iter()
+            .filter(|result| result["compiles"] == true)
+            .map(|result| result["rev"].clone())
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "currentRev": current,
+            "compatibleWith": compatible_with,
+            "revs": results,
+        });
+        json_result(compatible_with.len() == revs.len(), body)
+    }
+
+    #[tool(
+        description = "Runs the test suite with per-test statistics and returns the slowest tests, longest first, with their module, name and duration in seconds. Helps find expensive test setup or logic"
+    )]
+    async fn slowest_tests(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of tests to return. Defaults to 10")]
+        count: Option<usize>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let count = count.unwrap_or(10);
+        let TestOutput {
+            stdout,
+            stderr,
+            timed_out,
+        } = self
+            .test(&["--statistics".to_string()], &context.ct)
+            .await?;
+        let mut statistics = test_output::parse_statistics(&stdout);
+        if timed_out || statistics.is_empty() {
+            // No timings: the tests did not build, there are none, or the run was cut off.
+            let (_, errors) = self.extract_diagnostics(&stderr, &context.ct).await;
+            let body = serde_json::json!({
+                "tests": 0,
+                "slowest": [],
+                "buildErrors": errors.values().collect::<Vec<_>>(),
+                "timedOut": timed_out,
+            });
+            return json_result(!timed_out && errors.is_empty(), body);
+        }
+
+        statistics.sort_by(|a, b| b.seconds.total_cmp(&a.seconds));
+        let slowest = statistics
+            .iter()
+            .take(count)
+            .map(|entry| {
+                let (path, test) = entry.name.rsplit_once("::").unwrap_or(("", &entry.name));
+                let module = path.rsplit("::").next().unwrap_or(path);
+                serde_json::json!({
+                    "module": module,
+                    "test": test,
+                    "seconds": entry.seconds,
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "tests": statistics.len(),
+            "slowest": slowest,
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Lists recent events of a Move event type (e.g. `0x2::coin::CoinMetadata<...>` or `<package>::module::Event`), newest first and paginated, decoded into JSON using the type's struct layout. Events that cannot be decoded are returned as raw BCS"
+    )]
+    async fn query_events(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Fully-qualified event struct type, with type arguments when it is generic"
+        )]
+        event_type: String,
+        #[tool(param)]
+        #[schemars(description = "Number of events to return (default 20, max 50)")]
+        limit: Option<usize>,
+        #[tool(param)]
+        #[schemars(description = "`nextCursor` from a previous call, to fetch older events")]
+        cursor: Option<serde_json::Value>,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let tag = type_layout::parse(&event_type).map_err(|e| {
+            rmcp::Error::invalid_params(format!("Invalid `event_type`: {}", e), None)
+        })?;
+        if !matches!(tag, type_layout::TypeTag::Struct(_)) {
+            return Err(rmcp::Error::invalid_params(
+                "`event_type` must be a struct type",
+                None,
+            ));
+        }
+        // Addresses in full form, as the fullnode compares the type by its string.
+        let event_type = tag.to_string();
+        let page = match self
+            .rpc_query(
+                "suix_queryEvents",
+                serde_json::json!([
+                    { "MoveEventType": event_type },
+                    cursor,
+                    limit.unwrap_or(20).clamp(1, 50),
+                    true,
+                ]),
+                &context.ct,
+            )
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{:#}",
+                    e
+                ))]));
+            }
+        };
+
+        let events = page["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|event| {
+                let mut decoded = decode_event(event);
+                decoded["id"] = event["id"].clone();
+                decoded["timestampMs"] = event["timestampMs"].clone();
+                decoded
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "eventType": event_type,
+            "events": events,
+            "nextCursor": page["nextCursor"],
+            "hasNextPage": page["hasNextPage"].as_bool().unwrap_or(false),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+
+    #[tool(
+        description = "Converts an amount between SUI and MIST (1 SUI = 1,000,000,000 MIST), e.g. before passing a coin amount or gas budget to a call. SUI amounts are exact decimals with at most 9 decimal places; MIST amounts are whole numbers"
+    )]
+    async fn convert_units(
+        &self,
+        #[tool(param)]
+        #[schemars(
+            description = "Amount to convert, as a string so no precision is lost, e.g. `1.5` SUI or `1500000000` MIST"
+        )]
+        amount: String,
+        #[tool(param)]
+        #[schemars(description = "Unit of `amount`: `sui` or `mist`")]
+        from: String,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        let mist = match from.to_ascii_lowercase().as_str() {
+            "sui" => units::sui_to_mist(&amount),
+            "mist" => units::parse_mist(&amount),
+            _ => {
+                return Err(rmcp::Error::invalid_params(
+                    format!("Unknown unit `{}`, expected `sui` or `mist`", from),
+                    None,
+                ));
+            }
+        }
+        .map_err(|e| rmcp::Error::invalid_params(e, None))?;
+        let body = serde_json::json!({
+            "sui": units::mist_to_sui(mist),
+            // A string, as MIST amounts can exceed what JSON numbers hold exactly.
+            "mist": mist.to_string(),
+        });
+        Ok(CallToolResult::success(vec![Content::json(body)?]))
+    }
+}
+
+impl SuiService {
+    /// Disassembles every `.mv` module in a directory, in file name order.
+    async fn disassemble_dir(
+        &self,
+        dir: &Path,
+        ct: &CancellationToken,
+    ) -> Result<Vec<interface::ModuleInterface>, rmcp::Error> {
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(|e| {
+                rmcp::Error::internal_error(
+                    format!("Failed to read `{}`: {}", dir.display(), e),
+                    None,
+                )
+            })?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mv"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut modules = vec![];
+        for path in paths {
+            modules.extend(self.disassemble(&path, ct).await?);
+        }
+        Ok(modules)
+    }
+
+    /// Downloads the modules of a published package into the scratch directory and
+    /// disassembles them.
+    async fn published_interfaces(
+        &self,
+        package_id: &str,
+        ct: &CancellationToken,
+    ) -> Result<Vec<interface::ModuleInterface>, rmcp::Error> {
+        let object = self
+            .rpc_query(
+                "sui_getObject",
+                serde_json::json!([package_id, { "showBcs": true }]),
+                ct,
+            )
+            .await
+            .map_err(|e| rmcp::Error::internal_error(format!("{:#}", e), None))?;
+        let module_map = object["data"]["bcs"]["moduleMap"]
+            .as_object()
+            .ok_or_else(|| {
+                rmcp::Error::invalid_params(format!("`{}` is not a package", package_id), None)
+            })?;
+
+        let dir = self.scratch.create_dir("abi").map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to create scratch directory: {}", e), None)
+        })?;
+        let write = || -> anyhow::Result<()> {
+            for (name, bytes) in module_map {
+                let bytes = bytes
+                    .as_str()
+                    .and_then(rpc::decode_base64)
+                    .ok_or_else(|| anyhow::anyhow!("Module `{}` is not valid base64", name))?;
+                std::fs::write(dir.join(format!("{}.mv", name)), bytes)?;
+            }
+            Ok(())
+        };
+        let modules = match write() {
+            Ok(()) => self.disassemble_dir(&dir, ct).await,
+            Err(e) => Err(rmcp::Error::internal_error(
+                format!("Failed to save the published modules: {:#}", e),
+                None,
+            )),
+        };
+        let _ = std::fs::remove_dir_all(&dir);
+        modules
+    }
+
+    /// Disassembles a compiled module into its interface, or `None` if the output is not
+    /// recognised.
+    async fn disassemble(
+        &self,
+        path: &Path,
+        ct: &CancellationToken,
+    ) -> Result<Option<interface::ModuleInterface>, rmcp::Error> {
+        let output = command::output(
+            tokio::process::Command::new("sui")
+                .arg("move")
+                .arg("disassemble")
+                .arg(path)
+                .current_dir(&self.config.project_folder),
+            ct,
+        )
+        .await
+        .map_err(|e| {
+            rmcp::Error::internal_error(
+                format!("Failed to disassemble `{}`: {}", path.display(), e),
+                None,
+            )
+        })?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let module = interface::parse_disassembly(&stdout);
+        if module.is_none() {
+            tracing::warn!(path = %path.display(), "unrecognised disassembly output");
+        }
+        Ok(module)
+    }
+
+    /// Names of every tool the server implements, before any filtering.
+    pub fn tool_names() -> Vec<String> {
+        Self::tool_box()
+            .list()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    /// The tools this session offers, leaving out disabled tools, and mutating ones in
+    /// read-only mode.
+    fn available_tools(&self) -> Vec<rmcp::model::Tool> {
+        let mut tools = Self::tool_box().list();
+        tools.retain(|tool| !self.is_disabled(&tool.name));
+        if self.config.read_only {
+            tools.retain(|tool| !MUTATING_TOOLS.contains(&tool.name.as_ref()));
+        }
+        tools
+    }
+
+    /// Whether `ENABLED_TOOLS`/`DISABLED_TOOLS`, or a feature flag, exclude a tool.
+    fn is_disabled(&self, name: &str) -> bool {
+        let enabled = self
+            .config
+            .enabled_tools
+            .as_ref()
+            .is_none_or(|enabled| enabled.iter().any(|tool| tool == name));
+        let gated = name == "build_memory" && !self.config.measure_build_memory;
+        !enabled || gated || self.config.disabled_tools.iter().any(|tool| tool == name)
+    }
+
+    /// Starts tracking the progress of a build or test run, until the guard is dropped.
+    fn start_progress(&self, command: &'static str) -> progress::RunGuard<'_> {
+        let revisions = manifest::read_manifest(&self.config.project_folder)
+            .map(|manifest| manifest::git_revisions(&manifest))
+            .unwrap_or_default();
+        self.build_progress.start(command, revisions)
+    }
+
+    fn read_manifest_contents(&self) -> Result<String, rmcp::Error> {
+        std::fs::read_to_string(Path::new(&self.config.project_folder).join("Move.toml")).map_err(
+            |e| rmcp::Error::internal_error(format!("Failed to read Move.toml: {}", e), None),
+        )
+    }
+
+    /// Builds a scratch copy of the package once per `(description, Move.toml contents)`
+    /// variant. The project itself is never modified, so no lock is needed.
+    async fn build_manifest_variants(
+        &self,
+        variants: Vec<(String, String)>,
+        ct: &CancellationToken,
+    ) -> Result<Vec<serde_json::Value>, rmcp::Error> {
+        let folder = std::fs::canonicalize(&self.config.project_folder).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to resolve project folder: {}", e), None)
+        })?;
+        let copy = self.scratch.copy_package(&folder).map_err(|e| {
+            rmcp::Error::internal_error(format!("Failed to copy the package: {}", e), None)
+        })?;
+        let manifest_path = copy.join("Move.toml");
+        let lock_path = copy.join("Move.lock");
+        let original_lock = std::fs::read(&lock_path).ok();
+
+        let results = async {
+            let mut results = vec![];
+            for (description, contents) in variants {
+                // Each variant starts from the project's own lockfile.
+                let reset_lock = match &original_lock {
+                    Some(lock) => std::fs::write(&lock_path, lock),
+                    None if lock_path.exists() => std::fs::remove_file(&lock_path),
+                    None => Ok(()),
+                };
+                reset_lock
+                    .and_then(|_| {
+                        std::fs::write(
+                            &manifest_path,
+                            manifest::with_absolute_local_paths(&contents, &folder),
+                        )
+                    })
+                    .map_err(|e| {
+                        rmcp::Error::internal_error(
+                            format!("Failed to write the package copy: {}", e),
+                            None,
+                        )
+                    })?;
+                let completion = command::run(
+                    tokio::process::Command::new("sui")
+                        .arg("move")
+                        .arg("build")
+                        .current_dir(&copy),
+                    ct,
+                    self.config.command_timeout,
+                )
+                .await
+                .map_err(|e| {
+                    rmcp::Error::internal_error(
+                        format!("Failed to build with {}: {}", description, e),
+                        None,
+                    )
+                })?;
+                let (success, stderr, timed_out) = match completion {
+                    Completion::Finished(output) => (output.status.success(), output.stderr, false),
+                    Completion::TimedOut { stderr, .. } => (false, stderr, true),
+                };
+                let stderr = String::from_utf8_lossy(&stderr);
+                let (warnings, errors) = extract_build_output(&stderr);
+                // Failures outside the compiler, e.g. an edition the CLI does not know or a
+                // revision that cannot be fetched.
+                let error = (!success && errors.is_empty())
+                    .then(|| strip_ansi_escapes::strip_str(stderr.trim()));
+                results.push(serde_json::json!({
+                    "compiles": success,
+                    "buildErrors": errors.into_values().collect::<Vec<_>>(),
+                    "warnings": warnings.into_values().collect::<Vec<_>>(),
+                    "error": error,
+                    "timedOut": timed_out,
+                }));
+            }
+            Ok::<_, rmcp::Error>(results)
+        }
+        .await;
+
+        let _ = std::fs::remove_dir_all(&copy);
+        results
+    }
+
+    /// This session, or a session for `package`, a package directory inside the project
+    /// folder such as one package of a monorepo. The derived session shares the package locks,
+    /// so calls on different packages run concurrently while calls on the same package wait
+    /// for each other, but starts without this session's state such as the validation cache.
+    fn for_package(&self, package: Option<&str>) -> Result<Self, rmcp::Error> {
+        let Some(package) = package else {
+            return Ok(self.clone());
+        };
+        let folder = validate::package_dir(&self.config, package)?;
+        let config = Config {
+            project_folder: folder.to_string_lossy().to_string(),
+            ..(*self.config).clone()
+        };
+        Ok(Self {
+            config: Arc::new(config),
+            status_cache: Default::default(),
+            module_hashes: Default::default(),
+            last_validation: Default::default(),
+            validation_cache: Default::default(),
+            build_progress: Default::default(),
+            failed_tests: Default::default(),
+            ..self.clone()
+        })
+    }
+
+    /// Holds the project's package lock, failing if the request is cancelled while waiting.
+    async fn lock_package(&self, ct: &CancellationToken) -> Result<PackageGuard, rmcp::Error> {
+        self.package_locks
+            .lock(&self.config.project_folder, ct)
+            .await
+            .ok_or_else(|| {
+                rmcp::Error::internal_error("Cancelled while waiting for the package lock", None)
+            })
+    }
+
+    /// Sends a read-only JSON-RPC query to the configured `rpc_url`, or to the CLI's active
+    /// environment when none is set.
+    async fn rpc_query(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        ct: &CancellationToken,
+    ) -> anyhow::Result<serde_json::Value> {
+        let url = match &self.config.rpc_url {
+            Some(url) => url.clone(),
+            None => client::active_rpc_url(&self.config.project_folder, ct).await?,
+        };
+        rpc::call(&url, method, params, ct).await
+    }
+
+    async fn sui_version(&self, ct: &CancellationToken) -> Option<String> {
+        self.sui_version
+            .get_or_init(|| sui_version(ct))
+            .await
+            .clone()
+    }
+
+    /// Parses compiler diagnostics with the renderer used by the installed `sui`, detecting
+    /// it from the output when the version is unknown.
+    async fn extract_diagnostics(
+        &self,
+        output: &str,
+        ct: &CancellationToken,
+    ) -> (HashMap<LineNotice, String>, HashMap<LineNotice, String>) {
+        let version = self.sui_version(ct).await;
+        let format = version
+            .as_deref()
+            .and_then(DiagnosticFormat::for_version)
+            .or_else(|| DiagnosticFormat::detect(output));
+        let (warnings, errors) =
+            extract_build_output_as(output, format.unwrap_or(DiagnosticFormat::Unicode));
+        let has_headers = output.contains("error[") || output.contains("warning[");
+        if has_headers && warnings.is_empty() && errors.is_empty() {
+            tracing::warn!(
+                sui_version = version.as_deref().unwrap_or("unknown"),
+                ?format,
+                "unrecognized compiler diagnostic format"
+            );
+        }
+        (warnings, errors)
+    }
+
+    /// Runs the formatter over `patterns` (or the configured directories), returning whether
+    /// it succeeded and a JSON summary. `check` mode formats a scratch copy of the package
+    /// and only reports the changes; `patch` also returns them as a unified diff.
+    async fn format(
+        &self,
+        patterns: &[String],
+        formatter: Option<&str>,
+        check: bool,
+        patch: bool,
+        ct: &CancellationToken,
+    ) -> Result<(bool, serde_json::Value), rmcp::Error> {
+        let _guard = self.lock_package(ct).await?;
+        let formatter = match formatter {
+            Some(name) => self
+                .config
+                .formatters
+                .iter()
+                .find(|(other, _)| other == name)
+                .map(|(_, cmd)| cmd.as_str())
+                .ok_or_else(|| {
+                    let names = self
+                        .config
+                        .formatters
+                        .iter()
+                        .map(|(name, _)| format!("`{}`", name))
+                        .collect::<Vec<_>>();
+                    rmcp::Error::invalid_params(
+                        format!(
+                            "Unknown formatter `{}`; configured formatters: {}",
+                            name,
+                            if names.is_empty() {
+                                "none".to_string()
+                            } else {
+                                names.join(", ")
+                            }
+                        ),
+                        None,
+                    )
+                })?,
+            None => self.config.movefmt_cmd.as_str(),
+        };
+        let program = formatter.split_whitespace().next().unwrap_or_default();
+        if !program_exists(program) {
+            return Err(rmcp::Error::invalid_params(
+                format!("Formatter `{}` was not found", program),
+                None,
+            ));
+        }
+
+        let started = std::time::Instant::now();
+        let before = sources::snapshot(&self.config.project_folder);
+        if check && before.is_err() {
+            return Err(rmcp::Error::internal_error(
+                "Failed to read the sources to compare after a check",
+                None,
+            ));
+        }
+        let before = before.ok();
+        let root = std::fs::canonicalize(&self.config.project_folder).map_err(|e| {
+            rmcp::Error::internal_error(
+                format!("Failed to resolve the project folder: {}", e),
+                None,
+            )
+        })?;
+        // Check mode formats a scratch copy, so the user's files are never rewritten.
+        let copy = if check {
+            let copy = self.scratch.copy_package(&root).map_err(|e| {
+                rmcp::Error::internal_error(
+                    format!("Failed to copy the package to check it: {}", e),
+                    None,
+                )
+            })?;
+            Some(copy)
+        } else {
+            None
+        };
+        let target = copy.clone().unwrap_or_else(|| root.clone());
+
+        let mut failures = vec![];
+        let formatted = async {
+            if !patterns.is_empty() {
+                let files = resolve_patterns(&self.config, patterns)?;
+                if files.is_empty() {
+                    return Ok::<_, rmcp::Error>(None);
+                }
+                let files = files
+                    .iter()
+                    .map(|file| file.strip_prefix(&root).unwrap_or(file).to_path_buf())
+                    .collect::<Vec<_>>();
+                let output = command::output(
+                    build_fmt_command(formatter, &self.config)
+                        .args(files.iter().map(|file| target.join(file))),
+                    ct,
+                )
+                .await
+                .map_err(|e| {
+                    rmcp::Error::internal_error(format!("Failed to run formatter: {}", e), None)
+                })?;
+                if !output.status.success() {
+                    failures.push(String::from_utf8_lossy(&output.stderr).trim().to_string());
+                }
+                let files = files
+                    .iter()
+                    .map(|file| file.to_string_lossy().to_string())
+                    .collect::<Vec<_>>();
+                Ok(Some((files, vec![])))
+            } else {
+                let (dirs, missing) = self.format_dirs();
+                for dir in &dirs {
+                    let output = command::output(
+                        build_fmt_command(formatter, &self.config).arg(target.join(dir)),
+                        ct,
+                    )
+                    .await
+                    .map_err(|e| {
+                        rmcp::Error::internal_error(
+                            format!("Failed to run formatter on `{}`: {}", dir, e),
+                            None,
+                        )
+                    })?;
+                    if !output.status.success() {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        failures.push(format!("{}: {}", dir, stderr.trim()));
+                    }
+                }
+                Ok(Some((dirs, missing)))
+            }
+        }
+        .await;
+
+        // Compare file contents rather than trusting the formatter to report what it rewrote.
+        let after = sources::snapshot(&target.to_string_lossy()).ok();
+        if let Some(copy) = &copy {
+            let _ = std::fs::remove_dir_all(copy);
+        }
+        let changed = before.as_ref().zip(after.as_ref()).map(|(before, after)| {
+            let mut changed = after
+                .iter()
+                .filter(|(path, contents)| before.get(*path) != Some(*contents))
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>();
+            changed.sort();
+            changed
+        });
+
+        let Some((targets, skipped)) = formatted? else {
+            let body = serde_json::json!({
+                "status": "FAILED",
+                "errors": ["No files matched the given patterns"],
+            });
+            return Ok((false, body));
+        };
+
+        let patches = match (patch, &before, &after, &changed) {
+            (true, Some(before), Some(after), Some(changed)) => Some(
+                changed
+                    .iter()
+                    .filter_map(|path| {
+                        let file = path.to_string_lossy();
+                        let (patch, hunks) = diff::file_patch(
+                            &file,
+                            &String::from_utf8_lossy(before.get(path)?),
+                            &String::from_utf8_lossy(after.get(path)?),
+                        )?;
+                        Some((file.to_string(), patch, hunks))
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        };
+
+        let changed = changed.map(|changed| {
+            changed
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        });
+        let mut body = serde_json::json!({
+            "status": if failures.is_empty() { "OK" } else { "FAILED" },
+            "check": check,
+            "formatted": targets,
+            "skippedDirs": skipped,
+            "filesChanged": changed,
+            "errors": failures,
+            "durationMs": started.elapsed().as_millis() as u64,
+        });
+        if let Some(patches) = patches {
+            body["patch"] = patches
+                .iter()
+                .map(|(_, patch, _)| patch.as_str())
+                .collect::<String>()
+                .into();
+            body["patches"] = patches
+                .iter()
+                .map(|(file, _, hunks)| serde_json::json!({ "file": file, "hunks": hunks }))
+                .collect::<Vec<_>>()
+                .into();
+        }
+        Ok((failures.is_empty(), body))
+    }
+
+    /// Builds and tests the project, returning whether validation completed and a JSON
+    /// summary of the diagnostics and test results. The result is kept for `last_diagnostics`.
+    ///
+    /// With `use_cache`, a previous result for the same inputs (`Move.toml`, `Move.lock`, every
+    /// `.move` file, tests included, the sources of local dependencies and the CLI version) and
+    /// options is returned without rebuilding.
+    async fn validate(
+        &self,
+        strict: bool,
+        gas_limit: Option<u64>,
+        layout: DiagnosticsLayout,
+        verbose: bool,
+        use_cache: bool,
+        ct: &CancellationToken,
+    ) -> Result<(bool, serde_json::Value), rmcp::Error> {
+        let guard = self.lock_package(ct).await?;
+        // Hashed before building, so edits made while validating invalidate the result. The
+        // version is looked up again, as the CLI may have been upgraded since the session began.
+        let folder = &self.config.project_folder;
+        let version = sui_version(ct).await;
+        let cache_key = sources::build_hash(
+            folder,
+            &manifest::local_dependencies(folder),
+            version.as_deref(),
+        )
+        .ok()
+        .map(|hash| {
+            format!(
+                "{}:{}:{:?}:{:?}:{}",
+                hash, strict, gas_limit, layout, verbose
+            )
+        });
+        let cached = match &cache_key {
+            Some(key) if use_cache => self
+                .validation_cache
+                .lock()
+                .await
+                .as_ref()
+                .filter(|(cached_key, _)| cached_key == key)
+                .map(|(_, result)| result.clone()),
+            _ => None,
+        };
+        let hit = cached.is_some();
+        let (success, mut body) = match cached {
+            Some((success, mut body)) => {
+                body["changedModules"] = serde_json::json!([]);
+                (success, body)
+            }
+            None => {
+                let (success, body) = self
+                    .run_validation(&guard, strict, gas_limit, layout, verbose, ct)
+                    .await?;
+                if let Some(key) = cache_key.filter(|_| body["timedOut"] != true) {
+                    *self.validation_cache.lock().await = Some((key, (success, body.clone())));
+                }
+                (success, body)
+            }
+        };
+        drop(guard);
+        body["cached"] = serde_json::json!(hit);
+        *self.last_validation.lock().await = Some(LastValidation {
+            finished_at: std::time::SystemTime::now(),
+            source_hash: sources::package_hash(&self.config.project_folder).ok(),
+            success,
+            body: body.clone(),
+        });
+        Ok((success, body))
+    }
+
+    async fn run_validation(
+        &self,
+        guard: &PackageGuard,
+        strict: bool,
+        gas_limit: Option<u64>,
+        layout: DiagnosticsLayout,
+        verbose: bool,
+        ct: &CancellationToken,
+    ) -> Result<(bool, serde_json::Value), rmcp::Error> {
+        let changed_modules = self.changed_modules().await;
+        let BuildOutput {
+            warnings: build_warnings,
+            errors: build_errors,
+            timed_out,
+            hook_failure,
+        } = self.build_locked(guard, true, ct).await?;
+
+        if timed_out {
+            let body = serde_json::json!({
+                "warnings": build_warnings.values().collect::<Vec<_>>(),
+                "buildErrors": build_errors.values().collect::<Vec<_>>(),
+                "testResults": null,
+                "changedModules": changed_modules,
+                "timedOut": true
+            });
+            let body =
+                self.arrange_diagnostics(layout, verbose, body, &build_warnings, &build_errors);
+            return Ok((false, body));
+        }
+
+        if !build_errors.is_empty() {
+            let body = serde_json::json!({
+                "warnings": build_warnings.values().collect::<Vec<_>>(),
+                "buildErrors": build_errors.values().collect::<Vec<_>>(),
+                "testResults": null,
+                "changedModules": changed_modules
+            });
+            let body =
+                self.arrange_diagnostics(layout, verbose, body, &build_warnings, &build_errors);
+            return Ok((true, body));
+        }
+
+        if strict && !build_warnings.is_empty() {
+            let mut body = serde_json::json!({
+                "warnings": build_warnings.values().collect::<Vec<_>>(),
+                "buildErrors": [],
+                "testResults": null,
+                "changedModules": changed_modules
+            });
+            if let Some(failure) = hook_failure {
+                body["postBuildHook"] = failure;
+            }
+            let body =
+                self.arrange_diagnostics(layout, verbose, body, &build_warnings, &HashMap::new());
+            return Ok((false, body));
+        }
+
+        let mut test_args = vec![];
+        if let Some(gas_limit) = gas_limit {
+            test_args.push("--gas-limit".to_string());
+            test_args.push(gas_limit.to_string());
+        }
+        let junit = matches!(layout, DiagnosticsLayout::Junit);
+        if junit {
+            // Per-test timings for the report.
+            test_args.push("--statistics".to_string());
+        }
+        let TestOutput {
+            stdout,
+            stderr,
+            timed_out,
+        } = self.test_locked(guard, &test_args, ct).await?;
+
+        let (excluded_failures, test_failures): (Vec<_>, Vec<_>) =
+            test_output::parse_failures(&stdout)
+                .into_iter()
+                .partition(|failure| self.is_excluded_test(&failure.name));
+        let out_of_gas = test_failures
+            .iter()
+            .filter(|failure| failure.kind == FailureKind::OutOfGas)
+            .map(|failure| failure.name.clone())
+            .collect::<Vec<_>>();
+        // Only trust the exclusions when every failing test was parsed, so a failure the parser
+        // missed cannot turn the run into a pass.
+        let failed_tests = test_output::parse_statuses(&stdout)
+            .iter()
+            .filter(|(_, status)| *status != test_output::TestStatus::Pass)
+            .count();
+        let only_excluded_failed = !excluded_failures.is_empty()
+            && test_failures.is_empty()
+            && excluded_failures.len() == failed_tests;
+        let test_results = if stdout.contains("Test failures") && !only_excluded_failed {
+            let data = parse_test_output(&stdout);
+            Some(format!("FAILED:\n\n{}", data.trim()))
+        } else if stdout.contains("Test result: OK") || only_excluded_failed {
+            Some("PASSED".to_string())
+        } else {
+            None
+        };
+        let excluded_failures = excluded_failures
+            .into_iter()
+            .map(|failure| failure.name)
+            .collect::<Vec<_>>();
+        if test_results.is_some() {
+            self.record_failed_tests(&test_output::parse_statuses(&stdout))
+                .await;
+        }
+        let test_results = match test_results {
+            Some(_) if junit => {
+                let package = manifest::read_manifest(&self.config.project_folder)
+                    .and_then(|manifest| manifest::package_name(&manifest))
+                    .unwrap_or_default();
+                Some(test_output::junit_xml(
+                    &package,
+                    &test_output::parse_statuses(&stdout),
+                    // Excluded failures still failed, so the report includes them.
+                    &test_output::parse_failures(&stdout),
+                    &test_output::parse_statistics(&stdout),
+                ))
+            }
+            test_results => test_results,
+        };
+
+        let (mut test_warnings, test_errors) = self.extract_diagnostics(&stderr, ct).await;
+        test_warnings.extend(build_warnings);
+        let test_build_failed = test_build_failed(&stdout, &test_errors);
+
+        let mut body = serde_json::json!({
+            "warnings": test_warnings.values().collect::<Vec<_>>(),
+            "buildErrors": test_errors.values().collect::<Vec<_>>(),
+            "testResults": test_results,
+            "testFailures": test_failures,
+            "outOfGas": out_of_gas,
+            "excludedFailures": excluded_failures,
+            "testBuildFailed": test_build_failed,
+            "changedModules": changed_modules,
+            "timedOut": timed_out
+        });
+        if let Some(failure) = hook_failure {
+            body["postBuildHook"] = failure;
+        }
+        let body = self.arrange_diagnostics(layout, verbose, body, &test_warnings, &test_errors);
+        Ok((!timed_out, body))
+    }
+
+    /// Replaces the flat `warnings` and `buildErrors` of a result with the same diagnostics in
+    /// the requested layout. `verbose` adds the `details` of each diagnostic.
+    fn arrange_diagnostics(
+        &self,
+        layout: DiagnosticsLayout,
+        verbose: bool,
+        mut body: serde_json::Value,
+        warnings: &HashMap<LineNotice, String>,
+        errors: &HashMap<LineNotice, String>,
+    ) -> serde_json::Value {
+        if verbose {
+            body["details"] = diagnostic_details(warnings, errors);
+        }
+        let (key, diagnostics) = match layout {
+            DiagnosticsLayout::Flat | DiagnosticsLayout::Junit => return body,
+            DiagnosticsLayout::Grouped => ("diagnosticsByFile", group_by_file(warnings, errors)),
+            DiagnosticsLayout::Lsp => (
+                "publishDiagnostics",
+                publish_diagnostics(&self.config.project_folder, warnings, errors),
+            ),
+        };
+        if let Some(fields) = body.as_object_mut() {
+            fields.remove("warnings");
+            fields.remove("buildErrors");
+            fields.insert(key.to_string(), diagnostics);
+        }
+        body
+    }
+
+    /// Lists the modules whose source changed since the previous call, or every module on the
+    /// first call. Returns `None` if the sources could not be read.
+    async fn changed_modules(&self) -> Option<Vec<String>> {
+        let current = sources::module_hashes(&self.config.project_folder).ok()?;
+        let mut previous = self.module_hashes.lock().await;
+        let mut changed = current
+            .iter()
+            .filter(|(module, hash)| {
+                previous
+                    .as_ref()
+                    .is_none_or(|previous| previous.get(*module) != Some(*hash))
+            })
+            .map(|(module, _)| module.clone())
+            .collect::<Vec<_>>();
+        changed.sort();
+        *previous = Some(current);
+        Some(changed)
+    }
+
+    /// Remembers the tests that failed or timed out in a run, for `rerun_failed`.
+    async fn record_failed_tests(&self, statuses: &[(String, test_output::TestStatus)]) {
+        let failed = statuses
+            .iter()
+            .filter(|(_, status)| *status != test_output::TestStatus::Pass)
+            .map(|(name, _)| name.clone())
+            .collect();
+        *self.failed_tests.lock().await = Some(failed);
+    }
+
+    /// Whether a failing test matches one of the configured `validate_exclude` patterns.
+    fn is_excluded_test(&self, name: &str) -> bool {
+        self.config
+            .validate_exclude
+            .iter()
+            .any(|pattern| pattern.matches(name))
+    }
+
+    /// Splits the configured format directories into those present in the project and those
+    /// missing from it.
+    fn format_dirs(&self) -> (Vec<String>, Vec<String>) {
+        let Some(dirs) = &self.config.format_dirs else {
+            return (manifest::layout_dirs(&self.config.project_folder), vec![]);
+        };
+        dirs.iter().cloned().partition(|dir| {
+            std::path::Path::new(&self.config.project_folder)
+                .join(dir)
+                .is_dir()
+        })
+    }
+
+    /// Builds the package under its lock. Callers already holding the lock use
+    /// `build_locked`.
+    async fn build(&self, force: bool, ct: &CancellationToken) -> Result<BuildOutput, rmcp::Error> {
+        let guard = self.lock_package(ct).await?;
+        self.build_locked(&guard, force, ct).await
+    }
+
+    async fn build_locked(
+        &self,
+        _guard: &PackageGuard,
+        force: bool,
+        ct: &CancellationToken,
+    ) -> Result<BuildOutput, rmcp::Error> {
+        let _progress = self.start_progress("build");
+        // A build that fails without compiler diagnostics on a network error was fetching
+        // dependencies, so it is worth retrying.
+        let (completion, attempts) = retry::with_retries(
+            "build",
+            self.config.network_retries,
+            retry::is_transient,
+            ct,
+            || async move {
+                let mut cmd = tokio::process::Command::new("sui");
+                cmd.arg("move")
+                    .arg("build")
+                    .current_dir(&self.config.project_folder);
+                if force {
+                    cmd.arg("--force");
+                }
+                if self.config.json_errors {
+                    cmd.arg("--json-errors");
+                }
+                let completion =
+                    command::run_observed(&mut cmd, ct, self.config.command_timeout, &|line| {
+                        self.build_progress.observe(line)
+                    })
+                    .await?;
+                if let Completion::Finished(output) = &completion {
+                    let stderr =
+                        strip_ansi_escapes::strip_str(String::from_utf8_lossy(&output.stderr));
+                    if !output.status.success()
+                        && !stderr.contains("error[")
+                        && retry::is_transient(&stderr)
+                    {
+                        anyhow::bail!("{}", stderr.trim());
+                    }
+                }
+                Ok(completion)
+            },
+        )
+        .await;
+        let completion = completion.map_err(|e| {
+            rmcp::Error::internal_error(
+                format!(
+                    "Failed to build project after {} attempt(s): {:#}",
+                    attempts, e
+                ),
+                None,
+            )
+        })?;
+
+        let (success, stdout, stderr, timed_out) = match completion {
+            Completion::Finished(output) => {
+                (output.status.success(), output.stdout, output.stderr, false)
+            }
+            Completion::TimedOut { stdout, stderr } => (false, stdout, stderr, true),
+        };
+        let hook_failure = if success {
+            self.run_post_build_hook(ct).await
+        } else {
+            None
+        };
+        let stdout = String::from_utf8_lossy(&stdout);
+        let stderr = String::from_utf8_lossy(&stderr);
+        let text = self.extract_diagnostics(&stderr, ct).await;
+        let json_output = if self.config.json_errors {
+            extract_json_diagnostics(&stdout, &text)
+                .or_else(|| extract_json_diagnostics(&stderr, &text))
+        } else {
+            None
+        };
+        let (warnings, errors) = json_output.unwrap_or(text);
+        Ok(BuildOutput {
+            warnings,
+            errors,
+            timed_out,
+            hook_failure,
+        })
+    }
+
+    /// Runs the configured post-build hook in the project folder, returning its output if it
+    /// fails.
+    async fn run_post_build_hook(&self, ct: &CancellationToken) -> Option<serde_json::Value> {
+        let hook = self.config.post_build_hook.as_ref()?;
+        let command = std::iter::once(hook.program.to_string_lossy().to_string())
+            .chain(hook.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let completion = command::run(
+            tokio::process::Command::new(&hook.program)
+                .args(&hook.args)
+                .current_dir(&self.config.project_folder),
+            ct,
+            self.config.command_timeout,
+        )
+        .await;
+        let failure = match completion {
+            Ok(Completion::Finished(output)) if output.status.success() => return None,
+            Ok(Completion::Finished(output)) => serde_json::json!({
+                "command": command,
+                "exitCode": output.status.code(),
+                "stdout": String::from_utf8_lossy(&output.stdout),
+                "stderr": String::from_utf8_lossy(&output.stderr),
+                "timedOut": false,
+            }),
+            Ok(Completion::TimedOut { stdout, stderr }) => serde_json::json!({
+                "command": command,
+                "exitCode": null,
+                "stdout": String::from_utf8_lossy(&stdout),
+                "stderr": String::from_utf8_lossy(&stderr),
+                "timedOut": true,
+            }),
+            Err(e) => serde_json::json!({
+                "command": command,
+                "error": format!("Failed to run post-build hook: {}", e),
+            }),
+        };
+        tracing::warn!(%command, "post-build hook failed");
+        Some(failure)
+    }
 
-        let output = std::process::Command::new("sui")
-            .arg("move")
-            .arg("test")
-            // JSON output provides insufficient information
-            // https://github.com/MystenLabs/sui/blob/5f28d37e21e4064a99bb2fff08210c8a62fbbb94/external-crates/move/crates/move-compiler/src/diagnostics/mod.rs#L86
-            //.arg("--json-errors")
-            .current_dir(&self.project_folder)
-            .output()
-            .map_err(|e| {
-                rmcp::Error::internal_error(format!("Failed to run tests: {}", e), None)
-            })?;
+    /// Runs `sui move test` under the package's lock. Callers already holding the lock use
+    /// `test_locked`.
+    async fn test(
+        &self,
+        args: &[String],
+        ct: &CancellationToken,
+    ) -> Result<TestOutput, rmcp::Error> {
+        let guard = self.lock_package(ct).await?;
+        self.test_locked(&guard, args, ct).await
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    async fn test_locked(
+        &self,
+        _guard: &PackageGuard,
+        args: &[String],
+        ct: &CancellationToken,
+    ) -> Result<TestOutput, rmcp::Error> {
+        let _progress = self.start_progress("test");
+        let completion = command::run_observed(
+            tokio::process::Command::new("sui")
+                .arg("move")
+                .arg("test")
+                .args(args)
+                // JSON output provides insufficient information
+                // https://github.com/MystenLabs/sui/blob/5f28d37e21e4064a99bb2fff08210c8a62fbbb94/external-crates/move/crates/move-compiler/src/diagnostics/mod.rs#L86
+                //.arg("--json-errors")
+                .current_dir(&self.config.project_folder),
+            ct,
+            self.config.command_timeout,
+            &|line| self.build_progress.observe(line),
+        )
+        .await
+        .map_err(|e| rmcp::Error::internal_error(format!("Failed to run tests: {}", e), None))?;
 
-        let test_results = if stdout.contains("Test failures") {
-            let data = parse_test_output(&stdout);
-            Some(format!("FAILED:\n\n{}", data.trim()))
-        } else if stdout.contains("Test result: OK") {
-            Some("PASSED".to_string())
-        } else {
-            None
+        let (stdout, stderr, timed_out) = match completion {
+            Completion::Finished(output) => (output.stdout, output.stderr, false),
+            Completion::TimedOut { stdout, stderr } => (stdout, stderr, true),
         };
+        Ok(TestOutput {
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+            timed_out,
+        })
+    }
 
-        let (mut test_warnings, test_errors) = extract_build_output(&stderr);
-        test_warnings.extend(build_warnings);
+    /// Publishes the package, retrying transient network failures. Returns the response and
+    /// the number of attempts made.
+    async fn publish(
+        &self,
+        gas_budget: Option<u64>,
+        ct: &CancellationToken,
+    ) -> (anyhow::Result<serde_json::Value>, u32) {
+        // Publishing builds the package.
+        let Some(_guard) = self
+            .package_locks
+            .lock(&self.config.project_folder, ct)
+            .await
+        else {
+            return (
+                Err(anyhow::anyhow!(
+                    "Cancelled while waiting for the package lock"
+                )),
+                0,
+            );
+        };
+        let mut args = vec!["publish".to_string()];
+        if let Some(gas_budget) = gas_budget {
+            args.push("--gas-budget".to_string());
+            args.push(gas_budget.to_string());
+        }
+        let args = &args;
+        // A publish that timed out may still have been executed, so only failures from before
+        // the transaction was sent are retried.
+        retry::with_retries(
+            "publish",
+            self.config.network_retries,
+            retry::is_unsent,
+            ct,
+            || client::json(&self.config.project_folder, args, ct),
+        )
+        .await
+    }
 
-        let body = serde_json::json!({
-            "warnings": test_warnings.values().collect::<Vec<_>>(),
-            "buildErrors": test_errors.values().collect::<Vec<_>>(),
-            "testResults": test_results
-        });
-        let out = Content::json(body)?;
-        Ok(CallToolResult::success(vec![out]))
+    /// Lists the `.move` files that differ from `HEAD` (including untracked files), relative
+    /// to the project folder. Returns `None` when the project is not inside a git repository.
+    async fn changed_move_files(&self, ct: &CancellationToken) -> Option<Vec<String>> {
+        let mut files = vec![];
+        for args in [
+            &[
+                "diff",
+                "--name-only",
+                "--relative",
+                "--diff-filter=d",
+                "HEAD",
+            ][..],
+            &["ls-files", "--others", "--exclude-standard"][..],
+        ] {
+            let output = command::output(
+                tokio::process::Command::new("git")
+                    .args(args)
+                    .current_dir(&self.config.project_folder),
+                ct,
+            )
+            .await
+            .ok()?;
+            if !output.status.success() {
+                tracing::debug!(
+                    stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+                    "git unavailable, using full project"
+                );
+                return None;
+            }
+            files.extend(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|line| line.ends_with(".move"))
+                    .map(|line| line.to_string()),
+            );
+        }
+        files.sort();
+        files.dedup();
+        Some(files)
+    }
+
+    /// Lists the staged `.move` files, relative to the project folder. Returns `None` when the
+    /// project is not inside a git repository.
+    async fn staged_move_files(&self, ct: &CancellationToken) -> Option<Vec<String>> {
+        let output = command::output(
+            tokio::process::Command::new("git")
+                .args([
+                    "diff",
+                    "--cached",
+                    "--name-only",
+                    "--relative",
+                    "--diff-filter=d",
+                ])
+                .current_dir(&self.config.project_folder),
+            ct,
+        )
+        .await
+        .ok()?;
+        if !output.status.success() {
+            tracing::debug!(
+                stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+                "git unavailable"
+            );
+            return None;
+        }
+        let mut files = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.ends_with(".move"))
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        Some(files)
     }
 }
 
-#[tool(tool_box)]
 impl rmcp::ServerHandler for SuiService {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
@@ -133,21 +4718,499 @@ impl rmcp::ServerHandler for SuiService {
         }
         Ok(self.get_info())
     }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<ListToolsResult, rmcp::Error> {
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools: self.available_tools(),
+        })
+    }
+
+    /// Dispatches to the tool box, tagging the call's log span and its result (or error data)
+    /// with a generated `requestId`.
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParam,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        self.connection.record_request();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let span = tracing::info_span!("tool_call", tool = %request.name, %request_id);
+        // Clients can pass their own timeout as a reserved argument, so the server gives up
+        // when they do rather than finishing work nobody is waiting for.
+        let deadline = request
+            .arguments
+            .as_mut()
+            .and_then(|arguments| arguments.remove(DEADLINE_ARGUMENT))
+            .map(|timeout| {
+                timeout
+                    .as_u64()
+                    .map(std::time::Duration::from_millis)
+                    .ok_or_else(|| {
+                        rmcp::Error::invalid_params(
+                            format!("`{}` must be a number of milliseconds", DEADLINE_ARGUMENT),
+                            None,
+                        )
+                    })
+            })
+            .transpose()?;
+
+        let result = if self.is_disabled(&request.name) {
+            Err(rmcp::Error::invalid_request(
+                format!("`{}` is disabled on this server", request.name),
+                None,
+            ))
+        } else if self.config.read_only && MUTATING_TOOLS.contains(&request.name.as_ref()) {
+            Err(rmcp::Error::invalid_request(
+                format!(
+                    "`{}` is unavailable because the server is in read-only mode",
+                    request.name
+                ),
+                None,
+            ))
+        } else {
+            let ct = context.ct.clone();
+            let tool_context = ToolCallContext::new(self, request, context);
+            let call = Self::tool_box().call(tool_context).instrument(span);
+            match deadline {
+                None => call.await,
+                Some(deadline) => with_deadline(call, deadline, &ct).await,
+            }
+        };
+
+        match result {
+            Ok(mut result) => {
+                result.content.push(Content::json(
+                    serde_json::json!({ "requestId": request_id }),
+                )?);
+                Ok(result)
+            }
+            Err(mut e) => {
+                let data = e.data.get_or_insert_with(|| serde_json::json!({}));
+                if let Some(data) = data.as_object_mut() {
+                    data.insert("requestId".into(), request_id.into());
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Wraps a JSON body as a successful or failed tool result.
+fn json_result(success: bool, body: serde_json::Value) -> Result<CallToolResult, rmcp::Error> {
+    let out = Content::json(body)?;
+    if success {
+        Ok(CallToolResult::success(vec![out]))
+    } else {
+        Ok(CallToolResult::error(vec![out]))
+    }
 }
 
-fn build_fmt_command(cmd_str: &str) -> std::process::Command {
-    let mut parts = cmd_str.split(' ');
-    let mut cmd = std::process::Command::new(parts.next().unwrap());
+/// Splits a formatter command line on whitespace, resolving the program through `PATH` (and
+/// `PATHEXT` on Windows, so `movefmt` finds `movefmt.exe`). Arguments cannot contain spaces,
+/// so the program must not live under a path with spaces either.
+fn build_fmt_command(cmd_str: &str, config: &Config) -> tokio::process::Command {
+    let mut parts = cmd_str.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let mut cmd = match resolve_program(program) {
+        Some(path) => tokio::process::Command::new(path),
+        None => tokio::process::Command::new(program),
+    };
     for part in parts {
         cmd.arg(part);
     }
+    if let Some(config_path) = &config.movefmt_config {
+        cmd.arg("--config-path").arg(config_path);
+    }
     cmd
 }
 
+/// Output of `sui --version`, or `None` when it cannot be run.
+async fn sui_version(ct: &CancellationToken) -> Option<String> {
+    command::output(tokio::process::Command::new("sui").arg("--version"), ct)
+        .await
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A fresh seed for randomized tests, taken from a v4 UUID's random bits.
+fn random_seed() -> u64 {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    u64::from_le_bytes(bytes[..8].try_into().unwrap_or_default())
+}
+
+/// Splits timestamped `sui move build` output into stages. Everything before the first
+/// `BUILDING` line is dependency resolution, listing the git dependencies updated and
+/// packages included; compilation runs from there until the process exits.
+fn build_stages(
+    lines: &[(std::time::Duration, String)],
+    total: std::time::Duration,
+) -> serde_json::Value {
+    let strip = |line: &str| strip_ansi_escapes::strip_str(line).trim().to_string();
+    let building = lines
+        .iter()
+        .position(|(_, line)| strip(line).starts_with("BUILDING "));
+    let resolution_end = building.map_or(total, |index| lines[index].0);
+    let resolution = &lines[..building.unwrap_or(lines.len())];
+    let listed = |prefix: &str| {
+        resolution
+            .iter()
+            .filter_map(|(_, line)| Some(strip(line).strip_prefix(prefix)?.trim().to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut stages = vec![serde_json::json!({
+        "stage": "resolveDependencies",
+        "startMs": 0,
+        "durationMs": resolution_end.as_millis() as u64,
+        "updatedGitDependencies": listed("UPDATING GIT DEPENDENCY"),
+        "includedDependencies": listed("INCLUDING DEPENDENCY"),
+    })];
+    if let Some(index) = building {
+        let (start, line) = &lines[index];
+        stages.push(serde_json::json!({
+            "stage": "compile",
+            "package": strip(line).trim_start_matches("BUILDING ").trim(),
+            "startMs": start.as_millis() as u64,
+            "durationMs": total.saturating_sub(*start).as_millis() as u64,
+        }));
+    }
+    serde_json::Value::Array(stages)
+}
+
+/// Renders a tool's description and the arguments from its input schema as plain text.
+fn describe_tool(tool: &rmcp::model::Tool) -> String {
+    let mut out = format!("{}\n    {}\n", tool.name, tool.description);
+    let required = tool.input_schema["required"]
+        .as_array()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let properties = tool.input_schema["properties"].as_object();
+    for (name, schema) in properties.into_iter().flatten() {
+        let type_ = match &schema["type"] {
+            serde_json::Value::String(type_) => type_.clone(),
+            serde_json::Value::Array(types) => types
+                .iter()
+                .filter_map(|type_| type_.as_str())
+                .filter(|type_| *type_ != "null")
+                .collect::<Vec<_>>()
+                .join("|"),
+            _ => "object".to_string(),
+        };
+        out.push_str(&format!(
+            "    - {} ({}{}){}\n",
+            name,
+            type_,
+            if required.contains(&name.as_str()) {
+                ", required"
+            } else {
+                ""
+            },
+            schema["description"]
+                .as_str()
+                .map(|description| format!(": {}", description))
+                .unwrap_or_default()
+        ));
+    }
+    out
+}
+
+/// One `doctor` checklist entry, carrying the hint only when the check failed.
+fn check(name: &str, result: Result<String, String>, hint: &str) -> serde_json::Value {
+    match result {
+        Ok(detail) => serde_json::json!({ "name": name, "ok": true, "detail": detail }),
+        Err(detail) => serde_json::json!({
+            "name": name,
+            "ok": false,
+            "detail": detail,
+            "hint": hint,
+        }),
+    }
+}
+
+/// Whether `program` is an existing path or can be found on `PATH`.
+fn program_exists(program: &str) -> bool {
+    resolve_program(program).is_some()
+}
+
+/// Finds the file `program` refers to: the path itself when it contains a separator,
+/// otherwise the first match in a `PATH` directory. On Windows each `PATHEXT` extension is
+/// also tried when `program` has no extension of its own.
+fn resolve_program(program: &str) -> Option<std::path::PathBuf> {
+    resolve_program_in(
+        program,
+        std::env::var_os("PATH"),
+        cfg!(windows)
+            .then(|| std::env::var("PATHEXT").ok())
+            .flatten(),
+    )
+}
+
+/// [`resolve_program`] against the given `PATH` and `PATHEXT` values.
+fn resolve_program_in(
+    program: &str,
+    paths: Option<std::ffi::OsString>,
+    pathext: Option<String>,
+) -> Option<std::path::PathBuf> {
+    if program.is_empty() {
+        return None;
+    }
+    let candidates = program_candidates(program, pathext);
+    if program.contains('/') || program.contains(std::path::MAIN_SEPARATOR) {
+        return candidates
+            .into_iter()
+            .map(std::path::PathBuf::from)
+            .find(|path| path.is_file());
+    }
+    std::env::split_paths(&paths?).find_map(|dir| {
+        candidates
+            .iter()
+            .map(|candidate| dir.join(candidate))
+            .find(|path| path.is_file())
+    })
+}
+
+/// File names to look for: `program`, then `program` with each extension in `pathext`
+/// (`;`-separated, as in Windows' `PATHEXT`) if it has no extension already.
+fn program_candidates(program: &str, pathext: Option<String>) -> Vec<String> {
+    let mut candidates = vec![program.to_string()];
+    let Some(pathext) = pathext else {
+        return candidates;
+    };
+    if std::path::Path::new(program).extension().is_some() {
+        return candidates;
+    }
+    let pathext = if pathext.trim().is_empty() {
+        ".COM;.EXE;.BAT;.CMD".to_string()
+    } else {
+        pathext
+    };
+    candidates.extend(
+        pathext
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| format!("{}{}", program, ext.to_lowercase())),
+    );
+    candidates
+}
+
+/// Presents an event with its decoded fields, keeping the raw encoding when the RPC could not
+/// resolve the event type's layout.
+fn decode_event(event: &serde_json::Value) -> serde_json::Value {
+    let decoded = event
+        .get("parsedJson")
+        .filter(|fields| !fields.is_null())
+        .cloned();
+    let raw = match decoded {
+        Some(_) => serde_json::Value::Null,
+        None => event.get("bcs").cloned().unwrap_or_else(|| event.clone()),
+    };
+    serde_json::json!({
+        "type": event["type"],
+        "sender": event["sender"],
+        "packageId": event["packageId"],
+        "module": event["transactionModule"],
+        "fields": decoded,
+        "raw": raw,
+    })
+}
+
+/// Quotes an argument for a POSIX shell, leaving plain words as they are.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Builds the arguments for `sui client call`.
+fn call_args(
+    package_id: &str,
+    module: &str,
+    function: &str,
+    args: &[String],
+    type_args: &[String],
+    gas_budget: Option<u64>,
+) -> Vec<String> {
+    let mut call = vec![
+        "call".to_string(),
+        "--package".to_string(),
+        package_id.to_string(),
+        "--module".to_string(),
+        module.to_string(),
+        "--function".to_string(),
+        function.to_string(),
+    ];
+    if !args.is_empty() {
+        call.push("--args".to_string());
+        call.extend(args.iter().cloned());
+    }
+    if !type_args.is_empty() {
+        call.push("--type-args".to_string());
+        call.extend(type_args.iter().cloned());
+    }
+    if let Some(gas_budget) = gas_budget {
+        call.push("--gas-budget".to_string());
+        call.push(gas_budget.to_string());
+    }
+    call
+}
+
+/// Reads the MIST amounts in a transaction's `gasUsed`, which the CLI prints as strings, and
+/// adds the net cost actually charged.
+fn gas_report(gas_used: &serde_json::Value) -> serde_json::Value {
+    let amount = |key: &str| -> u64 {
+        gas_used[key]
+            .as_str()
+            .and_then(|amount| amount.parse().ok())
+            .or_else(|| gas_used[key].as_u64())
+            .unwrap_or(0)
+    };
+    let computation = amount("computationCost");
+    let storage = amount("storageCost");
+    let rebate = amount("storageRebate");
+    serde_json::json!({
+        "computationCost": computation,
+        "storageCost": storage,
+        "storageRebate": rebate,
+        "nonRefundableStorageFee": amount("nonRefundableStorageFee"),
+        "netCost": (computation + storage) as i64 - rebate as i64,
+        // The budget is checked against the gross cost, before the rebate.
+        "requiredGasBudget": computation + storage,
+    })
+}
+
+fn step_failure(
+    step: &str,
+    error: String,
+    completed_steps: serde_json::Map<String, serde_json::Value>,
+) -> Result<CallToolResult, rmcp::Error> {
+    let body = serde_json::json!({
+        "failedStep": step,
+        "error": error,
+        "completedSteps": completed_steps,
+    });
+    Ok(CallToolResult::error(vec![Content::json(body)?]))
+}
+
+/// Condenses a transaction block response into its digest, outcome and the commands it ran.
+fn summarize_transaction(tx: &serde_json::Value) -> serde_json::Value {
+    let commands = tx["transaction"]["data"]["transaction"]["transactions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|command| command.as_object()?.iter().next())
+        .map(|(kind, command)| match kind.as_str() {
+            "MoveCall" => format!(
+                "{}::{}::{}",
+                command["package"].as_str().unwrap_or_default(),
+                command["module"].as_str().unwrap_or_default(),
+                command["function"].as_str().unwrap_or_default()
+            ),
+            _ => kind.clone(),
+        })
+        .collect::<Vec<_>>();
+    let status = &tx["effects"]["status"];
+    serde_json::json!({
+        "digest": tx["digest"],
+        "timestampMs": tx["timestampMs"],
+        "status": status["status"],
+        "error": status["error"],
+        "gasUsed": tx["effects"]["gasUsed"],
+        "commands": commands,
+    })
+}
+
+/// Pads an address to its full 32-byte hex form so differently abbreviated IDs compare equal.
+fn normalize_address(address: &str) -> String {
+    let hex = address.trim().trim_start_matches("0x").to_lowercase();
+    format!("0x{:0>64}", hex)
+}
+
+fn is_upgrade_cap_type(type_: &str) -> bool {
+    type_
+        .strip_suffix("::package::UpgradeCap")
+        .is_some_and(|address| normalize_address(address) == normalize_address("0x2"))
+}
+
+fn upgrade_policy_name(policy: u64) -> &'static str {
+    match policy {
+        0 => "compatible",
+        128 => "additive",
+        192 => "dep_only",
+        _ => "unknown",
+    }
+}
+
+/// Expands glob patterns relative to the project folder, rejecting any that could match files
+/// outside of it. Matches that resolve outside the allowed directories are skipped.
+fn resolve_patterns(
+    config: &Config,
+    patterns: &[String],
+) -> Result<Vec<std::path::PathBuf>, rmcp::Error> {
+    let project_folder = &config.project_folder;
+    let root = std::fs::canonicalize(project_folder).map_err(|e| {
+        rmcp::Error::internal_error(
+            format!(
+                "Failed to resolve project folder `{}`: {}",
+                project_folder, e
+            ),
+            None,
+        )
+    })?;
+
+    let mut files = vec![];
+    for pattern in patterns {
+        if !validate::is_relative_within(std::path::Path::new(pattern)) {
+            return Err(rmcp::Error::invalid_params(
+                format!("Pattern `{}` must stay within the project folder", pattern),
+                None,
+            ));
+        }
+        let matches = glob::glob(&format!("{}/{}", root.display(), pattern)).map_err(|e| {
+            rmcp::Error::invalid_params(format!("Invalid pattern `{}`: {}", pattern, e), None)
+        })?;
+        for entry in matches.filter_map(Result::ok) {
+            let Ok(file) = std::fs::canonicalize(&entry) else {
+                continue;
+            };
+            if file.starts_with(&root)
+                && validate::allowed_path(config, &file).is_ok()
+                && file.is_file()
+                && !files.contains(&file)
+            {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
 fn parse_test_output(s: &str) -> String {
     remove_before(s, "Test failures")
 }
 
+/// Whether `sui move test` failed to compile the package. It is only run after a successful
+/// build, so errors without a test result come from `#[test_only]` code and no tests ran.
+fn test_build_failed(stdout: &str, test_errors: &HashMap<LineNotice, String>) -> bool {
+    !test_errors.is_empty() && !stdout.contains("Test result:")
+}
+
 fn remove_before(s: &str, pattern: &str) -> String {
     s.find(pattern)
         .map(|idx| &s[idx..])
@@ -155,7 +5218,7 @@ fn remove_before(s: &str, pattern: &str) -> String {
         .to_string()
 }
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub struct LineNotice {
     file: String,
     line_number: u32,
@@ -163,8 +5226,63 @@ pub struct LineNotice {
     code: String,
 }
 
+/// How the compiler renders source snippets in diagnostics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// codespan's box-drawing characters: `┌─ file:line:col` and a `│` gutter.
+    Unicode,
+    /// codespan's ASCII fallback: `--> file:line:col` and a `|` gutter.
+    Ascii,
+}
+
+impl DiagnosticFormat {
+    /// Every 1.x `sui` release renders Unicode snippets. Other versions are left to
+    /// [`DiagnosticFormat::detect`].
+    pub fn for_version(version: &str) -> Option<Self> {
+        let number = version.split_whitespace().nth(1)?;
+        let major = number.split('.').next()?.parse::<u32>().ok()?;
+        (major == 1).then_some(Self::Unicode)
+    }
+
+    /// Detects the format from the first snippet location in `output`.
+    pub fn detect(output: &str) -> Option<Self> {
+        output.lines().find_map(|line| {
+            let line = line.trim_start();
+            if line.starts_with(Self::Unicode.snippet_start()) {
+                Some(Self::Unicode)
+            } else if line.starts_with(Self::Ascii.snippet_start()) {
+                Some(Self::Ascii)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn snippet_start(self) -> &'static str {
+        match self {
+            Self::Unicode => "┌─",
+            Self::Ascii => "-->",
+        }
+    }
+
+    fn gutter(self) -> char {
+        match self {
+            Self::Unicode => '│',
+            Self::Ascii => '|',
+        }
+    }
+}
+
 pub fn extract_build_output(
     input: &str,
+) -> (HashMap<LineNotice, String>, HashMap<LineNotice, String>) {
+    let format = DiagnosticFormat::detect(input).unwrap_or(DiagnosticFormat::Unicode);
+    extract_build_output_as(input, format)
+}
+
+pub fn extract_build_output_as(
+    input: &str,
+    format: DiagnosticFormat,
 ) -> (HashMap<LineNotice, String>, HashMap<LineNotice, String>) {
     let mut warnings = HashMap::new();
     let mut errors = HashMap::new();
@@ -188,13 +5306,18 @@ pub fn extract_build_output(
                     break;
                 }
                 if location.is_none() {
-                    location = parse_location(next_line);
+                    location = parse_location(next_line, format);
                 }
                 warning_block.push_str(lines.next().unwrap());
                 warning_block.push('\n');
             }
 
-            let (file, line_number, column_number) = location.expect("warning block fail");
+            // Output cut short (e.g. by a timeout) can end before the location line.
+            let Some((file, line_number, column_number)) = location else {
+                continue;
+            };
+            let column_number = caret_span(&warning_block, line_number, format)
+                .map_or(column_number, |(start, _)| start);
 
             let notice = LineNotice {
                 file,
@@ -217,12 +5340,16 @@ pub fn extract_build_output(
                     break;
                 }
                 if location.is_none() {
-                    location = parse_location(next_line);
+                    location = parse_location(next_line, format);
                 }
                 error_block.push_str(lines.next().unwrap());
                 error_block.push('\n');
             }
-            let (file, line_number, column_number) = location.expect("error block fail");
+            let Some((file, line_number, column_number)) = location else {
+                continue;
+            };
+            let column_number = caret_span(&error_block, line_number, format)
+                .map_or(column_number, |(start, _)| start);
             let notice = LineNotice {
                 file,
                 line_number,
@@ -236,19 +5363,695 @@ pub fn extract_build_output(
     (warnings, errors)
 }
 
-fn parse_location(val: &str) -> Option<(String, u32, u32)> {
-    if val.trim().starts_with("┌─") {
-        let parts: Vec<&str> = val.split(':').collect();
-        if parts.len() >= 3 {
-            Some((
-                parts.get(0)?.replace("┌─", "").trim().to_string(),
-                parts.get(1)?.parse().ok()?,
-                parts.get(2)?.parse().ok()?,
-            ))
+#[derive(serde::Deserialize)]
+struct JsonDiagnostic {
+    file: String,
+    line: u32,
+    column: u32,
+    level: String,
+    category: u8,
+    code: u8,
+    #[serde(default)]
+    msg: Option<String>,
+}
+
+type Diagnostics = (HashMap<LineNotice, String>, HashMap<LineNotice, String>);
+
+/// Parses the diagnostics array printed by `--json-errors`, which carries exact codes and
+/// locations. Returns `None` when no such array is present, so callers can fall back to the
+/// text diagnostics.
+///
+/// Each message is rendered as the header and location lines of the text format, so it reads
+/// the same as a text-parsed diagnostic. `text` holds the same build's text-parsed
+/// diagnostics: an entry without a message takes the matching text block instead, and when
+/// an entry does not parse at all, text diagnostics missing from the JSON are kept.
+fn extract_json_diagnostics(input: &str, text: &Diagnostics) -> Option<Diagnostics> {
+    let entries = std::iter::once(input.trim())
+        .chain(
+            input
+                .lines()
+                .filter(|line| line.trim_start().starts_with('[')),
+        )
+        .filter_map(|json| serde_json::from_str::<Vec<serde_json::Value>>(json).ok())
+        .find(|entries| entries.iter().all(serde_json::Value::is_object))?;
+
+    // Text and JSON columns may count differently, so they are not compared.
+    let same = |a: &LineNotice, b: &LineNotice| {
+        a.file.trim_start_matches("./") == b.file.trim_start_matches("./")
+            && a.line_number == b.line_number
+            && a.code == b.code
+    };
+    let (text_warnings, text_errors) = text;
+    let mut warnings = HashMap::new();
+    let mut errors = HashMap::new();
+    let mut unparsed = 0;
+    for entry in entries {
+        let Ok(diagnostic) = serde_json::from_value::<JsonDiagnostic>(entry) else {
+            unparsed += 1;
+            continue;
+        };
+        let is_warning = diagnostic.level == "Warning";
+        let code = format!(
+            "{}{:02}{:03}",
+            if is_warning { 'W' } else { 'E' },
+            diagnostic.category,
+            diagnostic.code
+        );
+        let notice = LineNotice {
+            file: diagnostic.file,
+            line_number: diagnostic.line,
+            column_number: diagnostic.column,
+            code,
+        };
+        let candidates = if is_warning {
+            text_warnings
         } else {
-            None
+            text_errors
+        };
+        let block = match diagnostic.msg.filter(|msg| !msg.trim().is_empty()) {
+            Some(msg) => render_json_diagnostic(&notice, is_warning, &msg),
+            None => candidates
+                .iter()
+                .find(|(other, _)| same(&notice, other))
+                .map(|(_, block)| block.clone())
+                .unwrap_or_else(|| render_json_diagnostic(&notice, is_warning, "")),
+        };
+        if is_warning {
+            warnings.insert(notice, block);
+        } else {
+            errors.insert(notice, block);
+        }
+    }
+
+    if unparsed > 0 {
+        tracing::warn!(
+            unparsed,
+            "unparsed --json-errors entries, keeping text diagnostics"
+        );
+        for (parsed, text) in [(&mut warnings, text_warnings), (&mut errors, text_errors)] {
+            for (notice, block) in text {
+                if !parsed.keys().any(|other| same(notice, other)) {
+                    parsed.insert(notice.clone(), block.clone());
+                }
+            }
+        }
+    }
+    Some((warnings, errors))
+}
+
+/// Renders a JSON diagnostic as the header and location lines of the text format.
+fn render_json_diagnostic(notice: &LineNotice, is_warning: bool, message: &str) -> String {
+    format!(
+        "{}[{}]: {}\n  ┌─ {}:{}:{}",
+        if is_warning { "warning" } else { "error" },
+        notice.code,
+        message,
+        notice.file,
+        notice.line_number,
+        notice.column_number
+    )
+}
+
+/// Groups diagnostics by file, each file's warnings and errors sorted by position.
+fn group_by_file(
+    warnings: &HashMap<LineNotice, String>,
+    errors: &HashMap<LineNotice, String>,
+) -> serde_json::Value {
+    let mut files = std::collections::BTreeMap::<&str, [Vec<(&LineNotice, &String)>; 2]>::new();
+    for (index, notices) in [warnings, errors].into_iter().enumerate() {
+        for notice in notices {
+            files.entry(notice.0.file.as_str()).or_default()[index].push(notice);
+        }
+    }
+    files
+        .into_iter()
+        .map(|(file, [mut warnings, mut errors])| {
+            for notices in [&mut warnings, &mut errors] {
+                notices.sort_by_key(|(notice, _)| {
+                    (
+                        notice.line_number,
+                        notice.column_number,
+                        notice.code.clone(),
+                    )
+                });
+            }
+            let group = serde_json::json!({
+                "warnings": warnings.iter().map(|(_, block)| block).collect::<Vec<_>>(),
+                "errors": errors.iter().map(|(_, block)| block).collect::<Vec<_>>(),
+            });
+            (file.to_string(), group)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Groups diagnostics by file in the shape of LSP `textDocument/publishDiagnostics` params,
+/// with 0-based positions and each range ending after the primary caret markers.
+fn publish_diagnostics(
+    project_folder: &str,
+    warnings: &HashMap<LineNotice, String>,
+    errors: &HashMap<LineNotice, String>,
+) -> serde_json::Value {
+    let mut files = std::collections::BTreeMap::<String, Vec<serde_json::Value>>::new();
+    let notices = errors
+        .iter()
+        .map(|notice| (1, notice))
+        .chain(warnings.iter().map(|notice| (2, notice)));
+    for (severity, (notice, block)) in notices {
+        let format = DiagnosticFormat::detect(block).unwrap_or(DiagnosticFormat::Unicode);
+        let end_column = caret_span(block, notice.line_number, format)
+            .map_or(notice.column_number + 1, |(_, end)| end);
+        let message = block
+            .lines()
+            .next()
+            .and_then(|header| header.split_once("]: "))
+            .map_or(block.as_str(), |(_, message)| message);
+        let path = Path::new(&notice.file);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            Path::new(project_folder).join(path)
+        };
+        let line = notice.line_number.saturating_sub(1);
+        files
+            .entry(analyzer::file_uri(&path))
+            .or_default()
+            .push(serde_json::json!({
+                "range": {
+                    "start": { "line": line, "character": notice.column_number.saturating_sub(1) },
+                    "end": { "line": line, "character": end_column.saturating_sub(1) },
+                },
+                "severity": severity,
+                "code": notice.code,
+                "source": "sui",
+                "message": message,
+            }));
+    }
+    files
+        .into_iter()
+        .map(|(uri, diagnostics)| serde_json::json!({ "uri": uri, "diagnostics": diagnostics }))
+        .collect()
+}
+
+/// Breaks each diagnostic down into its message, labels and notes, in file and line order.
+fn diagnostic_details(
+    warnings: &HashMap<LineNotice, String>,
+    errors: &HashMap<LineNotice, String>,
+) -> serde_json::Value {
+    let mut details = warnings
+        .iter()
+        .map(|(notice, block)| ("warning", notice, block))
+        .chain(
+            errors
+                .iter()
+                .map(|(notice, block)| ("error", notice, block)),
+        )
+        .collect::<Vec<_>>();
+    details.sort_by_key(|(_, notice, _)| (&notice.file, notice.line_number, notice.column_number));
+    details
+        .into_iter()
+        .map(|(severity, notice, block)| {
+            let format = DiagnosticFormat::detect(block).unwrap_or(DiagnosticFormat::Unicode);
+            let (labels, notes) = diagnostic_labels(block, format);
+            serde_json::json!({
+                "severity": severity,
+                "code": notice.code,
+                "file": notice.file,
+                "line": notice.line_number,
+                "column": notice.column_number,
+                "message": block
+                    .lines()
+                    .next()
+                    .and_then(|header| header.split_once("]: "))
+                    .map(|(_, message)| message.trim()),
+                "labels": labels,
+                "notes": notes,
+            })
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Reads the labels under the source lines of a rendered diagnostic, e.g. `^^^ Invalid call`
+/// (primary) or `--- Expected: 'u64'` (secondary), including label text stacked below its
+/// marker, and the `= ...` notes after the snippet.
+fn diagnostic_labels(
+    block: &str,
+    format: DiagnosticFormat,
+) -> (Vec<serde_json::Value>, Vec<String>) {
+    let gutter_char = format.gutter();
+    let mut labels = vec![];
+    let mut notes = vec![];
+    let mut source_line = None;
+    for line in block.lines().skip(1) {
+        if let Some(note) = line.trim().strip_prefix("= ") {
+            notes.push(note.trim().to_string());
+            continue;
+        }
+        let Some((gutter, content)) = line.split_once(gutter_char) else {
+            continue;
+        };
+        if let Ok(number) = gutter.trim().parse::<u32>() {
+            source_line = Some(number);
+            continue;
+        }
+        if !gutter.trim().is_empty() {
+            continue;
+        }
+        let content = content.strip_prefix(' ').unwrap_or(content);
+        // Markers come first; the message belongs to the rightmost one. Lines without a
+        // marker carry the message of a label marked on an earlier line.
+        let mut marker = None;
+        let mut message = None;
+        for (start, token) in words_with_offsets(content) {
+            if token.chars().all(|c| c == '^') || token.chars().all(|c| c == '-') {
+                marker = Some((start, token.starts_with('^')));
+            } else if !token.chars().all(|c| c == gutter_char) {
+                message = Some((start, content[start..].trim()));
+                break;
+            }
+        }
+        let Some((message_start, text)) = message else {
+            continue;
+        };
+        let (column, primary) = match marker {
+            Some((start, primary)) => (start, Some(primary)),
+            None => (message_start, None),
+        };
+        let column = content[..column].chars().count() + 1;
+        labels.push(serde_json::json!({
+            "line": source_line,
+            "column": column,
+            "primary": primary,
+            "message": text,
+        }));
+    }
+    (labels, notes)
+}
+
+/// Splits on whitespace, keeping the byte offset of each word.
+fn words_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                words.push((word_start, &text[word_start..index]));
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Finds the columns spanned by the primary `^` markers under the snippet line for
+/// `line_number`, as a start column and an exclusive end column.
+///
+/// Columns are counted in characters from the snippet itself, which keeps them accurate for
+/// lines containing multibyte identifiers.
+fn caret_span(block: &str, line_number: u32, format: DiagnosticFormat) -> Option<(u32, u32)> {
+    let gutter_char = format.gutter();
+    let mut lines = block.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((gutter, _)) = line.split_once(gutter_char) else {
+            continue;
+        };
+        if gutter.trim().parse::<u32>().ok() != Some(line_number) {
+            continue;
+        }
+        while let Some(marker_line) = lines.next_if(|next| {
+            next.split_once(gutter_char)
+                .is_some_and(|(gutter, _)| gutter.trim().is_empty())
+        }) {
+            let (_, markers) = marker_line.split_once(gutter_char)?;
+            let markers = markers.strip_prefix(' ').unwrap_or(markers);
+            if let Some(offset) = markers.chars().position(|c| c == '^') {
+                let width = markers
+                    .chars()
+                    .skip(offset)
+                    .take_while(|c| *c == '^')
+                    .count();
+                let start = u32::try_from(offset + 1).ok()?;
+                return Some((start, start + u32::try_from(width).ok()?));
+            }
         }
+    }
+    None
+}
+
+/// Awaits a tool call, cancelling `ct` once `deadline` elapses. The call is still awaited
+/// after the cancellation, so it kills its commands and restores any files it changed before
+/// the deadline error is returned.
+async fn with_deadline<T>(
+    call: impl std::future::Future<Output = Result<T, rmcp::Error>>,
+    deadline: std::time::Duration,
+    ct: &CancellationToken,
+) -> Result<T, rmcp::Error> {
+    tokio::pin!(call);
+    tokio::select! {
+        result = &mut call => return result,
+        _ = tokio::time::sleep(deadline) => {}
+    }
+    tracing::warn!(?deadline, "client deadline elapsed, cancelling");
+    ct.cancel();
+    let _ = call.await;
+    Err(rmcp::Error::internal_error(
+        format!("Client deadline of {}ms elapsed", deadline.as_millis()),
+        None,
+    ))
+}
+
+fn parse_location(val: &str, format: DiagnosticFormat) -> Option<(String, u32, u32)> {
+    let location = val.trim().strip_prefix(format.snippet_start())?;
+    let parts: Vec<&str> = location.split(':').collect();
+    if parts.len() >= 3 {
+        Some((
+            parts.first()?.trim().to_string(),
+            parts.get(1)?.parse().ok()?,
+            parts.get(2)?.parse().ok()?,
+        ))
     } else {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deadline_cancels_and_waits_for_cleanup() {
+        let ct = CancellationToken::new();
+        let cleaned_up = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let call = {
+            let ct = ct.clone();
+            let cleaned_up = cleaned_up.clone();
+            async move {
+                ct.cancelled().await;
+                // Cleanup that takes a while after the cancellation, like restoring files.
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                cleaned_up.store(true, std::sync::atomic::Ordering::SeqCst);
+                Ok::<_, rmcp::Error>(())
+            }
+        };
+
+        let result = with_deadline(call, std::time::Duration::from_millis(10), &ct).await;
+
+        assert!(result.is_err());
+        assert!(ct.is_cancelled());
+        assert!(cleaned_up.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn call_finishing_before_the_deadline_is_not_cancelled() {
+        let ct = CancellationToken::new();
+        let result = with_deadline(
+            async { Ok::<_, rmcp::Error>(42) },
+            std::time::Duration::from_secs(5),
+            &ct,
+        )
+        .await;
+
+        assert_eq!(result.ok(), Some(42));
+        assert!(!ct.is_cancelled());
+    }
+
+    const JSON_ERRORS: &str = r#"[{"file":"./sources/vault.move","line":12,"column":9,"level":"Error","category":3,"code":2,"msg":"Unbound type 'Coin' in current scope"},{"file":"./sources/vault.move","line":4,"column":5,"level":"Warning","category":9,"code":1},{"file":"./sources/vault.move"}]"#;
+
+    const TEXT_ERRORS: &str = "\
+warning[W09001]: unused alias
+  ┌─ ./sources/vault.move:4:9
+  │
+4 │ use sui::coin;
+  │         ^^^^ Unused 'use' of alias 'coin'. Consider removing it
+  │
+  = This alias does not shadow any other name
+
+error[E01002]: unexpected token
+   ┌─ ./sources/vault.move:20:1
+   │
+20 │ }
+   │ ^ Unexpected '}'
+
+";
+
+    fn notice<'a>(
+        diagnostics: &'a HashMap<LineNotice, String>,
+        code: &str,
+    ) -> Option<(&'a LineNotice, &'a String)> {
+        diagnostics.iter().find(|(notice, _)| notice.code == code)
+    }
+
+    #[test]
+    fn json_diagnostics_fall_back_to_text_messages() {
+        let text = extract_build_output(TEXT_ERRORS);
+        let (warnings, errors) = extract_json_diagnostics(JSON_ERRORS, &text).unwrap();
+
+        // Codes and locations come from the JSON.
+        let (unbound, message) = notice(&errors, "E03002").unwrap();
+        assert_eq!((unbound.line_number, unbound.column_number), (12, 9));
+        assert_eq!(
+            message,
+            "error[E03002]: Unbound type 'Coin' in current scope\n  ┌─ ./sources/vault.move:12:9"
+        );
+
+        // No `msg`: the text block for the same diagnostic supplies it.
+        let (unused, message) = notice(&warnings, "W09001").unwrap();
+        assert_eq!((unused.line_number, unused.column_number), (4, 5));
+        assert!(message.contains("Unused 'use' of alias 'coin'"));
+
+        // The last entry does not parse, so text-only diagnostics are kept.
+        let (unexpected, _) = notice(&errors, "E01002").unwrap();
+        assert_eq!(unexpected.line_number, 20);
+        assert_eq!((warnings.len(), errors.len()), (1, 2));
+    }
+
+    #[test]
+    fn json_diagnostics_without_an_array_are_none() {
+        let text = extract_build_output(TEXT_ERRORS);
+        assert!(extract_json_diagnostics(TEXT_ERRORS, &text).is_none());
+        assert!(extract_json_diagnostics("BUILDING vault\n[1, 2]", &text).is_none());
+    }
+
+    // `é` is two bytes, so the byte-based header column is one past the caret.
+    const MULTIBYTE_WARNING: &str = "\
+warning[W09002]: unused variable
+  ┌─ ./sources/vault.move:7:17
+  │
+7 │     let (café, count) = split();
+  │                ^^^^^ Unused local variable 'count'
+  │
+  = Consider removing or prefixing with an underscore
+
+";
+
+    #[test]
+    fn caret_span_counts_characters_not_bytes() {
+        assert_eq!(
+            caret_span(MULTIBYTE_WARNING, 7, DiagnosticFormat::Unicode),
+            Some((16, 21))
+        );
+        assert_eq!(
+            caret_span(MULTIBYTE_WARNING, 8, DiagnosticFormat::Unicode),
+            None
+        );
+
+        let (warnings, _) = extract_build_output(MULTIBYTE_WARNING);
+        let (unused, _) = notice(&warnings, "W09002").unwrap();
+        assert_eq!((unused.line_number, unused.column_number), (7, 16));
+    }
+
+    #[test]
+    fn test_only_compile_error_fails_the_test_build() {
+        let stderr = "\
+error[E03003]: unbound module member
+   ┌─ ./tests/vault_tests.move:9:27
+   │
+ 9 │         let vault = vault::new_for_testing(ctx);
+   │                           ^^^^^^^^^^^^^^^^^^^^^^ Invalid module access. Unbound function 'new_for_testing' in module 'vault::vault'
+
+";
+        let (_, errors) = extract_build_output(stderr);
+        assert_eq!(errors.len(), 1);
+        assert!(test_build_failed(
+            "INCLUDING DEPENDENCY Sui\nBUILDING vault\n",
+            &errors
+        ));
+
+        // Tests that ran and failed are not a build failure.
+        let failed = "\
+Running Move unit tests
+[ FAIL    ] vault::vault_tests::test_withdraw
+Test result: FAILED. Total tests: 1; passed: 0; failed: 1
+";
+        assert!(!test_build_failed(failed, &errors));
+        assert!(!test_build_failed(
+            "Test result: OK. Total tests: 1; passed: 1; failed: 0\n",
+            &HashMap::new()
+        ));
+        assert!(!test_build_failed("", &HashMap::new()));
+    }
+
+    #[test]
+    fn patterns_cannot_match_outside_the_project() {
+        let root = std::env::temp_dir().join(format!("patterns-{}", uuid::Uuid::new_v4()));
+        let project = root.join("project");
+        std::fs::create_dir_all(project.join("sources")).unwrap();
+        std::fs::create_dir_all(root.join("outside")).unwrap();
+        std::fs::write(project.join("sources/vault.move"), "module vault::vault;").unwrap();
+        std::fs::write(root.join("outside/secret.move"), "module secret::secret;").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("outside"), project.join("escape")).unwrap();
+        let config = Config::for_project(&project);
+        let vault = std::fs::canonicalize(project.join("sources/vault.move")).unwrap();
+
+        let patterns = |patterns: &[&str]| {
+            let patterns = patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            resolve_patterns(&config, &patterns)
+        };
+        assert_eq!(patterns(&["sources/*.move"]).unwrap(), vec![vault.clone()]);
+        // Symlinked matches that resolve outside the project are skipped.
+        assert_eq!(patterns(&["**/*.move"]).unwrap(), vec![vault]);
+        assert!(patterns(&["../outside/*.move"]).is_err());
+        assert!(patterns(&["sources/../../outside/*.move"]).is_err());
+        assert!(patterns(&["sources/*.move", "../*/*.move"]).is_err());
+        let absolute = format!("{}/*.move", root.join("outside").display());
+        assert!(patterns(&[absolute.as_str()]).is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn program_candidates_add_pathext_extensions() {
+        // Unix: no `PATHEXT`, only the name itself.
+        assert_eq!(program_candidates("movefmt", None), vec!["movefmt"]);
+        assert_eq!(
+            program_candidates("movefmt", Some(".EXE;.CMD".to_string())),
+            vec!["movefmt", "movefmt.exe", "movefmt.cmd"]
+        );
+        assert_eq!(
+            program_candidates("movefmt", Some(String::new())),
+            vec![
+                "movefmt",
+                "movefmt.com",
+                "movefmt.exe",
+                "movefmt.bat",
+                "movefmt.cmd"
+            ]
+        );
+        assert_eq!(
+            program_candidates("movefmt", Some(";.EXE;".to_string())),
+            vec!["movefmt", "movefmt.exe"]
+        );
+        // An explicit extension is used as is.
+        assert_eq!(
+            program_candidates("prettier.cmd", Some(".EXE;.CMD".to_string())),
+            vec!["prettier.cmd"]
+        );
+    }
+
+    #[test]
+    fn resolve_program_searches_path_in_order() {
+        let root = std::env::temp_dir().join(format!("resolve-{}", uuid::Uuid::new_v4()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("movefmt.exe"), "").unwrap();
+        std::fs::write(second.join("movefmt"), "").unwrap();
+        std::fs::write(second.join("prettier.cmd"), "").unwrap();
+        let paths = Some(std::env::join_paths([&first, &second]).unwrap());
+        let windows = || Some(".EXE;.CMD".to_string());
+
+        // Unix: the extension-less file in the later directory.
+        assert_eq!(
+            resolve_program_in("movefmt", paths.clone(), None),
+            Some(second.join("movefmt"))
+        );
+        assert_eq!(resolve_program_in("prettier", paths.clone(), None), None);
+        // Windows: `PATHEXT` finds `movefmt.exe` in the earlier directory.
+        assert_eq!(
+            resolve_program_in("movefmt", paths.clone(), windows()),
+            Some(first.join("movefmt.exe"))
+        );
+        assert_eq!(
+            resolve_program_in("prettier", paths.clone(), windows()),
+            Some(second.join("prettier.cmd"))
+        );
+        assert_eq!(resolve_program_in("movefmt", None, None), None);
+        assert_eq!(resolve_program_in("", paths.clone(), None), None);
+
+        // A path is checked directly, not searched for.
+        let path = first.join("movefmt");
+        let path = path.to_string_lossy();
+        assert_eq!(resolve_program_in(&path, paths.clone(), None), None);
+        assert_eq!(
+            resolve_program_in(&path, None, windows()),
+            Some(first.join("movefmt.exe"))
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    /// Two packages of one project, each with a manifest, and a fake `sui` whose build of
+    /// either package only succeeds once the other package's build has started too.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn different_packages_build_concurrently() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("concurrent-{}", uuid::Uuid::new_v4()));
+        let project = root.join("project");
+        for package in ["a", "b"] {
+            std::fs::create_dir_all(project.join(package)).unwrap();
+            std::fs::write(
+                project.join(package).join("Move.toml"),
+                format!("[package]\nname = \"{}\"\n", package),
+            )
+            .unwrap();
+        }
+        let bin = root.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(
+            bin.join("sui"),
+            "#!/bin/sh\n\
+             touch started\n\
+             for _ in $(seq 100); do\n\
+             \x20 if [ -e ../a/started ] && [ -e ../b/started ]; then touch built; exit 0; fi\n\
+             \x20 sleep 0.05\n\
+             done\n\
+             exit 1\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(bin.join("sui"), std::fs::Permissions::from_mode(0o755)).unwrap();
+        command::set_env(vec![(
+            "PATH".to_string(),
+            format!(
+                "{}:{}",
+                bin.display(),
+                std::env::var("PATH").unwrap_or_default()
+            ),
+        )]);
+
+        let service = SuiService::new(
+            Arc::new(Config::for_project(&project)),
+            Localnet::default(),
+            Arc::new(PackageLocks::default()),
+            Arc::new(crate::stats::Stats::default()).connect(),
+        );
+        let (a, b) = (
+            service.for_package(Some("a")).unwrap(),
+            service.for_package(Some("b")).unwrap(),
+        );
+        let ct = CancellationToken::new();
+        let (built_a, built_b) = tokio::join!(a.build(false, &ct), b.build(false, &ct));
+
+        assert!(built_a.is_ok() && built_b.is_ok());
+        assert!(project.join("a/built").exists());
+        assert!(project.join("b/built").exists());
+        assert!(service.for_package(Some("../bin")).is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
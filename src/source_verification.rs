@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleMismatch {
+    /// `bytecodeMismatch`, `missingOnChain` or `missingLocally`.
+    pub kind: &'static str,
+    /// On-chain address of the package, when the CLI reports it.
+    pub address: Option<String>,
+    pub package: Option<String>,
+    pub module: String,
+    pub message: String,
+}
+
+/// Extracts the per-module failures from `sui client verify-source` output. Several failures
+/// are printed as a bulleted list under a "Multiple source verification errors" header.
+pub fn parse_mismatches(output: &str) -> Vec<ModuleMismatch> {
+    let stripped = strip_ansi_escapes::strip_str(output);
+    stripped
+        .lines()
+        .filter_map(|line| {
+            let message = line.trim().trim_start_matches("- ").trim();
+            let (kind, path) = if let Some(rest) =
+                message.strip_prefix("Local dependency did not match its on-chain version at ")
+            {
+                ("bytecodeMismatch", rest)
+            } else if let Some(rest) = message.strip_prefix("On-chain version of dependency ") {
+                ("missingOnChain", rest.strip_suffix(" was not found.")?)
+            } else if let Some(rest) = message.strip_prefix("Local version of dependency ") {
+                ("missingLocally", rest.strip_suffix(" was not found.")?)
+            } else {
+                return None;
+            };
+
+            let mut parts = path.trim().rsplitn(3, "::");
+            let module = parts.next()?.to_string();
+            let (address, package) = match (parts.next(), parts.next()) {
+                (Some(package), Some(address)) => {
+                    (Some(address.to_string()), Some(package.to_string()))
+                }
+                // `{package}::{module}` for missing on-chain modules, `{address}::{module}`
+                // for missing local ones.
+                (Some(qualifier), None) if kind == "missingOnChain" => {
+                    (None, Some(qualifier.to_string()))
+                }
+                (Some(qualifier), None) => (Some(qualifier.to_string()), None),
+                _ => (None, None),
+            };
+            Some(ModuleMismatch {
+                kind,
+                address,
+                package,
+                module,
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
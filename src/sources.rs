@@ -0,0 +1,630 @@
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Lists every `.move` file in the package, relative to `project_folder` and sorted, skipping
+/// the `build` output and hidden directories.
+pub fn move_files(project_folder: &str) -> std::io::Result<Vec<PathBuf>> {
+    let root = Path::new(project_folder);
+    let mut files = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if entry.file_type()?.is_dir() {
+                if !(name.starts_with('.') || (dir == root && name == "build")) {
+                    dirs.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "move") {
+                files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Computes a SHA-256 over `Move.toml` and every `.move` file (paths and contents), so the
+/// result only changes when the package sources do.
+pub fn package_hash(project_folder: &str) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut paths = vec![PathBuf::from("Move.toml")];
+    paths.extend(move_files(project_folder)?);
+    for path in paths {
+        let contents = std::fs::read(Path::new(project_folder).join(&path))?;
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Extends [`package_hash`] with the other inputs of a build: `Move.lock`, the sources of each
+/// local dependency folder in `dependencies` and the CLI `version`.
+pub fn build_hash(
+    project_folder: &str,
+    dependencies: &[PathBuf],
+    version: Option<&str>,
+) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut update = |name: &str, contents: &[u8]| {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(contents);
+    };
+    update("package", package_hash(project_folder)?.as_bytes());
+    match std::fs::read(Path::new(project_folder).join("Move.lock")) {
+        Ok(lockfile) => update("Move.lock", &lockfile),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    for dependency in dependencies {
+        let hash = package_hash(&dependency.to_string_lossy())?;
+        update(&dependency.to_string_lossy(), hash.as_bytes());
+    }
+    update("version", version.unwrap_or_default().as_bytes());
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Reads the contents of every `.move` file, keyed by path relative to `project_folder`.
+pub fn snapshot(project_folder: &str) -> std::io::Result<HashMap<PathBuf, Vec<u8>>> {
+    move_files(project_folder)?
+        .into_iter()
+        .map(|path| {
+            let contents = std::fs::read(Path::new(project_folder).join(&path))?;
+            Ok((path, contents))
+        })
+        .collect()
+}
+
+/// Maps each module declared in the package to a SHA-256 of the file declaring it.
+pub fn module_hashes(project_folder: &str) -> std::io::Result<HashMap<String, String>> {
+    let mut hashes = HashMap::new();
+    for contents in snapshot(project_folder)?.into_values() {
+        let hash = to_hex(&Sha256::digest(&contents));
+        let contents = String::from_utf8_lossy(&contents);
+        for module in contents.lines().filter_map(|line| module_name(line.trim())) {
+            hashes.insert(module, hash.clone());
+        }
+    }
+    Ok(hashes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestInfo {
+    /// `address::module::function`, as accepted by `sui move test` filters.
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub expected_failure: bool,
+    /// The arguments of `#[expected_failure(...)]`, e.g. `abort_code = ENotOwner`.
+    pub expected_failure_args: Option<String>,
+}
+
+/// Finds every `#[test]` function in the package by scanning the sources, without building.
+pub fn find_tests(project_folder: &str) -> std::io::Result<Vec<TestInfo>> {
+    let mut tests = vec![];
+    for path in move_files(project_folder)? {
+        let contents = std::fs::read_to_string(Path::new(project_folder).join(&path))?;
+        let file = path.to_string_lossy().to_string();
+
+        let mut module = None;
+        let mut attributes: Vec<String> = vec![];
+
+        for (index, line) in contents.lines().enumerate() {
+            let mut rest = line.trim();
+            if rest.is_empty() || rest.starts_with("//") {
+                continue;
+            }
+
+            if let Some(name) = module_name(rest) {
+                module = Some(name);
+                attributes.clear();
+                continue;
+            }
+
+            while let Some(attribute) = rest.strip_prefix("#[") {
+                let Some(end) = attribute.find(']') else {
+                    break;
+                };
+                attributes.extend(split_attributes(&attribute[..end]));
+                rest = attribute[end + 1..].trim();
+            }
+            if rest.is_empty() {
+                continue;
+            }
+
+            if let (Some(function), Some(module)) = (function_name(rest), &module) {
+                let is_test = attributes.iter().any(|attribute| {
+                    let name = attribute_name(attribute);
+                    name == "test" || name == "random_test"
+                });
+                if is_test {
+                    let expected_failure = attributes
+                        .iter()
+                        .find(|attribute| attribute_name(attribute) == "expected_failure");
+                    tests.push(TestInfo {
+                        name: format!("{}::{}", module, function),
+                        file: file.clone(),
+                        line: index + 1,
+                        expected_failure: expected_failure.is_some(),
+                        expected_failure_args: expected_failure.and_then(|attribute| {
+                            attribute
+                                .split_once('(')
+                                .map(|(_, args)| args.trim_end_matches(')').trim().to_string())
+                        }),
+                    });
+                }
+            }
+            attributes.clear();
+        }
+    }
+    Ok(tests)
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct ConstantInfo {
+    /// `address::module` declaring the constant.
+    pub module: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub value: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Finds every `const` declaration in the package by scanning the sources, without building.
+pub fn find_constants(project_folder: &str) -> std::io::Result<Vec<ConstantInfo>> {
+    let mut constants = vec![];
+    for path in move_files(project_folder)? {
+        let contents = std::fs::read_to_string(Path::new(project_folder).join(&path))?;
+        let mut module = None;
+
+        for (index, line) in contents.lines().enumerate() {
+            let mut rest = line.trim();
+            if let Some(name) = module_name(rest) {
+                module = Some(name);
+                continue;
+            }
+            // `#[error] const EFoo: ...`
+            while let Some((_, after)) = rest.strip_prefix("#[").and_then(|a| a.split_once(']')) {
+                rest = after.trim();
+            }
+            let Some(declaration) = rest.strip_prefix("const ") else {
+                continue;
+            };
+            let Some((name, type_, value)) =
+                declaration.split_once(':').and_then(|(name, rest)| {
+                    let (type_, value) = rest.split_once('=')?;
+                    Some((name, type_, value))
+                })
+            else {
+                continue;
+            };
+            let Some(module) = &module else {
+                continue;
+            };
+            constants.push(ConstantInfo {
+                module: module.clone(),
+                name: name.trim().to_string(),
+                type_: type_.trim().to_string(),
+                value: value.split(';').next().unwrap_or(value).trim().to_string(),
+                file: path.to_string_lossy().to_string(),
+                line: index + 1,
+            });
+        }
+    }
+    Ok(constants)
+}
+
+/// Parses a Move integer literal such as `1`, `0x1F`, `1_000` or `7u64`.
+pub fn parse_integer(literal: &str) -> Option<u128> {
+    let literal = literal.replace('_', "");
+    let (digits, radix) = match literal.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None => (literal.as_str(), 10),
+    };
+    let digits = ["u8", "u16", "u32", "u64", "u128", "u256"]
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits);
+    u128::from_str_radix(digits, radix).ok()
+}
+
+/// Parses `module addr::name {` and `module addr::name;` declarations.
+fn module_name(line: &str) -> Option<String> {
+    let rest = line.strip_prefix("module ")?;
+    let end = rest
+        .find(|c: char| c == '{' || c == ';' || c.is_whitespace())
+        .unwrap_or(rest.len());
+    Some(rest[..end].to_string())
+}
+
+/// Parses the name from a function declaration with any visibility/entry modifiers.
+pub(crate) fn function_name(line: &str) -> Option<&str> {
+    let start = line.find("fun ")? + "fun ".len();
+    let prefix = &line[..start - "fun ".len()];
+    if !prefix.split_whitespace().all(|word| {
+        matches!(word, "public" | "entry" | "native" | "macro") || word.starts_with("public(")
+    }) {
+        return None;
+    }
+    let rest = &line[start..];
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    Some(&rest[..end]).filter(|name| !name.is_empty())
+}
+
+/// Splits `test, expected_failure(abort_code = 1)` (or a use group like `a, b::{C, D}`) on
+/// top-level commas.
+fn split_attributes(input: &str) -> Vec<String> {
+    let mut attributes = vec![];
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in input.chars() {
+        match c {
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                attributes.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        attributes.push(current.trim().to_string());
+    }
+    attributes
+}
+
+fn attribute_name(attribute: &str) -> &str {
+    attribute.split('(').next().unwrap_or(attribute).trim()
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct SearchMatch {
+    pub file: String,
+    pub line: usize,
+    pub text: String,
+    /// Surrounding lines, including the matching one, as `(line number, text)`.
+    pub context: Vec<(usize, String)>,
+}
+
+/// Searches every `.move` file for `regex`, stopping after `max_results` matching lines.
+/// Returns the matches and whether the search was cut short.
+pub fn search(
+    project_folder: &str,
+    regex: &regex::Regex,
+    max_results: usize,
+    context_lines: usize,
+) -> std::io::Result<(Vec<SearchMatch>, bool)> {
+    let mut matches = vec![];
+    for path in move_files(project_folder)? {
+        let contents = std::fs::read_to_string(Path::new(project_folder).join(&path))?;
+        let lines = contents.lines().collect::<Vec<_>>();
+        for (index, line) in lines.iter().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            if matches.len() == max_results {
+                return Ok((matches, true));
+            }
+            let start = index.saturating_sub(context_lines);
+            let end = (index + context_lines + 1).min(lines.len());
+            matches.push(SearchMatch {
+                file: path.to_string_lossy().to_string(),
+                line: index + 1,
+                text: line.to_string(),
+                context: (start..end)
+                    .map(|i| (i + 1, lines[i].to_string()))
+                    .collect(),
+            });
+        }
+    }
+    Ok((matches, false))
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ModuleDependency {
+    pub from: String,
+    pub to: String,
+    pub file: String,
+    /// Line of the `use` declaration.
+    pub line: usize,
+}
+
+/// Collects the `use` dependencies between modules of the package by scanning the sources.
+/// Uses of modules outside the package are left out, since they cannot form a cycle with it.
+pub fn module_dependencies(project_folder: &str) -> std::io::Result<Vec<ModuleDependency>> {
+    let mut files = vec![];
+    for path in move_files(project_folder)? {
+        let contents = std::fs::read_to_string(Path::new(project_folder).join(&path))?;
+        files.push((path, contents));
+    }
+    let local = files
+        .iter()
+        .flat_map(|(_, contents)| contents.lines().filter_map(|line| module_name(line.trim())))
+        .collect::<std::collections::HashSet<_>>();
+
+    let mut dependencies = vec![];
+    for (path, contents) in &files {
+        let mut module = None;
+        // A `use` spanning several lines, with the line it started on.
+        let mut pending: Option<(usize, String)> = None;
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+            if let Some(name) = module_name(line) {
+                module = Some(name);
+                continue;
+            }
+            let statement = match pending.take() {
+                Some((start, mut text)) => {
+                    text.push(' ');
+                    text.push_str(line);
+                    (start, text)
+                }
+                None => {
+                    let mut rest = line;
+                    while let Some((_, after)) =
+                        rest.strip_prefix("#[").and_then(|a| a.split_once(']'))
+                    {
+                        rest = after.trim();
+                    }
+                    let Some(rest) = rest
+                        .strip_prefix("public use ")
+                        .or_else(|| rest.strip_prefix("use "))
+                    else {
+                        continue;
+                    };
+                    (index, rest.to_string())
+                }
+            };
+            let Some((tree, _)) = statement.1.split_once(';') else {
+                pending = Some(statement);
+                continue;
+            };
+            let Some(from) = &module else {
+                continue;
+            };
+            for to in used_modules(tree) {
+                if local.contains(&to) && &to != from {
+                    dependencies.push(ModuleDependency {
+                        from: from.clone(),
+                        to,
+                        file: path.to_string_lossy().to_string(),
+                        line: statement.0 + 1,
+                    });
+                }
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FriendDeclaration {
+    /// Module declaring the friend.
+    pub module: String,
+    /// The friend module, with a `use` alias expanded to its full name.
+    pub friend: String,
+    pub file: String,
+    pub line: usize,
+    /// The package has no module of that name.
+    pub dangling: bool,
+}
+
+/// A module's side of the friendship graph.
+#[derive(serde::Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Friendship {
+    /// Modules this module declares as friends.
+    pub friends: Vec<String>,
+    /// Modules declaring this module a friend.
+    pub friend_of: Vec<String>,
+    /// `public(friend)` functions.
+    pub friend_functions: Vec<String>,
+    /// `public(package)` functions.
+    pub package_functions: Vec<String>,
+}
+
+/// Collects the `friend` declarations of every module in the package. A friend given by
+/// alias (`friend b;` after `use pkg::b;`) is expanded; one whose address is spelled
+/// differently from the module's own declaration still resolves by module name.
+pub fn friend_declarations(project_folder: &str) -> std::io::Result<Vec<FriendDeclaration>> {
+    let mut files = vec![];
+    for path in move_files(project_folder)? {
+        let contents = std::fs::read_to_string(Path::new(project_folder).join(&path))?;
+        files.push((path, contents));
+    }
+    let local = files
+        .iter()
+        .flat_map(|(_, contents)| contents.lines().filter_map(|line| module_name(line.trim())))
+        .collect::<Vec<_>>();
+    let resolve = |friend: &str| {
+        local.iter().find(|module| *module == friend).or_else(|| {
+            let name = friend.rsplit("::").next();
+            local
+                .iter()
+                .find(|module| module.rsplit("::").next() == name)
+        })
+    };
+
+    let mut declarations = vec![];
+    for (path, contents) in &files {
+        let mut module = None;
+        let mut aliases = HashMap::new();
+        for (index, line) in contents.lines().enumerate() {
+            let mut line = line.split("//").next().unwrap_or_default().trim();
+            if let Some(name) = module_name(line) {
+                module = Some(name);
+                aliases.clear();
+                continue;
+            }
+            while let Some((_, after)) = line.strip_prefix("#[").and_then(|a| a.split_once(']')) {
+                line = after.trim();
+            }
+            if let Some(tree) = line
+                .strip_prefix("use ")
+                .and_then(|rest| rest.split_once(';'))
+                .map(|(tree, _)| tree.trim())
+            {
+                match tree.split_once(" as ") {
+                    Some((path, alias)) if !path.contains('{') => {
+                        aliases.insert(alias.trim().to_string(), path.trim().to_string());
+                    }
+                    _ => {
+                        for used in used_modules(tree) {
+                            let name = used.rsplit("::").next().unwrap_or_default().to_string();
+                            aliases.insert(name, used);
+                        }
+                    }
+                }
+                continue;
+            }
+            let (Some(from), Some(friend)) = (
+                &module,
+                line.strip_prefix("friend ")
+                    .and_then(|rest| rest.split_once(';'))
+                    .map(|(friend, _)| friend.trim()),
+            ) else {
+                continue;
+            };
+            let friend = aliases
+                .get(friend)
+                .cloned()
+                .unwrap_or_else(|| friend.to_string());
+            let resolved = resolve(&friend);
+            declarations.push(FriendDeclaration {
+                module: from.clone(),
+                friend: resolved.cloned().unwrap_or(friend),
+                file: path.to_string_lossy().to_string(),
+                line: index + 1,
+                dangling: resolved.is_none(),
+            });
+        }
+    }
+    Ok(declarations)
+}
+
+/// Expands a use tree such as `pkg::a`, `pkg::a::{Self, T}` or `pkg::{a, b::T}` into the
+/// `address::module` names it refers to. Function-level uses like `use fun` are ignored.
+fn used_modules(tree: &str) -> Vec<String> {
+    let tree = tree.trim();
+    let Some((address, rest)) = tree.split_once("::") else {
+        return vec![];
+    };
+    let address = address.trim();
+    if address == "fun" || address.contains(char::is_whitespace) {
+        return vec![];
+    }
+    let rest = rest.trim();
+    let items = match rest.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+        Some(group) => split_attributes(group),
+        None => vec![rest.to_string()],
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let end = item
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(item.len());
+            let name = &item[..end];
+            (!name.is_empty()).then(|| format!("{}::{}", address, name))
+        })
+        .collect()
+}
+
+/// Finds every cycle in the module dependency graph. Each cycle is reported once, as a path
+/// starting and ending at its alphabetically first module.
+pub fn find_cycles(dependencies: &[ModuleDependency]) -> Vec<Vec<String>> {
+    let mut graph = std::collections::BTreeMap::<&str, std::collections::BTreeSet<&str>>::new();
+    for dependency in dependencies {
+        graph
+            .entry(&dependency.from)
+            .or_default()
+            .insert(&dependency.to);
+    }
+
+    let mut cycles = std::collections::BTreeSet::new();
+    for &start in graph.keys() {
+        // Only follow modules ordered after `start`, so each cycle is found from its first
+        // module and nowhere else.
+        let mut stack = vec![(start, vec![start])];
+        while let Some((module, path)) = stack.pop() {
+            for &next in graph.get(module).into_iter().flatten() {
+                if next == start {
+                    let mut cycle = path.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+                    cycle.push(start.to_string());
+                    cycles.insert(cycle);
+                } else if next > start && !path.contains(&next) {
+                    let mut path = path.clone();
+                    path.push(next);
+                    stack.push((next, path));
+                }
+            }
+        }
+    }
+    cycles.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_hash_covers_lockfile_dependencies_and_version() {
+        let root = std::env::temp_dir().join(format!("build-hash-{}", uuid::Uuid::new_v4()));
+        let (package, dependency) = (root.join("vault"), root.join("math"));
+        for folder in [&package, &dependency] {
+            std::fs::create_dir_all(folder.join("sources")).unwrap();
+        }
+        std::fs::write(
+            package.join("Move.toml"),
+            "[package]\nname = \"vault\"\n\n[dependencies]\nmath = { local = \"../math\" }\n",
+        )
+        .unwrap();
+        std::fs::write(package.join("sources/vault.move"), "module vault::vault;").unwrap();
+        std::fs::write(dependency.join("Move.toml"), "[package]\nname = \"math\"\n").unwrap();
+        std::fs::write(dependency.join("sources/math.move"), "module math::math;").unwrap();
+
+        let folder = package.to_string_lossy().to_string();
+        let dependencies = crate::manifest::local_dependencies(&folder);
+        assert_eq!(dependencies, vec![dependency.canonicalize().unwrap()]);
+        let hash = || build_hash(&folder, &dependencies, Some("sui 1.50.0")).unwrap();
+
+        let initial = hash();
+        assert_eq!(hash(), initial);
+
+        std::fs::write(package.join("Move.lock"), "[move]\nversion = 3\n").unwrap();
+        let locked = hash();
+        assert_ne!(locked, initial);
+
+        std::fs::write(
+            dependency.join("sources/math.move"),
+            "module math::math { }",
+        )
+        .unwrap();
+        let dependency_changed = hash();
+        assert_ne!(dependency_changed, locked);
+
+        let upgraded = build_hash(&folder, &dependencies, Some("sui 1.51.0")).unwrap();
+        assert_ne!(upgraded, dependency_changed);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+/// Server-wide connection and request counters, served at `/stats`.
+#[derive(Debug, Default)]
+pub struct Stats {
+    active_connections: AtomicU64,
+    total_connections: AtomicU64,
+    total_requests: AtomicU64,
+}
+
+#[derive(serde::Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsSnapshot {
+    pub active_connections: u64,
+    pub total_connections: u64,
+    pub total_requests: u64,
+}
+
+impl Stats {
+    /// Records a new MCP connection, which counts as active until the returned handle is
+    /// dropped.
+    pub fn connect(self: &Arc<Self>) -> Connection {
+        let id = self.total_connections.fetch_add(1, Ordering::Relaxed) + 1;
+        let active = self.active_connections.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::info!(connection = id, active, "client connected");
+        Connection {
+            stats: self.clone(),
+            id,
+        }
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A live connection, owned by its session's service.
+#[derive(Debug)]
+pub struct Connection {
+    stats: Arc<Stats>,
+    id: u64,
+}
+
+impl Connection {
+    pub fn record_request(&self) {
+        self.stats.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let active = self
+            .stats
+            .active_connections
+            .fetch_sub(1, Ordering::Relaxed)
+            - 1;
+        tracing::info!(connection = self.id, active, "client disconnected");
+    }
+}
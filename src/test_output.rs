@@ -0,0 +1,409 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// `#[expected_failure(abort_code = X)]` test aborted, but with a different code.
+    WrongAbortCode,
+    /// `#[expected_failure]` test completed without aborting.
+    DidNotAbort,
+    /// A test without `#[expected_failure]` aborted.
+    UnexpectedAbort,
+    /// The test exhausted its gas/instruction budget (`--gas-limit`), e.g. a runaway loop.
+    OutOfGas,
+    Other,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TestFailure {
+    /// `address::module::function` of the failing test.
+    pub name: String,
+    pub kind: FailureKind,
+    pub expected_code: Option<String>,
+    pub actual_code: Option<String>,
+    pub message: String,
+    /// Where the test aborted, from the diagnostic's source location.
+    pub abort_location: Option<AbortLocation>,
+    /// The `stack trace` frames printed under the failure, innermost caller first.
+    pub stack_trace: Vec<StackFrame>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AbortLocation {
+    /// Module the abort originated in, e.g. `0x0::vault`.
+    pub module: Option<String>,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StackFrame {
+    pub module: String,
+    pub function: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+/// Reads the `[ PASS    ] 0x0::module::test` line printed for each test that ran, in
+/// output order.
+pub fn parse_statuses(stdout: &str) -> Vec<(String, TestStatus)> {
+    let stripped = strip_ansi_escapes::strip_str(stdout);
+    stripped
+        .lines()
+        .filter_map(|line| {
+            let (status, name) = line.trim().strip_prefix('[')?.split_once(']')?;
+            let status = match status.trim() {
+                "PASS" => TestStatus::Pass,
+                "FAIL" => TestStatus::Fail,
+                "TIMEOUT" => TestStatus::Timeout,
+                _ => return None,
+            };
+            Some((name.trim().to_string(), status))
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TestStatistics {
+    pub name: String,
+    pub seconds: f64,
+    /// Gas (instructions) used, when the CLI reports it.
+    pub gas_used: Option<u64>,
+}
+
+/// Reads the per-test rows of the `Test Statistics` table printed with `--statistics`.
+pub fn parse_statistics(stdout: &str) -> Vec<TestStatistics> {
+    let stripped = strip_ansi_escapes::strip_str(stdout);
+    stripped
+        .lines()
+        .filter_map(|line| {
+            let mut cells = line
+                .split('│')
+                .map(str::trim)
+                .filter(|cell| !cell.is_empty());
+            let name = cells.next().filter(|name| name.contains("::"))?;
+            let seconds = cells.next()?.trim_end_matches('s').parse().ok()?;
+            Some(TestStatistics {
+                name: name.to_string(),
+                seconds,
+                gas_used: cells.next().and_then(|gas| gas.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Renders the results of a test run as a JUnit XML report, with one `testsuite` per module
+/// and a `failure` element carrying the message of each failing or timed out test.
+/// `statistics` supplies the per-test `time` attributes when available.
+pub fn junit_xml(
+    package: &str,
+    statuses: &[(String, TestStatus)],
+    failures: &[TestFailure],
+    statistics: &[TestStatistics],
+) -> String {
+    let time = |name: &str| {
+        statistics
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.seconds)
+    };
+    let is_failure = |status: &TestStatus| *status != TestStatus::Pass;
+
+    // Modules in order of their first test.
+    let mut modules: Vec<(&str, Vec<(&str, &String, TestStatus)>)> = vec![];
+    for (name, status) in statuses {
+        let (module, function) = name.rsplit_once("::").unwrap_or(("", name));
+        match modules.iter_mut().find(|(other, _)| *other == module) {
+            Some((_, tests)) => tests.push((function, name, *status)),
+            None => modules.push((module, vec![(function, name, *status)])),
+        }
+    }
+
+    let total_time = statuses
+        .iter()
+        .filter_map(|(name, _)| time(name))
+        .fold(0.0, |total, seconds| total + seconds);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        escape_xml(package),
+        statuses.len(),
+        statuses
+            .iter()
+            .filter(|(_, status)| is_failure(status))
+            .count(),
+        total_time
+    ));
+    for (module, tests) in &modules {
+        let suite_time = tests
+            .iter()
+            .filter_map(|(_, name, _)| time(name))
+            .fold(0.0, |total, seconds| total + seconds);
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(module),
+            tests.len(),
+            tests
+                .iter()
+                .filter(|(_, _, status)| is_failure(status))
+                .count(),
+            suite_time
+        ));
+        for (function, name, status) in tests {
+            let mut testcase = format!(
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(function),
+                escape_xml(module)
+            );
+            if let Some(seconds) = time(name) {
+                testcase.push_str(&format!(" time=\"{:.3}\"", seconds));
+            }
+            if !is_failure(status) {
+                xml.push_str(&testcase);
+                xml.push_str("/>\n");
+                continue;
+            }
+            let failure = failures.iter().find(|failure| &failure.name == *name);
+            let (kind, message) = match (status, failure) {
+                (TestStatus::Timeout, _) => ("timeout".to_string(), "Test timed out".to_string()),
+                (_, Some(failure)) => (
+                    serde_json::to_value(failure.kind)
+                        .ok()
+                        .and_then(|kind| kind.as_str().map(str::to_string))
+                        .unwrap_or_default(),
+                    failure.message.clone(),
+                ),
+                (_, None) => ("failure".to_string(), "Test failed".to_string()),
+            };
+            let summary = message.lines().next().unwrap_or_default();
+            xml.push_str(&testcase);
+            xml.push_str(">\n");
+            xml.push_str(&format!(
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                escape_xml(&kind),
+                escape_xml(summary),
+                escape_xml(&message)
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escapes text for use in XML content and attribute values, dropping control characters
+/// XML 1.0 cannot represent.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits the `Test failures:` section of `sui move test` output into one entry per failing
+/// test, classifying each failure.
+pub fn parse_failures(stdout: &str) -> Vec<TestFailure> {
+    let stripped = strip_ansi_escapes::strip_str(stdout);
+    let mut failures = vec![];
+    let mut module = String::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in stripped.lines() {
+        if let Some(name) = line
+            .strip_prefix("Failures in ")
+            .and_then(|rest| rest.strip_suffix(':'))
+        {
+            module = name.to_string();
+        } else if let Some(rest) = line.strip_prefix("┌──") {
+            let name = rest.trim().trim_end_matches('─').trim();
+            current = Some((name.to_string(), vec![]));
+        } else if line.starts_with("└──") {
+            if let Some((name, lines)) = current.take() {
+                let name = if module.is_empty() {
+                    name
+                } else {
+                    format!("{}::{}", module, name)
+                };
+                failures.push(classify(name, &lines.join("\n")));
+            }
+        } else if let Some((_, lines)) = current.as_mut() {
+            let content = line.strip_prefix('│').unwrap_or(line);
+            lines.push(content.strip_prefix(' ').unwrap_or(content));
+        }
+    }
+
+    failures
+}
+
+fn classify(name: String, message: &str) -> TestFailure {
+    let (kind, expected_code, actual_code) =
+        if message.contains("OUT_OF_GAS") || message.contains("Test timed out") {
+            (FailureKind::OutOfGas, None, None)
+        } else if message.contains("did not abort with expected code") {
+            (
+                FailureKind::WrongAbortCode,
+                code_after(message, "abort with code "),
+                code_after(message, "instead it aborted with code "),
+            )
+        } else if message.contains("did not error as expected") {
+            (FailureKind::DidNotAbort, None, None)
+        } else if message.contains("not expected to error") {
+            (
+                FailureKind::UnexpectedAbort,
+                None,
+                code_after(message, "aborted with code "),
+            )
+        } else {
+            (FailureKind::Other, None, None)
+        };
+
+    TestFailure {
+        name,
+        kind,
+        expected_code,
+        actual_code,
+        message: message.trim().to_string(),
+        abort_location: abort_location(message),
+        stack_trace: stack_trace(message),
+    }
+}
+
+/// Reads the first `┌─ file:line:column` of the failure's diagnostic, along with the module
+/// named in "originating in the module ...".
+fn abort_location(message: &str) -> Option<AbortLocation> {
+    let location = message
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("┌─"))?;
+    let mut parts = location.trim().rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?.to_string();
+    let module = message
+        .split_once("originating in the module ")
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .map(|module| module.to_string());
+    Some(AbortLocation {
+        module,
+        file,
+        line,
+        column,
+    })
+}
+
+/// Parses the `module::function(file:line)` frames listed after a `stack trace` line.
+fn stack_trace(message: &str) -> Vec<StackFrame> {
+    let Some((_, trace)) = message.split_once("stack trace") else {
+        return vec![];
+    };
+    trace
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with("error") && !line.starts_with("└"))
+        .filter_map(|line| {
+            let (path, location) = match line.split_once('(') {
+                Some((path, location)) => (path, Some(location.trim_end_matches(')'))),
+                None => (line, None),
+            };
+            let (module, function) = path.rsplit_once("::")?;
+            let (file, line) = match location.and_then(|location| location.rsplit_once(':')) {
+                Some((file, line)) => (Some(file.to_string()), line.parse().ok()),
+                None => (location.map(|location| location.to_string()), None),
+            };
+            Some(StackFrame {
+                module: module.to_string(),
+                function: function.to_string(),
+                file,
+                line,
+            })
+        })
+        .collect()
+}
+
+fn code_after(message: &str, pattern: &str) -> Option<String> {
+    let start = message.find(pattern)? + pattern.len();
+    let code = message[start..]
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .next()?;
+    Some(code.to_string()).filter(|code| !code.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn junit_xml_reports_passes_failures_and_timeouts() {
+        let statuses = vec![
+            ("0x0::vault::deposit_works".to_string(), TestStatus::Pass),
+            ("0x0::vault::withdraw_fails".to_string(), TestStatus::Fail),
+            (
+                "0x0::vault_tests::slow_loop".to_string(),
+                TestStatus::Timeout,
+            ),
+        ];
+        let failures = vec![TestFailure {
+            name: "0x0::vault::withdraw_fails".to_string(),
+            kind: FailureKind::UnexpectedAbort,
+            expected_code: None,
+            actual_code: Some("0".to_string()),
+            message: "error[E11001]: test failure\nassert!(x < 1 && y > \"q\", 0); // it's"
+                .to_string(),
+            abort_location: None,
+            stack_trace: vec![],
+        }];
+        let statistics = vec![
+            TestStatistics {
+                name: "0x0::vault::deposit_works".to_string(),
+                seconds: 0.004,
+                gas_used: Some(12),
+            },
+            TestStatistics {
+                name: "0x0::vault::withdraw_fails".to_string(),
+                seconds: 0.01,
+                gas_used: None,
+            },
+        ];
+
+        assert_eq!(
+            junit_xml("vault", &statuses, &failures, &statistics),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="vault" tests="3" failures="2" time="0.014">
+  <testsuite name="0x0::vault" tests="2" failures="1" time="0.014">
+    <testcase name="deposit_works" classname="0x0::vault" time="0.004"/>
+    <testcase name="withdraw_fails" classname="0x0::vault" time="0.010">
+      <failure type="unexpected_abort" message="error[E11001]: test failure">error[E11001]: test failure&#10;assert!(x &lt; 1 &amp;&amp; y &gt; &quot;q&quot;, 0); // it&apos;s</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="0x0::vault_tests" tests="1" failures="1" time="0.000">
+    <testcase name="slow_loop" classname="0x0::vault_tests">
+      <failure type="timeout" message="Test timed out">Test timed out</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
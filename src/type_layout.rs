@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+/// A Move type, with addresses in full 32-byte hex form.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeTag {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector(Box<TypeTag>),
+    Struct(StructTag),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructTag {
+    pub address: String,
+    pub module: String,
+    pub name: String,
+    pub type_args: Vec<TypeTag>,
+}
+
+impl StructTag {
+    /// `address::module::name`, without type arguments.
+    pub fn key(&self) -> String {
+        format!("{}::{}::{}", self.address, self.module, self.name)
+    }
+}
+
+impl std::fmt::Display for TypeTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeTag::Bool => write!(f, "bool"),
+            TypeTag::U8 => write!(f, "u8"),
+            TypeTag::U16 => write!(f, "u16"),
+            TypeTag::U32 => write!(f, "u32"),
+            TypeTag::U64 => write!(f, "u64"),
+            TypeTag::U128 => write!(f, "u128"),
+            TypeTag::U256 => write!(f, "u256"),
+            TypeTag::Address => write!(f, "address"),
+            TypeTag::Signer => write!(f, "signer"),
+            TypeTag::Vector(element) => write!(f, "vector<{}>", element),
+            TypeTag::Struct(tag) => {
+                write!(f, "{}", tag.key())?;
+                if !tag.type_args.is_empty() {
+                    let args = tag
+                        .type_args
+                        .iter()
+                        .map(|arg| arg.to_string())
+                        .collect::<Vec<_>>();
+                    write!(f, "<{}>", args.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Pads a `0x`-prefixed hex address to its full 64-digit lowercase form.
+pub fn normalize_address(address: &str) -> Option<String> {
+    let hex = address.strip_prefix("0x")?;
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!("0x{:0>64}", hex.to_ascii_lowercase()))
+}
+
+/// Parses a type such as `0x2::coin::Coin<0x2::sui::SUI>` or `vector<u8>`.
+pub fn parse(input: &str) -> Result<TypeTag, String> {
+    let (tag, rest) = parse_prefix(input.trim())?;
+    if !rest.trim().is_empty() {
+        return Err(format!("Unexpected `{}` after type `{}`", rest.trim(), tag));
+    }
+    Ok(tag)
+}
+
+/// Parses one type from the start of `input`, returning it and the unparsed remainder.
+fn parse_prefix(input: &str) -> Result<(TypeTag, &str), String> {
+    let input = input.trim_start();
+    let end = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(input.len());
+    let (path, rest) = input.split_at(end);
+
+    let (type_args, rest) = match rest.trim_start().strip_prefix('<') {
+        Some(mut rest) => {
+            let mut args = vec![];
+            loop {
+                let (arg, after) = parse_prefix(rest)?;
+                args.push(arg);
+                let after = after.trim_start();
+                if let Some(after) = after.strip_prefix(',') {
+                    rest = after;
+                } else if let Some(after) = after.strip_prefix('>') {
+                    break (args, after);
+                } else {
+                    return Err(format!(
+                        "Expected `,` or `>` in type arguments of `{}`",
+                        path
+                    ));
+                }
+            }
+        }
+        None => (vec![], rest),
+    };
+
+    let primitive = match path {
+        "bool" => Some(TypeTag::Bool),
+        "u8" => Some(TypeTag::U8),
+        "u16" => Some(TypeTag::U16),
+        "u32" => Some(TypeTag::U32),
+        "u64" => Some(TypeTag::U64),
+        "u128" => Some(TypeTag::U128),
+        "u256" => Some(TypeTag::U256),
+        "address" => Some(TypeTag::Address),
+        "signer" => Some(TypeTag::Signer),
+        _ => None,
+    };
+    let tag = match (primitive, path) {
+        (Some(tag), _) if type_args.is_empty() => tag,
+        (None, "vector") => match <[TypeTag; 1]>::try_from(type_args) {
+            Ok([element]) => TypeTag::Vector(Box::new(element)),
+            Err(_) => return Err("`vector` takes exactly one type argument".to_string()),
+        },
+        (None, _) => {
+            let mut parts = path.split("::");
+            let (Some(address), Some(module), Some(name), None) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(format!(
+                    "`{}` is not a primitive or an `address::module::Struct` type",
+                    path
+                ));
+            };
+            let address = normalize_address(address)
+                .ok_or_else(|| format!("`{}` is not a hex address", address))?;
+            TypeTag::Struct(StructTag {
+                address,
+                module: module.to_string(),
+                name: name.to_string(),
+                type_args,
+            })
+        }
+        (Some(_), _) => return Err(format!("`{}` takes no type arguments", path)),
+    };
+    Ok((tag, rest))
+}
+
+/// Converts a type from `sui_getNormalizedMoveStruct` into a tag, substituting the
+/// struct's `TypeParameter` indices with `type_args`.
+pub fn from_normalized(value: &serde_json::Value, type_args: &[TypeTag]) -> Option<TypeTag> {
+    if let Some(name) = value.as_str() {
+        return Some(match name {
+            "Bool" => TypeTag::Bool,
+            "U8" => TypeTag::U8,
+            "U16" => TypeTag::U16,
+            "U32" => TypeTag::U32,
+            "U64" => TypeTag::U64,
+            "U128" => TypeTag::U128,
+            "U256" => TypeTag::U256,
+            "Address" => TypeTag::Address,
+            "Signer" => TypeTag::Signer,
+            _ => return None,
+        });
+    }
+    if let Some(element) = value.get("Vector") {
+        return Some(TypeTag::Vector(Box::new(from_normalized(
+            element, type_args,
+        )?)));
+    }
+    if let Some(index) = value.get("TypeParameter") {
+        return type_args
+            .get(usize::try_from(index.as_u64()?).ok()?)
+            .cloned();
+    }
+    let tag = value.get("Struct")?;
+    Some(TypeTag::Struct(StructTag {
+        address: normalize_address(tag["address"].as_str()?)?,
+        module: tag["module"].as_str()?.to_string(),
+        name: tag["name"].as_str()?.to_string(),
+        type_args: tag["typeArguments"]
+            .as_array()?
+            .iter()
+            .map(|arg| from_normalized(arg, type_args))
+            .collect::<Option<Vec<_>>>()?,
+    }))
+}
+
+/// Lists the structs reachable from `tag` whose definitions are missing from `structs`
+/// (normalized definitions keyed by [`StructTag::key`]).
+pub fn missing_structs(
+    tag: &TypeTag,
+    structs: &HashMap<String, serde_json::Value>,
+) -> Vec<StructTag> {
+    let mut missing: Vec<StructTag> = vec![];
+    let mut pending = vec![tag.clone()];
+    while let Some(tag) = pending.pop() {
+        match tag {
+            TypeTag::Vector(element) => pending.push(*element),
+            TypeTag::Struct(tag) => {
+                pending.extend(tag.type_args.iter().cloned());
+                match structs.get(&tag.key()) {
+                    Some(definition) => pending.extend(field_types(definition, &tag.type_args)),
+                    None if !missing.iter().any(|other| other.key() == tag.key()) => {
+                        missing.push(tag)
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    missing
+}
+
+fn field_types(definition: &serde_json::Value, type_args: &[TypeTag]) -> Vec<TypeTag> {
+    definition["fields"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|field| from_normalized(&field["type"], type_args))
+        .collect()
+}
+
+/// Builds the nested field layout of `tag` from the fetched struct definitions, in BCS
+/// order. Structs whose definition is unavailable have `fields: null`.
+pub fn layout(tag: &TypeTag, structs: &HashMap<String, serde_json::Value>) -> serde_json::Value {
+    match tag {
+        TypeTag::Vector(element) => serde_json::json!({
+            "type": tag.to_string(),
+            "element": layout(element, structs),
+        }),
+        TypeTag::Struct(struct_tag) => {
+            let Some(definition) = structs.get(&struct_tag.key()) else {
+                return serde_json::json!({ "type": tag.to_string(), "fields": null });
+            };
+            let fields = definition["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|field| {
+                    let layout = match from_normalized(&field["type"], &struct_tag.type_args) {
+                        Some(field_type) => layout(&field_type, structs),
+                        None => serde_json::json!({ "type": field["type"], "fields": null }),
+                    };
+                    serde_json::json!({ "name": field["name"], "layout": layout })
+                })
+                .collect::<Vec<_>>();
+            let abilities = definition["abilities"]["abilities"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|ability| Some(ability.as_str()?.to_ascii_lowercase()))
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "type": tag.to_string(),
+                "abilities": abilities,
+                "fields": fields,
+            })
+        }
+        _ => serde_json::json!({ "type": tag.to_string() }),
+    }
+}
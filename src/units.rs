@@ -0,0 +1,113 @@
+/// MIST in one SUI.
+const MIST_PER_SUI: u64 = 1_000_000_000;
+
+/// Decimal places of a SUI amount, the precision of one MIST.
+const DECIMALS: usize = 9;
+
+/// Converts a decimal SUI amount such as `1.5` or `0.000000001` to MIST, rejecting amounts
+/// finer than one MIST or too large for a `u64`.
+pub fn sui_to_mist(amount: &str) -> Result<u64, String> {
+    let amount = amount.trim();
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(format!("`{}` is not a decimal SUI amount", amount));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > DECIMALS {
+        return Err(format!(
+            "`{}` has more than {} decimal places, finer than 1 MIST",
+            amount, DECIMALS
+        ));
+    }
+    let too_large = || format!("`{}` SUI exceeds the largest MIST amount", amount);
+    let whole = match whole {
+        "" => 0,
+        whole => whole.parse::<u64>().map_err(|_| too_large())?,
+    };
+    let fraction = format!("{:0<width$}", fraction, width = DECIMALS)
+        .parse::<u64>()
+        .map_err(|_| too_large())?;
+    whole
+        .checked_mul(MIST_PER_SUI)
+        .and_then(|mist| mist.checked_add(fraction))
+        .ok_or_else(too_large)
+}
+
+/// Parses a whole MIST amount.
+pub fn parse_mist(amount: &str) -> Result<u64, String> {
+    let amount = amount.trim();
+    if amount.is_empty() || !amount.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "`{}` is not a MIST amount: MIST amounts are whole numbers",
+            amount
+        ));
+    }
+    amount
+        .parse()
+        .map_err(|_| format!("`{}` MIST exceeds the largest u64", amount))
+}
+
+/// Writes a MIST amount in SUI without trailing zeros, e.g. `1.5` or `0.000000001`.
+pub fn mist_to_sui(mist: u64) -> String {
+    let fraction = format!("{:0>width$}", mist % MIST_PER_SUI, width = DECIMALS);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (mist / MIST_PER_SUI).to_string()
+    } else {
+        format!("{}.{}", mist / MIST_PER_SUI, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sui_to_mist_keeps_nine_decimal_places() {
+        assert_eq!(sui_to_mist("1"), Ok(1_000_000_000));
+        assert_eq!(sui_to_mist("1.5"), Ok(1_500_000_000));
+        assert_eq!(sui_to_mist(".5"), Ok(500_000_000));
+        assert_eq!(sui_to_mist("0.000000001"), Ok(1));
+        // Trailing zeros past the ninth place do not add precision.
+        assert_eq!(sui_to_mist("1.0000000000"), Ok(1_000_000_000));
+
+        assert!(sui_to_mist("0.0000000001").is_err());
+        assert!(sui_to_mist("1.0000000001").is_err());
+    }
+
+    #[test]
+    fn sui_to_mist_rejects_amounts_beyond_u64() {
+        assert_eq!(sui_to_mist("18446744073.709551615"), Ok(u64::MAX));
+        assert!(sui_to_mist("18446744073.709551616").is_err());
+        assert!(sui_to_mist("18446744074").is_err());
+        assert!(sui_to_mist("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn sui_to_mist_rejects_non_decimal_input() {
+        for amount in ["", " ", ".", "-1", "+1", "1e9", "1.2.3", "1,5", "one"] {
+            assert!(sui_to_mist(amount).is_err(), "accepted `{}`", amount);
+        }
+    }
+
+    #[test]
+    fn parse_mist_takes_whole_numbers() {
+        assert_eq!(parse_mist("0"), Ok(0));
+        assert_eq!(parse_mist(" 1500000000 "), Ok(1_500_000_000));
+        assert_eq!(parse_mist("18446744073709551615"), Ok(u64::MAX));
+
+        for amount in ["", "-1", "1.5", "1e9", "18446744073709551616"] {
+            assert!(parse_mist(amount).is_err(), "accepted `{}`", amount);
+        }
+    }
+
+    #[test]
+    fn mist_to_sui_drops_trailing_zeros() {
+        assert_eq!(mist_to_sui(0), "0");
+        assert_eq!(mist_to_sui(1), "0.000000001");
+        assert_eq!(mist_to_sui(1_500_000_000), "1.5");
+        assert_eq!(mist_to_sui(2_000_000_000), "2");
+        assert_eq!(mist_to_sui(u64::MAX), "18446744073.709551615");
+    }
+}
@@ -0,0 +1,388 @@
+use crate::config::Config;
+
+pub fn non_empty(name: &str, value: &str) -> Result<(), rmcp::Error> {
+    if value.trim().is_empty() {
+        return Err(rmcp::Error::invalid_params(
+            format!("`{}` must not be empty", name),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+pub fn gas_budget(gas_budget: Option<u64>) -> Result<(), rmcp::Error> {
+    if gas_budget == Some(0) {
+        return Err(rmcp::Error::invalid_params(
+            "`gas_budget` must be greater than 0 (MIST)",
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a test name filter for `sui move test`, which would parse a leading `-` as an option.
+pub fn test_filter(value: &str) -> Result<(), rmcp::Error> {
+    non_empty("filter", value)?;
+    if value.trim_start().starts_with('-') {
+        return Err(rmcp::Error::invalid_params(
+            format!("`filter` must be a test name, got `{}`", value),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks for a `0x`-prefixed hex object/package ID of at most 32 bytes.
+pub fn object_id(name: &str, value: &str) -> Result<(), rmcp::Error> {
+    let valid = value.strip_prefix("0x").is_some_and(|hex| {
+        !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if !valid {
+        return Err(rmcp::Error::invalid_params(
+            format!(
+                "`{}` must be a 0x-prefixed hex object ID, got `{}`",
+                name, value
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks for a base58 transaction digest.
+pub fn digest(value: &str) -> Result<(), rmcp::Error> {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let valid = (32..=44).contains(&value.len()) && value.chars().all(|c| BASE58.contains(c));
+    if !valid {
+        return Err(rmcp::Error::invalid_params(
+            format!(
+                "`digest` must be a base58 transaction digest, got `{}`",
+                value
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+pub fn key_scheme(value: &str) -> Result<(), rmcp::Error> {
+    if !matches!(value, "ed25519" | "secp256k1" | "secp256r1") {
+        return Err(rmcp::Error::invalid_params(
+            format!(
+                "`key_scheme` must be `ed25519`, `secp256k1` or `secp256r1`, got `{}`",
+                value
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks for a Move identifier, such as a module or function name.
+pub fn identifier(name: &str, value: &str) -> Result<(), rmcp::Error> {
+    let mut chars = value.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(rmcp::Error::invalid_params(
+            format!("`{}` must be a Move identifier, got `{}`", name, value),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Whether a user-supplied relative path cannot escape the directory it is joined to.
+pub fn is_relative_within(path: &std::path::Path) -> bool {
+    !path.is_absolute()
+        && !path
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+}
+
+/// Resolves a file path relative to the project folder, rejecting paths outside of it or of
+/// the allowed directories.
+pub fn project_file(config: &Config, file: &str) -> Result<std::path::PathBuf, rmcp::Error> {
+    non_empty("file", file)?;
+    let project_folder = &config.project_folder;
+    let outside = || {
+        rmcp::Error::invalid_params(
+            format!("`{}` must be a file within the project folder", file),
+            None,
+        )
+    };
+    if !is_relative_within(std::path::Path::new(file)) {
+        return Err(outside());
+    }
+    let root = std::fs::canonicalize(project_folder).map_err(|e| {
+        rmcp::Error::internal_error(
+            format!(
+                "Failed to resolve project folder `{}`: {}",
+                project_folder, e
+            ),
+            None,
+        )
+    })?;
+    let path = std::fs::canonicalize(root.join(file)).map_err(|e| {
+        rmcp::Error::invalid_params(format!("Failed to resolve `{}`: {}", file, e), None)
+    })?;
+    if !path.starts_with(&root) || !path.is_file() {
+        return Err(outside());
+    }
+    allowed_path(config, &path)
+}
+
+/// Resolves a package directory relative to the project folder, rejecting directories outside
+/// of it or of the allowed directories, and directories without a `Move.toml`.
+pub fn package_dir(config: &Config, package: &str) -> Result<std::path::PathBuf, rmcp::Error> {
+    non_empty("package", package)?;
+    let project_folder = &config.project_folder;
+    let invalid = || {
+        rmcp::Error::invalid_params(
+            format!(
+                "`{}` must be a package directory within the project folder",
+                package
+            ),
+            None,
+        )
+    };
+    if !is_relative_within(std::path::Path::new(package)) {
+        return Err(invalid());
+    }
+    let root = std::fs::canonicalize(project_folder).map_err(|e| {
+        rmcp::Error::internal_error(
+            format!(
+                "Failed to resolve project folder `{}`: {}",
+                project_folder, e
+            ),
+            None,
+        )
+    })?;
+    let path = std::fs::canonicalize(root.join(package)).map_err(|e| {
+        rmcp::Error::invalid_params(format!("Failed to resolve `{}`: {}", package, e), None)
+    })?;
+    if !path.starts_with(&root) || !path.join("Move.toml").is_file() {
+        return Err(invalid());
+    }
+    allowed_path(config, &path)
+}
+
+/// Resolves `path` (following symlinks) and checks that it lies within one of the configured
+/// allowed directories. A path that does not exist yet is resolved through its parent, so it
+/// can be checked before being created.
+pub fn allowed_path(
+    config: &Config,
+    path: &std::path::Path,
+) -> Result<std::path::PathBuf, rmcp::Error> {
+    let resolved = std::fs::canonicalize(path).or_else(|e| {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(e);
+        };
+        std::fs::canonicalize(parent).map(|parent| parent.join(name))
+    });
+    match resolved {
+        Ok(resolved)
+            if config
+                .allowed_dirs
+                .iter()
+                .any(|dir| resolved.starts_with(dir)) =>
+        {
+            Ok(resolved)
+        }
+        Ok(_) => {
+            tracing::warn!(path = %path.display(), "rejected path outside allowed directories");
+            Err(rmcp::Error::invalid_params(
+                format!("`{}` is outside the allowed directories", path.display()),
+                None,
+            ))
+        }
+        Err(e) => Err(rmcp::Error::invalid_params(
+            format!("Failed to resolve `{}`: {}", path.display(), e),
+            None,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_empty_rejects_blank_values() {
+        assert!(non_empty("module", "vault").is_ok());
+        assert!(non_empty("module", "").is_err());
+        assert!(non_empty("module", " \t\n").is_err());
+    }
+
+    #[test]
+    fn gas_budget_must_be_positive() {
+        assert!(gas_budget(None).is_ok());
+        assert!(gas_budget(Some(1)).is_ok());
+        assert!(gas_budget(Some(u64::MAX)).is_ok());
+        assert!(gas_budget(Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_filter_cannot_be_an_option() {
+        assert!(test_filter("vault_tests::test_withdraw").is_ok());
+        assert!(test_filter("withdraw").is_ok());
+
+        assert!(test_filter("").is_err());
+        assert!(test_filter("--install-dir=/tmp/elsewhere").is_err());
+        assert!(test_filter("--path=/other/pkg").is_err());
+        assert!(test_filter(" -p").is_err());
+    }
+
+    #[test]
+    fn object_id_is_prefixed_hex_of_at_most_32_bytes() {
+        assert!(object_id("package_id", "0x2").is_ok());
+        assert!(object_id("package_id", "0xDeadBeef").is_ok());
+        assert!(object_id("package_id", &format!("0x{}", "a".repeat(64))).is_ok());
+
+        assert!(object_id("package_id", "").is_err());
+        assert!(object_id("package_id", "0x").is_err());
+        assert!(object_id("package_id", "deadbeef").is_err());
+        assert!(object_id("package_id", "0xg1").is_err());
+        assert!(object_id("package_id", &format!("0x{}", "a".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn digest_is_base58_of_digest_length() {
+        assert!(digest("GSMnyYPRfTkVd1Vi6xGQHeWbSEZHhQfYknbHDxNvHtuH").is_ok());
+        assert!(digest(&"1".repeat(32)).is_ok());
+
+        assert!(digest("").is_err());
+        assert!(digest(&"1".repeat(31)).is_err());
+        assert!(digest(&"1".repeat(45)).is_err());
+        // `0`, `O`, `I` and `l` are not in the base58 alphabet.
+        assert!(digest(&format!("0{}", "1".repeat(31))).is_err());
+        assert!(digest(&format!("l{}", "1".repeat(31))).is_err());
+    }
+
+    #[test]
+    fn key_scheme_is_one_of_the_supported_schemes() {
+        assert!(key_scheme("ed25519").is_ok());
+        assert!(key_scheme("secp256k1").is_ok());
+        assert!(key_scheme("secp256r1").is_ok());
+
+        assert!(key_scheme("").is_err());
+        assert!(key_scheme("ED25519").is_err());
+        assert!(key_scheme("bls12381").is_err());
+    }
+
+    #[test]
+    fn identifier_follows_move_rules() {
+        assert!(identifier("module", "vault").is_ok());
+        assert!(identifier("module", "_private").is_ok());
+        assert!(identifier("module", "Coin2_v1").is_ok());
+
+        assert!(identifier("module", "").is_err());
+        assert!(identifier("module", "2coin").is_err());
+        assert!(identifier("module", "my-module").is_err());
+        assert!(identifier("module", "vault::new").is_err());
+        assert!(identifier("module", "café").is_err());
+    }
+
+    /// A project with `sources/vault.move`, next to an `outside/secret.move` it must not reach.
+    /// On unix, `project/link.move` and `project/escape` are symlinks out of the project.
+    fn fixture() -> (std::path::PathBuf, Config) {
+        let root = std::env::temp_dir().join(format!("validate-{}", uuid::Uuid::new_v4()));
+        let project = root.join("project");
+        std::fs::create_dir_all(project.join("sources")).unwrap();
+        std::fs::create_dir_all(root.join("outside")).unwrap();
+        std::fs::write(project.join("sources/vault.move"), "module vault::vault;").unwrap();
+        std::fs::write(root.join("outside/secret.move"), "module secret::secret;").unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("outside/secret.move"), project.join("link.move"))
+                .unwrap();
+            std::os::unix::fs::symlink(root.join("outside"), project.join("escape")).unwrap();
+        }
+        let config = Config::for_project(&project);
+        (root, config)
+    }
+
+    #[test]
+    fn relative_paths_cannot_climb_out() {
+        assert!(is_relative_within(std::path::Path::new(
+            "sources/vault.move"
+        )));
+        assert!(is_relative_within(std::path::Path::new("./sources")));
+
+        assert!(!is_relative_within(std::path::Path::new("../outside")));
+        assert!(!is_relative_within(std::path::Path::new(
+            "sources/../../outside/secret.move"
+        )));
+        assert!(!is_relative_within(std::path::Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn project_file_rejects_traversal() {
+        let (root, config) = fixture();
+        let vault = std::fs::canonicalize(root.join("project/sources/vault.move")).unwrap();
+
+        assert_eq!(
+            project_file(&config, "sources/vault.move").ok(),
+            Some(vault)
+        );
+        assert!(project_file(&config, "").is_err());
+        assert!(project_file(&config, "sources").is_err());
+        assert!(project_file(&config, "../outside/secret.move").is_err());
+        assert!(project_file(&config, "sources/../../outside/secret.move").is_err());
+        let absolute = root.join("outside/secret.move");
+        assert!(project_file(&config, &absolute.to_string_lossy()).is_err());
+        #[cfg(unix)]
+        {
+            assert!(project_file(&config, "link.move").is_err());
+            assert!(project_file(&config, "escape/secret.move").is_err());
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn package_dir_needs_a_manifest_inside_the_project() {
+        let (root, config) = fixture();
+        let project = root.join("project");
+        std::fs::create_dir_all(project.join("packages/token")).unwrap();
+        std::fs::write(project.join("packages/token/Move.toml"), "[package]").unwrap();
+        std::fs::write(root.join("outside/Move.toml"), "[package]").unwrap();
+        let token = std::fs::canonicalize(project.join("packages/token")).unwrap();
+
+        assert_eq!(package_dir(&config, "packages/token").ok(), Some(token));
+        assert!(package_dir(&config, "").is_err());
+        assert!(package_dir(&config, "sources").is_err());
+        assert!(package_dir(&config, "packages/missing").is_err());
+        assert!(package_dir(&config, "../outside").is_err());
+        let absolute = root.join("outside");
+        assert!(package_dir(&config, &absolute.to_string_lossy()).is_err());
+        #[cfg(unix)]
+        assert!(package_dir(&config, "escape").is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn allowed_path_resolves_before_checking() {
+        let (root, config) = fixture();
+        let project = root.join("project");
+
+        assert!(allowed_path(&config, &project.join("sources/vault.move")).is_ok());
+        // Paths that do not exist yet are checked through their parent.
+        assert!(allowed_path(&config, &project.join("sources/new.move")).is_ok());
+
+        assert!(allowed_path(&config, &root.join("outside/secret.move")).is_err());
+        assert!(allowed_path(&config, &project.join("../outside/secret.move")).is_err());
+        assert!(allowed_path(&config, &project.join("../outside/new.move")).is_err());
+        assert!(allowed_path(&config, &project.join("missing/new.move")).is_err());
+        #[cfg(unix)]
+        {
+            assert!(allowed_path(&config, &project.join("link.move")).is_err());
+            assert!(allowed_path(&config, &project.join("escape/new.move")).is_err());
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+//! When the server runs as a stdio subprocess, stdout carries MCP framing, so logs must never
+//! be written there.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+#[test]
+fn logs_go_to_stderr_not_stdout() {
+    let package = std::env::temp_dir().join(format!("sui-dev-mcp-stdout-{}", std::process::id()));
+    std::fs::create_dir_all(&package).unwrap();
+    std::fs::write(
+        package.join("Move.toml"),
+        "[package]\nname = \"stdout_test\"\n",
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sui-dev-mcp"))
+        .env("PORT", "0")
+        .env("PROJECT_FOLDER", &package)
+        .env("MOVEFMT_CMD", "movefmt")
+        .env("RUST_LOG", "debug")
+        .env_remove("LOG_FILE")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Startup logs the resolved package root once logging is set up.
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    let logged = stderr
+        .lines()
+        .map_while(Result::ok)
+        .any(|line| line.contains("resolved package root"));
+    child.kill().unwrap();
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    child.wait().unwrap();
+    let _ = std::fs::remove_dir_all(&package);
+
+    assert!(logged);
+    assert_eq!(stdout, "");
+}